@@ -1,39 +1,137 @@
+use crate::http::HttpClient;
 use crate::links;
+use crate::resolver::{Arch, LinkResolver, ResolveError, ResolvedDownload};
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum Browser {
-    Brave,
     Chrome,
     Chromium,
     Edge,
-    Firefox,
-    Librewolf,
+    Firefox(FirefoxRelease),
     Opera,
     OperaGX,
     PaleMoon,
-    SeaMonkey,
     Tor,
     Vivaldi,
     Waterfox,
 }
 
 impl Browser {
-    pub fn get_link(&self) -> Option<String> {
+    // Maps the `builtin` name of a catalog entry to its compiled in resolver
+    pub fn from_name(name: &str) -> Option<Browser> {
+        match name {
+            "Chrome" => Some(Browser::Chrome),
+            "Chromium" => Some(Browser::Chromium),
+            "Edge" => Some(Browser::Edge),
+            "Firefox" => Some(Browser::Firefox(FirefoxRelease::default())),
+            "Opera" => Some(Browser::Opera),
+            "OperaGX" => Some(Browser::OperaGX),
+            "PaleMoon" => Some(Browser::PaleMoon),
+            "Tor" => Some(Browser::Tor),
+            "Vivaldi" => Some(Browser::Vivaldi),
+            "Waterfox" => Some(Browser::Waterfox),
+            _ => None,
+        }
+    }
+}
+
+impl LinkResolver for Browser {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        match self {
+            Browser::Chrome => links::get_chrome_link(),
+            Browser::Chromium => links::get_chromium_link(),
+            Browser::Edge => links::get_edge_link(),
+            Browser::Firefox(release) => links::get_firefox_link(release),
+            Browser::Opera => links::get_opera_link(),
+            Browser::OperaGX => links::get_operagx_link(),
+            Browser::PaleMoon => links::get_palemoon_link(),
+            Browser::Tor => links::get_tor_link(http),
+            Browser::Vivaldi => links::get_vivaldi_link(http),
+            Browser::Waterfox => links::get_waterfox_link(http),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirefoxChannel {
+    Release,
+    Beta,
+    Esr,
+    DevEdition,
+    Nightly,
+}
+
+impl FirefoxChannel {
+    pub fn parse(value: &str) -> Option<FirefoxChannel> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "release" | "stable" => Some(FirefoxChannel::Release),
+            "beta" => Some(FirefoxChannel::Beta),
+            "esr" => Some(FirefoxChannel::Esr),
+            "devedition" | "developer" => Some(FirefoxChannel::DevEdition),
+            "nightly" => Some(FirefoxChannel::Nightly),
+            _ => None,
+        }
+    }
+
+    // Product name prefix on download.mozilla.org
+    fn product(&self) -> &'static str {
         match self {
-            Browser::Brave => Some(links::get_brave_link()),
-            Browser::Chrome => Some(links::get_chrome_link()),
-            Browser::Chromium => Some(links::get_chromium_link()),
-            Browser::Edge => Some(links::get_edge_link()),
-            Browser::Firefox => Some(links::get_firefox_link()),
-            Browser::Librewolf => Some(links::get_librewolf_link()),
-            Browser::Opera => Some(links::get_opera_link()),
-            Browser::OperaGX => Some(links::get_operagx_link()),
-            Browser::PaleMoon => Some(links::get_palemoon_link()),
-            Browser::SeaMonkey => Some(links::get_seamonkey_link()),
-            Browser::Tor => Some(links::get_tor_link()),
-            Browser::Vivaldi => Some(links::get_vivaldi_link()),
-            Browser::Waterfox => Some(links::get_waterfox_link()),
+            FirefoxChannel::Release => "firefox",
+            FirefoxChannel::Beta => "firefox-beta",
+            FirefoxChannel::Esr => "firefox-esr",
+            FirefoxChannel::DevEdition => "firefox-devedition",
+            FirefoxChannel::Nightly => "firefox-nightly",
+        }
+    }
+}
+
+// Which build Mozilla's download service hands out, set with channel, arch, variant and locale in a [browser.Firefox] table
+#[derive(Debug, Clone)]
+pub struct FirefoxRelease {
+    pub channel: FirefoxChannel,
+    // None gets the stub installer, which picks the architecture when it runs (ESR has no stub and defaults to x64)
+    pub arch: Option<Arch>,
+    pub msi: bool,
+    pub locale: String,
+}
+
+impl Default for FirefoxRelease {
+    fn default() -> FirefoxRelease {
+        FirefoxRelease {
+            channel: FirefoxChannel::Release,
+            arch: None,
+            msi: false,
+            locale: String::from("en-US"),
+        }
+    }
+}
+
+impl FirefoxRelease {
+    // e.g. https://download.mozilla.org/?product=firefox-esr-msi-latest-ssl&os=win64&lang=de
+    pub fn url(&self) -> String {
+        let product = self.channel.product();
+        if self.arch.is_none() && !self.msi && self.channel != FirefoxChannel::Esr {
+            return format!(
+                "https://download.mozilla.org/?product={product}-stub&os=win&lang={}",
+                self.locale
+            );
         }
+        let os = match self.arch.unwrap_or(Arch::X64) {
+            Arch::X86 => "win",
+            Arch::X64 => "win64",
+            Arch::Arm64 => "win64-aarch64",
+        };
+        let msi = if self.msi { "-msi" } else { "" };
+        // Nightly publishes other locales as separate l10n builds
+        let l10n = if self.channel == FirefoxChannel::Nightly && self.locale != "en-US" {
+            "-l10n"
+        } else {
+            ""
+        };
+        format!(
+            "https://download.mozilla.org/?product={product}{msi}-latest{l10n}-ssl&os={os}&lang={}",
+            self.locale
+        )
     }
 }
@@ -0,0 +1,570 @@
+use crate::checksum::normalize_sha256;
+use crate::feed::{FeedResolver, VersionSource};
+use crate::filename::sanitize;
+use crate::github::{AssetPattern, GitHubResolver};
+use crate::index::DirectoryIndexResolver;
+use crate::resolver::Arch;
+use crate::sniff::FileType;
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::{env, fs};
+
+// The catalog shipped with wsb, additional entries can be layered over it at runtime (see Catalog::load)
+const EMBEDDED_CATALOG: &str = include_str!("../catalog.toml");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Browser,
+    Gaming,
+    Programming,
+    System,
+}
+
+impl Category {
+    pub const ALL: [Category; 4] = [
+        Category::Browser,
+        Category::Gaming,
+        Category::Programming,
+        Category::System,
+    ];
+
+    // Name of the config.ini section holding this category's keys
+    pub fn section_name(&self) -> &'static str {
+        match self {
+            Category::Browser => "browser",
+            Category::Gaming => "gaming",
+            Category::Programming => "programming",
+            Category::System => "system",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.section_name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolverKind {
+    Builtin,
+    Direct,
+    // github:<owner>/<repo>
+    GitHub(String),
+    // du_cli:<app>, handed to the DU_CLI.exe helper under that app name
+    Helper(String),
+    Feed,
+    Index,
+    Python,
+    Node,
+}
+
+impl fmt::Display for ResolverKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolverKind::Builtin => f.write_str("builtin"),
+            ResolverKind::Direct => f.write_str("direct"),
+            ResolverKind::GitHub(repo) => write!(f, "github:{repo}"),
+            ResolverKind::Helper(app) => write!(f, "du_cli:{app}"),
+            ResolverKind::Feed => f.write_str("feed"),
+            ResolverKind::Index => f.write_str("index"),
+            ResolverKind::Python => f.write_str("python"),
+            ResolverKind::Node => f.write_str("node"),
+        }
+    }
+}
+
+impl ResolverKind {
+    fn parse(value: &str) -> Option<ResolverKind> {
+        if let Some(repo) = value.strip_prefix("github:") {
+            return Some(ResolverKind::GitHub(repo.trim().to_string()));
+        }
+        if let Some(app) = value.strip_prefix("du_cli:") {
+            return Some(ResolverKind::Helper(app.trim().to_string()));
+        }
+        match value {
+            "builtin" => Some(ResolverKind::Builtin),
+            "direct" => Some(ResolverKind::Direct),
+            "feed" => Some(ResolverKind::Feed),
+            "index" => Some(ResolverKind::Index),
+            "python" => Some(ResolverKind::Python),
+            "node" => Some(ResolverKind::Node),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogEntry {
+    pub key: String,
+    pub category: Category,
+    pub name: String,
+    // Other names the app is known by, for search and command line lookups
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    // Written next to the app's key by `wsb init`
+    pub homepage: Option<String>,
+    pub notes: Option<String>,
+    // winget package identifier, for `wsb init --from-installed`
+    winget: Option<String>,
+    resolver: String,
+    builtin: Option<String>,
+    pub url: Option<String>,
+    // Only meant for urls that never change (a pinned version), a scraped "latest" link would stop matching on the next release
+    pub sha256: Option<String>,
+    // Name the download is saved under, for vendors whose headers and urls don't carry a usable one
+    pub filename: Option<String>,
+    // exe, msi, zip or iso, checked against the downloaded file's signature
+    pub file_type: Option<FileType>,
+    // GitHub resolver settings, asset is a glob and asset_regex a regular expression matched against the asset names
+    asset: Option<String>,
+    asset_regex: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+    arch: Option<String>,
+    // Feed and index resolver settings, the feed or listing itself is `url`
+    version_from: Option<String>,
+    version_pattern: Option<String>,
+    url_template: Option<String>,
+    // Index resolver only, the file picked inside the matched directory
+    file_pattern: Option<String>,
+    // Index resolver only, checksum file published with the download, {url} is replaced with the download url
+    sha256_url: Option<String>,
+}
+
+impl CatalogEntry {
+    pub fn resolver_kind(&self) -> Option<ResolverKind> {
+        ResolverKind::parse(&self.resolver)
+    }
+
+    // Name of the compiled in resolver, entries only need to set `builtin` when it differs from their key
+    pub fn builtin_name(&self) -> &str {
+        self.builtin.as_deref().unwrap_or(&self.key)
+    }
+
+    pub fn github_resolver(&self) -> Result<GitHubResolver, String> {
+        let Some(ResolverKind::GitHub(repo)) = self.resolver_kind() else {
+            return Err(format!("{}: not a github resolver", self.key));
+        };
+        let valid_repo = repo.split_once('/').is_some_and(|(owner, name)| {
+            !owner.is_empty() && !name.is_empty() && !name.contains('/')
+        });
+        if !valid_repo {
+            return Err(format!(
+                "{}: expected github:<owner>/<repo>, found \"{}\"",
+                self.key, self.resolver
+            ));
+        }
+
+        let asset = match (&self.asset, &self.asset_regex) {
+            (Some(glob), None) => AssetPattern::Glob(glob.clone()),
+            (None, Some(regex)) => {
+                Regex::new(regex).map_err(|e| format!("{}: invalid asset_regex: {e}", self.key))?;
+                AssetPattern::Regex(regex.clone())
+            }
+            _ => {
+                return Err(format!(
+                    "{}: the github resolver requires either asset or asset_regex",
+                    self.key
+                ));
+            }
+        };
+
+        Ok(GitHubResolver {
+            repo,
+            asset,
+            prerelease: self.prerelease,
+            arch: self.arch()?,
+        })
+    }
+
+    pub fn arch(&self) -> Result<Option<Arch>, String> {
+        match &self.arch {
+            Some(arch) => Ok(Some(Arch::parse(arch).ok_or_else(|| {
+                format!(
+                    "{}: unknown arch \"{arch}\", expected x86, x64 or arm64",
+                    self.key
+                )
+            })?)),
+            None => Ok(None),
+        }
+    }
+
+    // Downloads handed to the DU_CLI.exe helper instead of being resolved by wsb
+    pub fn needs_helper(&self) -> bool {
+        matches!(self.resolver_kind(), Some(ResolverKind::Helper(_)))
+    }
+
+    // Whether an installed winget package is this app, Python.Python also matches Python.Python.3.12
+    pub fn is_winget_package(&self, id: &str) -> bool {
+        self.winget.as_deref().is_some_and(|winget| {
+            id.eq_ignore_ascii_case(winget)
+                || id
+                    .get(..winget.len() + 1)
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{winget}.")))
+        })
+    }
+
+    // What a versioned entry's config.ini value looks like, None for true/false entries
+    pub fn value_hint(&self) -> Option<&'static str> {
+        match self.resolver_kind()? {
+            ResolverKind::Python => {
+                Some("latest, 3.12, 3.13.* or 3.12.4, optionally followed by x64 or arm64")
+            }
+            ResolverKind::Node => Some(
+                "lts, current, a codename (jod) or a range (^20.11), optionally followed by msi, zip or exe and x86, x64 or arm64",
+            ),
+            _ => None,
+        }
+    }
+
+    // Keys a [category.Key] table can set for this entry (see src/options.rs), the resolver has to be able to act on them.
+    // Helper downloads are named by DU_CLI.exe, so they don't take an output_name.
+    pub fn supported_options(&self) -> &'static [&'static str] {
+        match self.resolver_kind() {
+            Some(ResolverKind::Python) => &["enabled", "version", "arch", "output_name"],
+            Some(ResolverKind::Node) => &[
+                "enabled",
+                "version",
+                "channel",
+                "arch",
+                "variant",
+                "output_name",
+            ],
+            Some(ResolverKind::GitHub(_)) => &["enabled", "channel", "arch", "output_name"],
+            Some(ResolverKind::Builtin) if self.builtin_name() == "Firefox" => &[
+                "enabled",
+                "channel",
+                "arch",
+                "variant",
+                "locale",
+                "output_name",
+            ],
+            Some(ResolverKind::Helper(_)) => &["enabled"],
+            _ => &["enabled", "output_name"],
+        }
+    }
+
+    // Entries whose config.ini value picks the version(s) to download instead of being true or false
+    pub fn takes_version(&self) -> bool {
+        matches!(
+            self.resolver_kind(),
+            Some(ResolverKind::Python | ResolverKind::Node)
+        )
+    }
+
+    pub fn feed_resolver(&self) -> Result<FeedResolver, String> {
+        let (Some(url), Some(url_template)) = (&self.url, &self.url_template) else {
+            return Err(format!(
+                "{}: the feed resolver requires url and url_template",
+                self.key
+            ));
+        };
+        let version_from = match self.version_from.as_deref() {
+            Some(version_from) => VersionSource::parse(version_from).ok_or_else(|| {
+                format!(
+                    "{}: unknown version_from \"{version_from}\", expected title, id or link",
+                    self.key
+                )
+            })?,
+            None => VersionSource::Title,
+        };
+        if let Some(pattern) = &self.version_pattern {
+            Regex::new(pattern)
+                .map_err(|e| format!("{}: invalid version_pattern: {e}", self.key))?;
+        }
+        Ok(FeedResolver {
+            url: url.clone(),
+            version_from,
+            version_pattern: self.version_pattern.clone(),
+            url_template: url_template.clone(),
+        })
+    }
+
+    pub fn index_resolver(&self) -> Result<DirectoryIndexResolver, String> {
+        let (Some(url), Some(version_pattern)) = (&self.url, &self.version_pattern) else {
+            return Err(format!(
+                "{}: the index resolver requires url and version_pattern",
+                self.key
+            ));
+        };
+        if self.file_pattern.is_some() && self.url_template.is_some() {
+            return Err(format!(
+                "{}: file_pattern and url_template can't be combined",
+                self.key
+            ));
+        }
+        Regex::new(version_pattern)
+            .map_err(|e| format!("{}: invalid version_pattern: {e}", self.key))?;
+        if let Some(file_pattern) = &self.file_pattern {
+            Regex::new(&file_pattern.replace("{version}", "0"))
+                .map_err(|e| format!("{}: invalid file_pattern: {e}", self.key))?;
+        }
+        Ok(DirectoryIndexResolver {
+            url: url.clone(),
+            version_pattern: version_pattern.clone(),
+            file_pattern: self.file_pattern.clone(),
+            url_template: self.url_template.clone(),
+            prerelease: self.prerelease,
+            sha256_url: self.sha256_url.clone(),
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self.resolver_kind() {
+            None => {
+                return Err(format!(
+                    "{}: unknown resolver \"{}\"",
+                    self.key, self.resolver
+                ));
+            }
+            Some(ResolverKind::Direct) if self.url.is_none() => {
+                return Err(format!("{}: the direct resolver requires a url", self.key));
+            }
+            Some(ResolverKind::Helper(app)) if app.is_empty() => {
+                return Err(format!("{}: expected du_cli:<app>", self.key));
+            }
+            Some(ResolverKind::GitHub(_)) => {
+                self.github_resolver()?;
+            }
+            Some(ResolverKind::Feed) => {
+                self.feed_resolver()?;
+            }
+            Some(ResolverKind::Index) => {
+                self.index_resolver()?;
+            }
+            Some(ResolverKind::Node) => {
+                self.arch()?;
+            }
+            Some(ResolverKind::Python) => {
+                if self.arch()? == Some(Arch::X86) {
+                    return Err(format!(
+                        "{}: the python resolver supports x64 and arm64",
+                        self.key
+                    ));
+                }
+            }
+            Some(_) => {}
+        }
+        if self
+            .sha256
+            .as_deref()
+            .is_some_and(|sha256| normalize_sha256(sha256).is_none())
+        {
+            return Err(format!(
+                "{}: sha256 must be 64 hexadecimal characters",
+                self.key
+            ));
+        }
+        if self
+            .filename
+            .as_deref()
+            .is_some_and(|filename| sanitize(filename).as_deref() != Some(filename))
+        {
+            return Err(format!(
+                "{}: filename is not a valid Windows filename",
+                self.key
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Catalog {
+    #[serde(rename = "app", default)]
+    entries: Vec<CatalogEntry>,
+}
+
+impl Catalog {
+    pub fn parse(contents: &str) -> Result<Catalog, Box<dyn Error>> {
+        let catalog: Catalog = toml::from_str(contents)?;
+        for (i, entry) in catalog.entries.iter().enumerate() {
+            entry.validate()?;
+            if catalog.entries[..i].iter().any(|e| e.key == entry.key) {
+                return Err(format!("{}: duplicate key", entry.key).into());
+            }
+        }
+        Ok(catalog)
+    }
+
+    pub fn embedded() -> Catalog {
+        Catalog::parse(EMBEDDED_CATALOG).expect("The embedded catalog.toml is invalid")
+    }
+
+    // Loads the embedded catalog and merges the runtime catalog over it when one is present.
+    // WSB_CATALOG takes priority, otherwise catalog.toml next to the executable is used.
+    pub fn load() -> Result<Catalog, Box<dyn Error>> {
+        let mut catalog = Catalog::embedded();
+        if let Some(path) = runtime_catalog_path() {
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| format!("Unable to read catalog {}: {e}", path.display()))?;
+            let overlay = Catalog::parse(&contents)
+                .map_err(|e| format!("Invalid catalog {}: {e}", path.display()))?;
+            catalog.merge(overlay);
+        }
+        Ok(catalog)
+    }
+
+    // Entries sharing a key with an existing entry replace it in place, new keys are appended
+    pub fn merge(&mut self, overlay: Catalog) {
+        for entry in overlay.entries {
+            match self.entries.iter_mut().find(|e| e.key == entry.key) {
+                Some(existing) => *existing = entry,
+                None => self.entries.push(entry),
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&CatalogEntry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    // Looks an app up by its config key, display name or alias as typed on the command line,
+    // case and punctuation are ignored so firefox finds Firefox and 7zip finds 7-Zip.
+    // An alias shared by several apps (code) matches none of them.
+    pub fn find(&self, name: &str) -> Option<&CatalogEntry> {
+        if let Some(entry) = self.get(name) {
+            return Some(entry);
+        }
+        let wanted = normalize_name(name);
+        if let Some(entry) = self.entries.iter().find(|entry| {
+            normalize_name(&entry.key) == wanted || normalize_name(&entry.name) == wanted
+        }) {
+            return Some(entry);
+        }
+        let mut aliased = self.entries.iter().filter(|entry| {
+            entry
+                .aliases
+                .iter()
+                .any(|alias| normalize_name(alias) == wanted)
+        });
+        match (aliased.next(), aliased.next()) {
+            (Some(entry), None) => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &CatalogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn in_category(&self, category: Category) -> impl Iterator<Item = &CatalogEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.category == category)
+    }
+}
+
+fn runtime_catalog_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("WSB_CATALOG") {
+        return Some(PathBuf::from(path));
+    }
+    let path = env::current_exe().ok()?.parent()?.join("catalog.toml");
+    if path.is_file() { Some(path) } else { None }
+}
+
+// Lowercase letters and digits only, "Notepad++" and "notepad" compare equal
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, extra: &str) -> String {
+        format!("[[app]]\nkey = \"{key}\"\ncategory = \"system\"\nname = \"{key}\"\n{extra}\n")
+    }
+
+    fn error(contents: &str) -> String {
+        Catalog::parse(contents).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn embedded_catalog_is_valid() {
+        let catalog = Catalog::embedded();
+        assert!(catalog.entries().count() > 50);
+        for category in Category::ALL {
+            assert!(catalog.in_category(category).next().is_some());
+        }
+        assert!(catalog.get("Firefox").is_some());
+    }
+
+    #[test]
+    fn overlays_replace_by_key_and_append() {
+        let mut catalog = Catalog::embedded();
+        let count = catalog.entries().count();
+        let position = catalog.entries().position(|e| e.key == "Firefox");
+        let overlay = Catalog::parse(&format!(
+            "{}{}",
+            entry(
+                "Firefox",
+                "resolver = \"direct\"\nurl = \"https://example.com/firefox.exe\""
+            ),
+            entry(
+                "InHouseTool",
+                "resolver = \"direct\"\nurl = \"https://example.com/tool.msi\""
+            ),
+        ))
+        .unwrap();
+        catalog.merge(overlay);
+
+        assert_eq!(catalog.entries().count(), count + 1);
+        assert_eq!(catalog.entries().position(|e| e.key == "Firefox"), position);
+        let firefox = catalog.get("Firefox").unwrap();
+        assert_eq!(firefox.category, Category::System);
+        assert_eq!(
+            firefox.url.as_deref(),
+            Some("https://example.com/firefox.exe")
+        );
+        assert_eq!(catalog.entries().last().unwrap().key, "InHouseTool");
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        assert_eq!(
+            error(&entry("Tool", "resolver = \"ftp\"")),
+            "Tool: unknown resolver \"ftp\""
+        );
+        let direct = entry(
+            "Tool",
+            "resolver = \"direct\"\nurl = \"https://example.com/tool.exe\"",
+        );
+        assert_eq!(error(&format!("{direct}{direct}")), "Tool: duplicate key");
+        assert_eq!(
+            error(&entry("Tool", "resolver = \"github:owner/tool\"")),
+            "Tool: the github resolver requires either asset or asset_regex"
+        );
+        assert!(
+            Catalog::parse(&entry(
+                "Tool",
+                "resolver = \"github:owner/tool\"\nasset = \"tool-*.exe\""
+            ))
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn find_ignores_case_and_punctuation() {
+        let catalog = Catalog::embedded();
+        let found = |name| catalog.find(name).map(|entry| entry.key.as_str());
+        assert_eq!(found("NotepadPlusPlus"), Some("NotepadPlusPlus"));
+        assert_eq!(found("notepadplusplus"), Some("NotepadPlusPlus"));
+        assert_eq!(found("7-zip"), Some("7Zip"));
+        assert_eq!(found("Visual Studio Code"), Some("VisualStudioCode"));
+        assert_eq!(found("VS-Code"), Some("VisualStudioCode"));
+        assert_eq!(found("Notepad++"), Some("NotepadPlusPlus"));
+        assert_eq!(found("NPP"), Some("NotepadPlusPlus"));
+        assert_eq!(found("code"), None);
+        assert_eq!(found("firefx"), None);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::http::HttpClient;
+use crate::resolver::{ResolveError, ResolvedDownload};
+use serde_json::Value;
+
+// Normalizes a published SHA-256 to lowercase hex, GitHub prefixes its asset digests with "sha256:"
+pub fn normalize_sha256(value: &str) -> Option<String> {
+    let value = value.trim();
+    let hex = value.strip_prefix("sha256:").unwrap_or(value);
+    if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(hex.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+// Finds the digest of filename in a checksum file. Understands the coreutils format ("<hash>  <file>" or "<hash> *<file>"),
+// the BSD format used by Wireshark's SIGNATURES files ("SHA256(<file>)=<hash>") and .sha256 files holding only the hash.
+pub fn find_sha256(contents: &str, filename: &str) -> Option<String> {
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("SHA256(") {
+            if let Some((name, hash)) = rest.split_once(")=")
+                && name == filename
+            {
+                return normalize_sha256(hash);
+            }
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        if let (Some(hash), Some(name)) = (parts.next(), parts.next())
+            && name.trim_start_matches('*') == filename
+            && let Some(hash) = normalize_sha256(hash)
+        {
+            return Some(hash);
+        }
+    }
+
+    let mut tokens = contents.split_whitespace();
+    match (tokens.next(), tokens.next()) {
+        (Some(hash), None) => normalize_sha256(hash),
+        _ => None,
+    }
+}
+
+// Reads the digest of filename from the checksum file at checksum_url
+pub fn fetch_sha256_from(
+    http: &HttpClient,
+    checksum_url: &str,
+    filename: &str,
+) -> Result<Option<String>, ResolveError> {
+    let (_, contents) = http.get_text(checksum_url)?;
+    Ok(find_sha256(&contents, filename))
+}
+
+// Finds the SHA-256 of an asset in a GitHub release's "assets" array, preferring the digest GitHub computes for every asset
+// and falling back to a checksum asset (<asset>.sha256, SHA256SUMS, sha256sums.txt) published with the release.
+pub fn github_asset_sha256(
+    http: &HttpClient,
+    assets: &[Value],
+    asset_name: &str,
+) -> Result<Option<String>, ResolveError> {
+    let asset_digest = assets
+        .iter()
+        .find(|asset| asset["name"].as_str() == Some(asset_name))
+        .and_then(|asset| asset["digest"].as_str())
+        .and_then(normalize_sha256);
+    if asset_digest.is_some() {
+        return Ok(asset_digest);
+    }
+
+    let checksum_asset = assets.iter().find_map(|asset| {
+        let name = asset["name"].as_str()?.to_ascii_lowercase();
+        let is_checksum = name == format!("{}.sha256", asset_name.to_ascii_lowercase())
+            || (name.contains("sha256") && name.contains("sum"));
+        if is_checksum {
+            asset["browser_download_url"].as_str()
+        } else {
+            None
+        }
+    });
+    match checksum_asset {
+        Some(url) => fetch_sha256_from(http, url, asset_name),
+        None => Ok(None),
+    }
+}
+
+pub fn wireshark_sha256(
+    http: &HttpClient,
+    version: &str,
+    filename: &str,
+) -> Result<Option<String>, ResolveError> {
+    fetch_sha256_from(
+        http,
+        &format!("https://www.wireshark.org/download/SIGNATURES-{version}.txt"),
+        filename,
+    )
+}
+
+// Nmap publishes the output of gpg --print-mds for every release in dist/sigs/, e.g. nmap-7.95-setup.exe.digest.txt
+pub fn nmap_sha256(http: &HttpClient, filename: &str) -> Result<Option<String>, ResolveError> {
+    let (_, contents) =
+        http.get_text(&format!("https://nmap.org/dist/sigs/{filename}.digest.txt"))?;
+    Ok(find_print_mds_sha256(&contents))
+}
+
+// gpg --print-mds writes "<file>: SHA256 = 3B5D9A77 0E8BB4D4 ..." with the digest split into groups and wrapped over
+// several indented lines
+pub fn find_print_mds_sha256(contents: &str) -> Option<String> {
+    let mut lines = contents.lines();
+    let first = lines.find_map(|line| line.split_once("SHA256 =").map(|(_, hash)| hash))?;
+    let continuation =
+        lines.take_while(|line| line.starts_with(char::is_whitespace) && !line.contains('='));
+    let hash: String = std::iter::once(first)
+        .chain(continuation)
+        .flat_map(str::split_whitespace)
+        .collect();
+    normalize_sha256(&hash)
+}
+
+// PuTTY lists every file of a release in sha256sums at the release root, with paths like w64/putty-64bit-0.83-installer.msi
+pub fn putty_sha256(http: &HttpClient, url: &str) -> Result<Option<String>, ResolveError> {
+    let Some((root, path)) = url
+        .find("/latest/")
+        .map(|index| url.split_at(index + "/latest/".len()))
+    else {
+        return Ok(None);
+    };
+    fetch_sha256_from(http, &format!("{root}sha256sums"), path)
+}
+
+// Every VirtualBox release directory has a SHA256SUMS covering the installers and the extension pack
+pub fn virtualbox_sha256(http: &HttpClient, url: &str) -> Result<Option<String>, ResolveError> {
+    let Some((directory, filename)) = url.rsplit_once('/') else {
+        return Ok(None);
+    };
+    fetch_sha256_from(http, &format!("{directory}/SHA256SUMS"), filename)
+}
+
+pub fn tor_sha256(
+    http: &HttpClient,
+    version: &str,
+    filename: &str,
+) -> Result<Option<String>, ResolveError> {
+    fetch_sha256_from(
+        http,
+        &format!(
+            "https://www.torproject.org/dist/torbrowser/{version}/sha256sums-signed-build.txt"
+        ),
+        filename,
+    )
+}
+
+// Every Node.js release lists its files in SHASUMS256.txt, with paths relative to the release directory (win-x64/node.exe)
+pub fn nodejs_sha256(
+    http: &HttpClient,
+    version: &str,
+    path: &str,
+) -> Result<Option<String>, ResolveError> {
+    fetch_sha256_from(
+        http,
+        &format!("https://nodejs.org/dist/{version}/SHASUMS256.txt"),
+        path,
+    )
+}
+
+// Checksums are best effort: a vendor that is briefly unreachable or stops publishing a digest
+// shouldn't block the download, it is only left unverified.
+pub fn attach_sha256(
+    resolved: ResolvedDownload,
+    lookup: Result<Option<String>, ResolveError>,
+) -> ResolvedDownload {
+    match lookup {
+        Ok(Some(sha256)) => resolved.with_sha256(sha256),
+        Ok(None) => {
+            let note = format!(
+                "No published SHA-256 found for {}, continuing without verification...",
+                resolved.url
+            );
+            resolved.with_note(note)
+        }
+        Err(e) => {
+            let note = format!(
+                "Unable to fetch the SHA-256 for {} ({e}), continuing without verification...",
+                resolved.url
+            );
+            resolved.with_note(note)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256: &str = "c2d8d4f30d1f6e9a7b1a3e622f0fbe0a2f4a63e76b8e9d0fa6d6b81c0a1c55e3";
+
+    #[test]
+    fn print_mds_digest_spread_over_lines() {
+        let contents = "\
+nmap-7.95-setup.exe: MD5 = 0A 1B 2C 3D 4E 5F 60 71  82 93 A4 B5 C6 D7 E8 F9
+nmap-7.95-setup.exe: SHA256 = C2D8D4F3 0D1F6E9A 7B1A3E62 2F0FBE0A 2F4A63E7 6B8E9D0F
+                              A6D6B81C 0A1C55E3
+nmap-7.95-setup.exe: SHA384 = 00000000 00000000
+";
+        assert_eq!(find_print_mds_sha256(contents).as_deref(), Some(SHA256));
+    }
+
+    #[test]
+    fn sums_with_paths_and_binary_marker() {
+        let putty = format!("{SHA256}  w64/putty-64bit-0.83-installer.msi\n");
+        assert_eq!(
+            find_sha256(&putty, "w64/putty-64bit-0.83-installer.msi").as_deref(),
+            Some(SHA256)
+        );
+        let virtualbox = format!("{SHA256} *VirtualBox-7.1.6-167084-Win.exe\n");
+        assert_eq!(
+            find_sha256(&virtualbox, "VirtualBox-7.1.6-167084-Win.exe").as_deref(),
+            Some(SHA256)
+        );
+        assert_eq!(
+            find_sha256(&virtualbox, "VirtualBox-7.1.6-167084-OSX.dmg"),
+            None
+        );
+    }
+}
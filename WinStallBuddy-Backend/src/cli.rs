@@ -0,0 +1,181 @@
+use crate::catalog::{Catalog, Category};
+use crate::config::ConfigOptions;
+use crate::utils::{self, DownloadOptions};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+// wsb's command line, every command runs without prompting once --yes is given so it can be scripted
+#[derive(Debug, Parser)]
+#[command(
+    name = "wsb",
+    version,
+    arg_required_else_help = true,
+    about = "Downloads the latest Windows installers of the apps you pick"
+)]
+pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "config.ini to use, otherwise WSB_CONFIG or the first config.ini in the current directory, next to wsb.exe or in the user config directory"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long = "set",
+        global = true,
+        value_name = "SECTION.KEY=VALUE",
+        help = "Override a config.ini value (programming.NodeJS=true), can be repeated"
+    )]
+    overrides: Vec<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "Print JSON lines on stdout instead of text, messages go to stderr"
+    )]
+    json: bool,
+    #[arg(short, long, global = true, help = "Answer yes to every prompt")]
+    yes: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(about = "Download apps, the ones set to true in config.ini when none are named")]
+    Download(SelectionArgs),
+    #[command(about = "Download and install apps (not implemented yet)", hide = true)]
+    Install(SelectionArgs),
+    #[command(about = "List the apps wsb knows about and their config.ini keys")]
+    List {
+        #[arg(
+            short,
+            long = "category",
+            value_enum,
+            value_name = "CATEGORY",
+            help = "Only list this category, can be repeated"
+        )]
+        categories: Vec<Category>,
+    },
+    #[command(about = "Find apps by name, config key or alias, typos are tolerated")]
+    Search {
+        #[arg(help = "Part of a name, e.g. code finds Visual Studio Code and VSCodium")]
+        query: String,
+    },
+    #[command(about = "Show where an app would be downloaded from without downloading it")]
+    Info {
+        #[arg(help = "App name or config key, Python=3.12 for a versioned app")]
+        app: String,
+    },
+    #[command(about = "Check config.ini for unknown keys and invalid values, same as config check")]
+    Check,
+    #[command(about = "Write a config.ini listing every app, all set to false")]
+    Init {
+        #[arg(long, help = "Overwrite an existing config.ini")]
+        force: bool,
+        #[arg(
+            long,
+            help = "Set the apps winget reports as installed to true, needs winget"
+        )]
+        from_installed: bool,
+    },
+    #[command(about = "Inspect config.ini", subcommand)]
+    Config(ConfigCommand),
+    #[command(about = "Print wsb's version")]
+    Version,
+    #[command(about = "Print a completion script for the given shell")]
+    Completions { shell: Shell },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    #[command(about = "Check config.ini for unknown keys and invalid values")]
+    Check,
+    #[command(about = "Print the effective configuration and where each value comes from")]
+    Show,
+}
+
+#[derive(Debug, Args)]
+struct SelectionArgs {
+    #[arg(
+        value_name = "APP",
+        help = "App names or config keys (firefox vlc 7zip), Key=value passes a version (Python=3.12)"
+    )]
+    apps: Vec<String>,
+    #[arg(
+        short,
+        long = "category",
+        value_enum,
+        value_name = "CATEGORY",
+        help = "Download the apps set to true in this config.ini section, can be repeated"
+    )]
+    categories: Vec<Category>,
+    #[arg(
+        short,
+        long,
+        value_name = "DIR",
+        help = "Folder the installers are saved to [default: applications next to wsb.exe]"
+    )]
+    output_dir: Option<PathBuf>,
+    #[arg(long, help = "Resolve the download links without downloading anything")]
+    dry_run: bool,
+    #[arg(
+        short,
+        long,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Downloads running at the same time, overrides jobs in config.ini"
+    )]
+    jobs: Option<u16>,
+}
+
+impl Cli {
+    pub fn run(self, catalog: &Catalog) -> ExitCode {
+        let config = ConfigOptions {
+            path: self.config,
+            overrides: self.overrides,
+        };
+        match self.command {
+            Command::Download(selection) => {
+                let options = DownloadOptions {
+                    config,
+                    apps: selection.apps,
+                    categories: selection.categories,
+                    output_dir: selection.output_dir,
+                    dry_run: selection.dry_run,
+                    jobs: selection.jobs.map(usize::from),
+                    json: self.json,
+                    yes: self.yes,
+                };
+                utils::download(catalog, &options)
+            }
+            Command::Install(_) => {
+                eprintln!("install isn't implemented yet, use download to fetch the installers");
+                ExitCode::from(2)
+            }
+            Command::List { categories } => {
+                utils::list(catalog, &categories, self.json);
+                ExitCode::SUCCESS
+            }
+            Command::Search { query } => utils::search(catalog, &query, self.json),
+            Command::Info { app } => utils::info(catalog, &config, &app, self.json),
+            Command::Init {
+                force,
+                from_installed,
+            } => utils::init(catalog, config.path.as_deref(), force, from_installed),
+            Command::Check | Command::Config(ConfigCommand::Check) => {
+                utils::check(catalog, &config, self.json)
+            }
+            Command::Config(ConfigCommand::Show) => utils::show_config(&config, self.json),
+            Command::Version => {
+                println!("WSB v{}", env!("CARGO_PKG_VERSION"));
+                ExitCode::SUCCESS
+            }
+            Command::Completions { shell } => {
+                clap_complete::generate(shell, &mut Cli::command(), "wsb", &mut io::stdout());
+                ExitCode::SUCCESS
+            }
+        }
+    }
+}
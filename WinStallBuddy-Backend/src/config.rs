@@ -0,0 +1,387 @@
+use crate::utils::get_exe_dir;
+use ini::{Ini, ParseOption};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fmt};
+
+const FILE_NAME: &str = "config.ini";
+// Outside of any section, names a file read before this one: include = \\server\share\wsb\base.ini
+const INCLUDE_KEY: &str = "include";
+
+// The global --config and --set options
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOptions {
+    pub path: Option<PathBuf>,
+    // section.Key=value, applied over every file
+    pub overrides: Vec<String>,
+}
+
+// How the config.ini in use was found, in the order they are tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discovery {
+    Flag,
+    Env,
+    CurrentDir,
+    ExeDir,
+    UserDir,
+}
+
+impl fmt::Display for Discovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discovery::Flag => f.write_str("--config"),
+            Discovery::Env => f.write_str("WSB_CONFIG"),
+            Discovery::CurrentDir => f.write_str("the current directory"),
+            Discovery::ExeDir => f.write_str("the directory of wsb.exe"),
+            Discovery::UserDir => f.write_str("the user config directory"),
+        }
+    }
+}
+
+// Where a value of the effective configuration was set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    File(PathBuf),
+    Override,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Override => f.write_str("--set"),
+        }
+    }
+}
+
+// The configuration after includes and --set are applied, ini is what the rest of wsb reads
+#[derive(Debug)]
+pub struct Config {
+    pub ini: Ini,
+    pub found: Option<(PathBuf, Discovery)>,
+    // Every file read, included files before the file including them
+    pub files: Vec<PathBuf>,
+    origins: HashMap<(Option<String>, String), Origin>,
+}
+
+impl Config {
+    pub fn origin(&self, section: Option<&str>, key: &str) -> Option<&Origin> {
+        self.origins
+            .get(&(section.map(String::from), key.to_string()))
+    }
+
+    // Reads path after the files it includes, so its own values win. Relative includes are relative to path's directory.
+    fn layer(&mut self, path: &Path, including: &mut Vec<PathBuf>) -> Result<(), String> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if including.contains(&canonical) {
+            return Err(format!("includes loop back to {}", path.display()));
+        }
+        // rust-ini treats \ as an escape by default, which would turn C:\certs\root.pem into C:certsroot.pem
+        let parse_option = ParseOption {
+            enabled_escape: false,
+            ..ParseOption::default()
+        };
+        let file = Ini::load_from_file_opt(path, parse_option)
+            .map_err(|e| format!("Unable to load {}: {e}", path.display()))?;
+
+        including.push(canonical);
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for include in file.general_section().get_all(INCLUDE_KEY) {
+            self.layer(&dir.join(include), including)
+                .map_err(|e| format!("{e} (included from {})", path.display()))?;
+        }
+        including.pop();
+
+        for (section, properties) in file.iter() {
+            // An empty [system] is still a section, it selects none of its apps instead of being missing
+            self.ini
+                .entry(section.map(String::from))
+                .or_insert_with(Default::default);
+            for (key, value) in properties.iter() {
+                if section.is_none() && key == INCLUDE_KEY {
+                    continue;
+                }
+                self.set(section, key, value, Origin::File(path.to_path_buf()));
+            }
+        }
+        self.files.push(path.to_path_buf());
+        Ok(())
+    }
+
+    fn set(&mut self, section: Option<&str>, key: &str, value: &str, origin: Origin) {
+        self.ini.with_section(section).set(key, value);
+        self.origins
+            .insert((section.map(String::from), key.to_string()), origin);
+    }
+}
+
+// Finds config.ini and reads it with its includes, then applies the --set overrides.
+// Without a file the configuration is empty (every setting at its default), which is an error when required and nothing is set.
+pub fn load(options: &ConfigOptions, required: bool) -> Result<Config, String> {
+    let overrides: Vec<(String, String, String)> = options
+        .overrides
+        .iter()
+        .map(|text| parse_override(text))
+        .collect::<Result<_, _>>()?;
+    let mut config = Config {
+        ini: Ini::new(),
+        found: discover(options.path.as_deref()),
+        files: Vec::new(),
+        origins: HashMap::new(),
+    };
+
+    match config.found.clone() {
+        Some((path, _)) => config.layer(&path, &mut Vec::new())?,
+        None if required && overrides.is_empty() => {
+            let searched: Vec<String> = candidates()
+                .iter()
+                .map(|(path, _)| path.display().to_string())
+                .collect();
+            return Err(format!(
+                "No config.ini found (looked for {}), run `wsb init` to write one",
+                searched.join(", ")
+            ));
+        }
+        None => {}
+    }
+    for (section, key, value) in &overrides {
+        config.set(Some(section), key, value, Origin::Override);
+    }
+    Ok(config)
+}
+
+// --config and WSB_CONFIG are used even when the file is missing, so a typo in them is reported instead of silently
+// falling back to another config.ini
+pub fn discover(explicit: Option<&Path>) -> Option<(PathBuf, Discovery)> {
+    if let Some(path) = explicit {
+        return Some((path.to_path_buf(), Discovery::Flag));
+    }
+    if let Some(path) = env::var_os("WSB_CONFIG").filter(|path| !path.is_empty()) {
+        return Some((PathBuf::from(path), Discovery::Env));
+    }
+    candidates().into_iter().find(|(path, _)| path.is_file())
+}
+
+// Where `wsb init` writes: --config, WSB_CONFIG or next to wsb.exe
+pub fn init_path(explicit: Option<&Path>) -> PathBuf {
+    match discover(explicit) {
+        Some((path, Discovery::Flag | Discovery::Env)) => path,
+        _ => PathBuf::from(get_exe_dir()).join(FILE_NAME),
+    }
+}
+
+fn candidates() -> Vec<(PathBuf, Discovery)> {
+    let mut candidates: Vec<(PathBuf, Discovery)> = Vec::new();
+    if let Ok(dir) = env::current_dir() {
+        candidates.push((dir.join(FILE_NAME), Discovery::CurrentDir));
+    }
+    candidates.push((
+        PathBuf::from(get_exe_dir()).join(FILE_NAME),
+        Discovery::ExeDir,
+    ));
+    if let Some(dir) = user_config_dir() {
+        candidates.push((dir.join(FILE_NAME), Discovery::UserDir));
+    }
+    candidates
+}
+
+// %APPDATA%\wsb on Windows, $XDG_CONFIG_HOME/wsb or ~/.config/wsb elsewhere
+fn user_config_dir() -> Option<PathBuf> {
+    let var = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+    let dir = if cfg!(windows) {
+        var("APPDATA").map(PathBuf::from)
+    } else {
+        var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    dir.map(|dir| dir.join("wsb"))
+}
+
+// section.Key=value, the section is everything before the last dot so [http.headers.<host>] can be set as well
+fn parse_override(text: &str) -> Result<(String, String, String), String> {
+    let invalid = || {
+        format!("Invalid --set {text}, expected section.Key=value (e.g. programming.NodeJS=true)")
+    };
+    let (name, value) = text.split_once('=').ok_or_else(invalid)?;
+    let (section, key) = name.trim().rsplit_once('.').ok_or_else(invalid)?;
+    if section.is_empty() || key.is_empty() {
+        return Err(invalid());
+    }
+    Ok((
+        section.to_string(),
+        key.to_string(),
+        value.trim().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stub::scratch_dir;
+    use std::fs;
+
+    fn load_file(path: &Path) -> Config {
+        let options = ConfigOptions {
+            path: Some(path.to_path_buf()),
+            overrides: Vec::new(),
+        };
+        load(&options, true).unwrap()
+    }
+
+    #[test]
+    fn backslash_paths_are_kept() {
+        let dir = scratch_dir("config-backslash");
+        let path = dir.join("config.ini");
+        fs::write(
+            &path,
+            "[http]\nca_bundle = C:\\certs\\corporate-root.pem\n\n[helper]\npath = tools\\DU_CLI.exe\n",
+        )
+        .unwrap();
+
+        let config = load_file(&path);
+        let value = |section: &str, key: &str| {
+            config
+                .ini
+                .section(Some(section))
+                .and_then(|section| section.get(key))
+                .map(String::from)
+        };
+        assert_eq!(
+            value("http", "ca_bundle").as_deref(),
+            Some("C:\\certs\\corporate-root.pem")
+        );
+        assert_eq!(
+            value("helper", "path").as_deref(),
+            Some("tools\\DU_CLI.exe")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn includes_are_read_first_and_overridden() {
+        let dir = scratch_dir("config-include");
+        fs::write(
+            dir.join("base.ini"),
+            "[browser]\nFirefox = true\nBrave = true\n",
+        )
+        .unwrap();
+        let path = dir.join("config.ini");
+        fs::write(&path, "include = base.ini\n\n[browser]\nBrave = false\n").unwrap();
+
+        let config = load_file(&path);
+        let browser = config.ini.section(Some("browser")).unwrap();
+        assert_eq!(browser.get("Firefox"), Some("true"));
+        assert_eq!(browser.get("Brave"), Some("false"));
+        assert_eq!(
+            config.origin(Some("browser"), "Firefox"),
+            Some(&Origin::File(dir.join("base.ini")))
+        );
+        assert!(config.ini.general_section().get(INCLUDE_KEY).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty_sections_are_kept() {
+        let dir = scratch_dir("config-empty");
+        let path = dir.join("config.ini");
+        fs::write(&path, "[browser]\nFirefox = true\n\n[system]\n").unwrap();
+
+        let config = load_file(&path);
+        let system = config.ini.section(Some("system")).unwrap();
+        assert!(system.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn overrides_win_over_the_file() {
+        let dir = scratch_dir("config-overrides");
+        let path = dir.join("config.ini");
+        fs::write(&path, "[programming]\nNodeJS = false\n").unwrap();
+
+        let options = ConfigOptions {
+            path: Some(path.clone()),
+            overrides: vec![
+                "programming.NodeJS = 20".to_string(),
+                "http.headers.example.com.X-Token=secret".to_string(),
+            ],
+        };
+        let config = load(&options, true).unwrap();
+        assert_eq!(
+            config.ini.get_from(Some("programming"), "NodeJS"),
+            Some("20")
+        );
+        assert_eq!(
+            config
+                .ini
+                .get_from(Some("http.headers.example.com"), "X-Token"),
+            Some("secret")
+        );
+        assert_eq!(
+            config.origin(Some("programming"), "NodeJS"),
+            Some(&Origin::Override)
+        );
+        assert_eq!(config.files, vec![path]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_overrides_are_rejected() {
+        for text in [
+            "NodeJS=true",
+            ".Key=v",
+            "programming.=true",
+            "programming.NodeJS",
+        ] {
+            assert_eq!(
+                parse_override(text).unwrap_err(),
+                format!(
+                    "Invalid --set {text}, expected section.Key=value (e.g. programming.NodeJS=true)"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn include_loops_are_errors() {
+        let dir = scratch_dir("config-loop");
+        fs::write(dir.join("a.ini"), "include = b.ini\n").unwrap();
+        fs::write(dir.join("b.ini"), "include = a.ini\n").unwrap();
+
+        let options = ConfigOptions {
+            path: Some(dir.join("a.ini")),
+            overrides: Vec::new(),
+        };
+        let error = load(&options, true).unwrap_err();
+        assert!(error.starts_with("includes loop back to "), "{error}");
+        assert!(
+            error.ends_with(&format!(
+                "(included from {}) (included from {})",
+                dir.join("b.ini").display(),
+                dir.join("a.ini").display()
+            )),
+            "{error}"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_missing_config_flag_file_is_an_error() {
+        let dir = scratch_dir("config-missing");
+        let path = dir.join("config.ini");
+        let options = ConfigOptions {
+            path: Some(path.clone()),
+            overrides: Vec::new(),
+        };
+        assert_eq!(discover(Some(&path)), Some((path.clone(), Discovery::Flag)));
+        // Not even with nothing required is another config.ini used in its place
+        for required in [true, false] {
+            let error = load(&options, required).unwrap_err();
+            assert!(
+                error.starts_with(&format!("Unable to load {}", path.display())),
+                "{error}"
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
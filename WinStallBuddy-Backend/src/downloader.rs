@@ -0,0 +1,584 @@
+use crate::filename;
+use crate::http::HttpClient;
+use crate::progress::{ProgressEvent, ProgressSubscriber, TransferMeter};
+use crate::resolver::ResolvedDownload;
+use crate::sniff::{self, FileType};
+use reqwest::StatusCode;
+use reqwest::blocking::Response;
+use reqwest::header::{
+    ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_RANGE, ETAG, HeaderMap, HeaderValue, IF_RANGE,
+    LAST_MODIFIED, RANGE, REFERER,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+// Suffix of the file a download is streamed into, it is only renamed to the real filename once complete
+const PART_SUFFIX: &str = ".part";
+// Suffix of the sidecar written next to a .part file so the download can be resumed
+const METADATA_SUFFIX: &str = ".json";
+// Folder inside applications that downloads failing the content check are moved to
+const QUARANTINE_DIR: &str = "quarantine";
+// Size of the reads between progress updates
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Downloads the resolved installer into output_dir and returns its filename.
+// The body is streamed into <filename>.part, flushed to disk and only then renamed to <filename>,
+// so an interrupted or failed download never leaves a truncated installer behind.
+// When the server advertises byte ranges the .part file is kept on failure (alongside a <filename>.part.json sidecar)
+// and the next run resumes it, as long as the url and the server's validator (ETag/Last-Modified) are unchanged.
+// Progress is reported to the subscriber under the app's config key.
+pub fn download_file(
+    http: &HttpClient,
+    app: &str,
+    resolved: &ResolvedDownload,
+    output_dir: &Path,
+    progress: &dyn ProgressSubscriber,
+) -> Result<String, Box<dyn Error>> {
+    let message = |message: String| {
+        progress.on_event(&ProgressEvent::Message {
+            app: app.to_string(),
+            message,
+        });
+    };
+    let request_headers = request_headers(resolved)?;
+    let mut response = http.get_with_headers(&resolved.url, request_headers.clone())?; // Non success statuses are returned as an Err by the http client
+    let headers = response.headers().clone();
+    let filename = response_filename(resolved, &headers, response.url().as_str())
+        .ok_or("Unable to parse filename from response, continuing...")?;
+
+    let applications_dir = output_dir.to_path_buf();
+    fs::create_dir_all(&applications_dir)?;
+    let final_path = applications_dir.join(&filename);
+    let part_path = applications_dir.join(format!("{filename}{PART_SUFFIX}"));
+    let metadata_path = applications_dir.join(format!("{filename}{PART_SUFFIX}{METADATA_SUFFIX}"));
+
+    let mut metadata = PartMetadata::from_headers(&resolved.url, &headers);
+    let resumable = accepts_ranges(&headers) && metadata.if_range().is_some();
+
+    if resumable && let Some(offset) = resume_offset(&part_path, &metadata_path, &metadata) {
+        drop(response); // Closes the full length transfer, the ranged request replaces it
+        match request_range(
+            http,
+            &resolved.url,
+            request_headers.clone(),
+            offset,
+            &metadata,
+        ) {
+            Ok(ranged)
+                if ranged.status() == StatusCode::PARTIAL_CONTENT
+                    && content_range_start(ranged.headers()) == Some(offset) =>
+            {
+                metadata.bytes = offset;
+                response = ranged;
+            }
+            // Appending a range that doesn't start where the .part file ends would corrupt it
+            Ok(ranged) if ranged.status() == StatusCode::PARTIAL_CONTENT => {
+                drop(ranged);
+                message(format!(
+                    "{filename} was resumed at the wrong offset by the server, restarting the download"
+                ));
+                response = http.get_with_headers(&resolved.url, request_headers)?;
+                metadata = PartMetadata::from_headers(&resolved.url, response.headers());
+            }
+            // The server ignored the range (If-Range no longer matched), the 200 carries the whole file
+            Ok(full) => {
+                message(format!(
+                    "{filename} changed on the server, restarting the download"
+                ));
+                metadata = PartMetadata::from_headers(&resolved.url, full.headers());
+                response = full;
+            }
+            Err(e) => {
+                message(format!(
+                    "Unable to resume {filename} ({e}), restarting the download"
+                ));
+                response = http.get_with_headers(&resolved.url, request_headers)?;
+            }
+        }
+    } else if part_path.exists() {
+        message(format!(
+            "Discarding the incomplete {filename}, restarting the download"
+        ));
+    }
+
+    if resumable {
+        // Written before the transfer starts so a killed process can still resume
+        metadata.save(&metadata_path)?;
+    } else {
+        let _ = fs::remove_file(&metadata_path);
+    }
+
+    let offset = metadata.bytes;
+    let total = response.content_length().map(|length| length + offset);
+    progress.on_event(&ProgressEvent::Started {
+        app: app.to_string(),
+        filename: filename.clone(),
+        total,
+        resumed_from: offset,
+    });
+
+    // Only hashed when there is a published or pinned digest to compare against
+    let mut hasher = match &resolved.sha256 {
+        Some(_) => Some(hash_existing(&part_path, offset)?),
+        None => None,
+    };
+
+    let bytes = match stream_to_file(
+        &mut response,
+        &part_path,
+        offset,
+        app,
+        total,
+        progress,
+        hasher.as_mut(),
+    ) {
+        Ok(bytes) => bytes,
+        Err(e) if resumable => {
+            // Best effort, the next run falls back to a full download if the sidecar is missing
+            metadata.bytes = fs::metadata(&part_path).map_or(0, |m| m.len());
+            let _ = metadata.save(&metadata_path);
+            return Err(format!(
+                "Failed to download {filename} after {} bytes, rerun to resume: {e}",
+                metadata.bytes
+            )
+            .into());
+        }
+        Err(e) => {
+            // Best effort, the .part file may not have been created yet
+            let _ = fs::remove_file(&part_path);
+            return Err(format!("Failed to download {filename}: {e}").into());
+        }
+    };
+
+    // Scraped links sometimes land on login walls or error pages served with a 200 status
+    let expected_type = resolved
+        .file_type
+        .or_else(|| FileType::from_extension(&filename));
+    // A complete but unchecked .part file must not be resumed by the next run
+    let discard = || {
+        let _ = fs::remove_file(&part_path);
+        let _ = fs::remove_file(&metadata_path);
+    };
+    let sniffed = sniff::check(&part_path, expected_type).map_err(|e| {
+        discard();
+        format!("Unable to check the contents of {filename}: {e}")
+    })?;
+    if let Err(found) = sniffed {
+        let _ = fs::remove_file(&metadata_path);
+        let quarantined = quarantine(&applications_dir, &part_path, &filename).map_err(|e| {
+            discard();
+            format!("Unable to quarantine {filename}: {e}")
+        })?;
+        let expected = match expected_type {
+            Some(expected) => expected.to_string(),
+            None => String::from("an installer"),
+        };
+        return Err(format!(
+            "{filename} is {found}, not {expected}, it was quarantined to {}",
+            quarantined.display()
+        )
+        .into());
+    }
+
+    if let (Some(expected), Some(hasher)) = (&resolved.sha256, hasher) {
+        let actual = format!("{:x}", hasher.finalize());
+        if actual != *expected {
+            // The bytes can't be trusted, resuming them on the next run would keep failing
+            discard();
+            return Err(format!(
+                "SHA-256 mismatch for {filename}: expected {expected}, got {actual}"
+            )
+            .into());
+        }
+    }
+
+    fs::rename(&part_path, &final_path)?; // Replaces an existing installer with the same name
+    let _ = fs::remove_file(&metadata_path);
+
+    progress.on_event(&ProgressEvent::Finished {
+        app: app.to_string(),
+        filename: filename.clone(),
+        bytes,
+        verified: resolved.sha256.is_some(),
+    });
+    Ok(filename)
+}
+
+// Moves a rejected download into applications/quarantine so it can be inspected but is never picked up as an installer
+fn quarantine(applications_dir: &Path, part_path: &Path, filename: &str) -> io::Result<PathBuf> {
+    let quarantine_dir = applications_dir.join(QUARANTINE_DIR);
+    fs::create_dir_all(&quarantine_dir)?;
+    let quarantined = quarantine_dir.join(filename);
+    fs::rename(part_path, &quarantined)?;
+    Ok(quarantined)
+}
+
+// Written next to a .part file, describes which response the partial bytes came from
+#[derive(Debug, Serialize, Deserialize)]
+struct PartMetadata {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    bytes: u64,
+}
+
+impl PartMetadata {
+    fn from_headers(url: &str, headers: &HeaderMap) -> PartMetadata {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        PartMetadata {
+            url: url.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            bytes: 0,
+        }
+    }
+
+    fn load(path: &Path) -> Option<PartMetadata> {
+        let contents = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Both responses describe the same file when the url and the validators match
+    fn same_source(&self, other: &PartMetadata) -> bool {
+        self.url == other.url
+            && self.etag == other.etag
+            && self.last_modified == other.last_modified
+    }
+
+    // If-Range only accepts a strong ETag, weak ones fall back to Last-Modified
+    fn if_range(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+fn accepts_ranges(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT_RANGES)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|unit| unit.trim() == "bytes"))
+}
+
+// Number of bytes that can be kept from a previous run, None when the download has to start over
+fn resume_offset(part_path: &Path, metadata_path: &Path, current: &PartMetadata) -> Option<u64> {
+    let previous = PartMetadata::load(metadata_path)?;
+    let received = fs::metadata(part_path).ok()?.len();
+    // A .part shorter than the sidecar claims was not written by the run that saved it
+    if !previous.same_source(current) || received == 0 || received < previous.bytes {
+        return None;
+    }
+    Some(received)
+}
+
+// First byte of a 206 response, 1000 for Content-Range: bytes 1000-1999/2000
+fn content_range_start(headers: &HeaderMap) -> Option<u64> {
+    let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    let (start, _) = value.trim().strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+// Name the download is saved under, taken from the resolver, the response headers or the url (see filename::resolve_filename)
+pub fn response_filename(
+    resolved: &ResolvedDownload,
+    headers: &HeaderMap,
+    final_url: &str,
+) -> Option<String> {
+    let content_disposition = headers
+        .get(CONTENT_DISPOSITION)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string());
+    filename::resolve_filename(
+        resolved.filename.as_deref(),
+        content_disposition.as_deref(),
+        final_url,
+        &resolved.url,
+    )
+}
+
+// Headers sent with every request for the download, ranged or not
+pub fn request_headers(resolved: &ResolvedDownload) -> Result<HeaderMap, Box<dyn Error>> {
+    let mut headers = HeaderMap::new();
+    if let Some(referer) = &resolved.referer {
+        headers.insert(REFERER, HeaderValue::from_str(referer)?);
+    }
+    Ok(headers)
+}
+
+fn request_range(
+    http: &HttpClient,
+    url: &str,
+    mut headers: HeaderMap,
+    offset: u64,
+    metadata: &PartMetadata,
+) -> Result<Response, Box<dyn Error>> {
+    headers.insert(RANGE, HeaderValue::from_str(&format!("bytes={offset}-"))?);
+    if let Some(validator) = metadata.if_range() {
+        headers.insert(IF_RANGE, HeaderValue::from_str(validator)?);
+    }
+    Ok(http.get_with_headers(url, headers)?)
+}
+
+// Starts a hasher over the bytes a resumed download keeps, so the digest covers the whole file
+fn hash_existing(part_path: &Path, offset: u64) -> io::Result<Sha256> {
+    let mut hasher = Sha256::new();
+    if offset > 0 {
+        let mut file = File::open(part_path)?.take(offset);
+        io::copy(&mut file, &mut hasher)?;
+    }
+    Ok(hasher)
+}
+
+// Appends to the .part file when resuming (offset > 0), otherwise it is truncated.
+// Returns the size of the .part file once everything has been written.
+fn stream_to_file(
+    response: &mut Response,
+    part_path: &Path,
+    offset: u64,
+    app: &str,
+    total: Option<u64>,
+    progress: &dyn ProgressSubscriber,
+    mut hasher: Option<&mut Sha256>,
+) -> io::Result<u64> {
+    let file = if offset > 0 {
+        OpenOptions::new().append(true).open(part_path)?
+    } else {
+        File::create(part_path)?
+    };
+    let mut writer = BufWriter::new(file);
+    let mut meter = TransferMeter::new(offset);
+    let mut bytes = offset;
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = match response.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&buffer[..read]);
+        }
+        bytes += read as u64;
+        if let Some(bytes_per_second) = meter.tick(bytes) {
+            progress.on_event(&ProgressEvent::Progress {
+                app: app.to_string(),
+                bytes,
+                total,
+                bytes_per_second,
+            });
+        }
+    }
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?; // The data has to be on disk before the rename makes it visible
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{HttpConfig, HttpError};
+    use crate::progress::NoProgress;
+    use crate::stub::{self, StubRequest, StubServer};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Cut after this many bytes when the stub drops the connection
+    const CUT_AT: usize = 100_000;
+
+    fn installer() -> Vec<u8> {
+        let mut bytes = b"MZ".to_vec();
+        bytes.extend((0..300_000u32).map(|i| (i % 251) as u8));
+        bytes
+    }
+
+    // Start of the range a request asks for, bytes=1000- gives 1000
+    fn range_start(request: &StubRequest) -> Option<usize> {
+        request
+            .header("range")?
+            .strip_prefix("bytes=")?
+            .trim_end_matches('-')
+            .parse()
+            .ok()
+    }
+
+    fn cut_response(body: &[u8]) -> Vec<u8> {
+        stub::truncated_response(
+            200,
+            &[("Accept-Ranges", "bytes"), ("ETag", "\"v1\"")],
+            &body[..CUT_AT],
+            body.len(),
+        )
+    }
+
+    fn partial_response(body: &[u8], start: usize) -> Vec<u8> {
+        let content_range = format!("bytes {start}-{}/{}", body.len() - 1, body.len());
+        stub::response(
+            206,
+            &[
+                ("Accept-Ranges", "bytes"),
+                ("ETag", "\"v1\""),
+                ("Content-Range", &content_range),
+            ],
+            &body[start..],
+        )
+    }
+
+    #[test]
+    fn connection_cut_mid_transfer_is_resumed() {
+        let body = installer();
+        let served = body.clone();
+        // Full downloads are always cut, only a ranged request can finish the file
+        let server = StubServer::start(move |request| match range_start(request) {
+            Some(start) => partial_response(&served, start),
+            None => cut_response(&served),
+        });
+        let dir = stub::scratch_dir("downloader-resume");
+        let resolved = ResolvedDownload::direct(server.url("/app.exe"));
+
+        let e = download_file(&server.client(), "App", &resolved, &dir, &NoProgress).unwrap_err();
+        assert!(e.to_string().contains("rerun to resume"), "{e}");
+        let kept = fs::metadata(dir.join("app.exe.part")).unwrap().len() as usize;
+        assert!(kept > 0 && kept <= CUT_AT);
+        assert!(dir.join("app.exe.part.json").is_file());
+
+        let filename =
+            download_file(&server.client(), "App", &resolved, &dir, &NoProgress).unwrap();
+        assert_eq!(filename, "app.exe");
+        assert_eq!(fs::read(dir.join("app.exe")).unwrap(), body);
+        assert!(!dir.join("app.exe.part").exists());
+        assert!(!dir.join("app.exe.part.json").exists());
+        let ranged = server
+            .requests()
+            .into_iter()
+            .find(|request| request.header("range").is_some())
+            .unwrap();
+        assert_eq!(range_start(&ranged), Some(kept));
+        assert_eq!(ranged.header("if-range"), Some("\"v1\""));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn range_starting_elsewhere_restarts_the_download() {
+        let body = installer();
+        let served = body.clone();
+        let full_requests = Arc::new(AtomicUsize::new(0));
+        let counted = full_requests.clone();
+        // Answers every range from the first byte, only the first full download is cut
+        let server = StubServer::start(move |request| match range_start(request) {
+            Some(_) => partial_response(&served, 0),
+            None if counted.fetch_add(1, Ordering::SeqCst) == 0 => cut_response(&served),
+            None => stub::response(
+                200,
+                &[("Accept-Ranges", "bytes"), ("ETag", "\"v1\"")],
+                &served,
+            ),
+        });
+        let dir = stub::scratch_dir("downloader-range-mismatch");
+        let resolved = ResolvedDownload::direct(server.url("/app.exe"));
+
+        assert!(download_file(&server.client(), "App", &resolved, &dir, &NoProgress).is_err());
+        download_file(&server.client(), "App", &resolved, &dir, &NoProgress).unwrap();
+        assert_eq!(fs::read(dir.join("app.exe")).unwrap(), body);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn error_status_fails_without_leaving_a_file() {
+        let server = StubServer::start(|_| stub::response(404, &[], b"Not here"));
+        let dir = stub::scratch_dir("downloader-status");
+        let resolved = ResolvedDownload::direct(server.url("/app.exe"));
+
+        let e = download_file(&server.client(), "App", &resolved, &dir, &NoProgress).unwrap_err();
+        assert!(e.to_string().contains("404"), "{e}");
+        assert!(!dir.join("app.exe").exists());
+        assert!(!dir.join("app.exe.part").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn refused_connection_is_an_error() {
+        // Nothing listens on the port once the listener is dropped
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let http = HttpClient::new(&HttpConfig {
+            retries: 0,
+            ..HttpConfig::default()
+        })
+        .unwrap();
+        let dir = stub::scratch_dir("downloader-refused");
+        let resolved = ResolvedDownload::direct(format!("http://127.0.0.1:{port}/app.exe"));
+
+        let e = download_file(&http, "App", &resolved, &dir, &NoProgress).unwrap_err();
+        let e = e.downcast::<HttpError>().unwrap();
+        assert!(
+            matches!(*e, HttpError::Request(ref e) if e.is_connect()),
+            "{e}"
+        );
+        assert!(!dir.join("app.exe.part").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_page_served_as_an_installer_is_quarantined() {
+        let server = StubServer::start(|_| {
+            stub::response(
+                200,
+                &[("Content-Type", "text/html")],
+                b"\xEF\xBB\xBF\n<!DOCTYPE html><title>Please sign in</title>",
+            )
+        });
+        let dir = stub::scratch_dir("downloader-quarantine");
+        let resolved = ResolvedDownload::direct(server.url("/app.exe"));
+
+        let e = download_file(&server.client(), "App", &resolved, &dir, &NoProgress).unwrap_err();
+        assert!(e.to_string().contains("is an HTML page"), "{e}");
+        assert!(dir.join(QUARANTINE_DIR).join("app.exe").is_file());
+        assert!(!dir.join("app.exe").exists());
+        assert!(!dir.join("app.exe.part").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_quarantine_leaves_nothing_to_resume() {
+        let server = StubServer::start(|_| {
+            stub::response(
+                200,
+                &[("Accept-Ranges", "bytes"), ("ETag", "\"v1\"")],
+                b"<!DOCTYPE html><title>Please sign in</title>",
+            )
+        });
+        let dir = stub::scratch_dir("downloader-quarantine-failed");
+        // A file where the quarantine folder would be created
+        fs::write(dir.join(QUARANTINE_DIR), b"").unwrap();
+        let resolved = ResolvedDownload::direct(server.url("/app.exe"));
+
+        let e = download_file(&server.client(), "App", &resolved, &dir, &NoProgress).unwrap_err();
+        assert!(
+            e.to_string().starts_with("Unable to quarantine app.exe: "),
+            "{e}"
+        );
+        assert!(!dir.join("app.exe").exists());
+        assert!(!dir.join("app.exe.part").exists());
+        assert!(!dir.join("app.exe.part.json").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,320 @@
+use crate::http::HttpClient;
+use crate::resolver::{LinkResolver, ResolveError, ResolvedDownload};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use regex::Regex;
+
+// Used when an entry doesn't set version_pattern: the first dotted version number, with an optional -N release suffix
+const DEFAULT_VERSION_PATTERN: &str = r"(\d+(?:\.\d+)+(?:-\d+)?)";
+
+// One <entry> (Atom) or <item> (RSS) of a feed, in the order the feed lists them (newest first for release feeds)
+#[derive(Debug, Clone, Default)]
+pub struct FeedEntry {
+    pub title: String,
+    // Atom <id> or RSS <guid>
+    pub id: String,
+    // Atom <link href>, RSS <link> text and <enclosure url>, in document order
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Title,
+    Id,
+    Link,
+}
+
+// Parses an Atom or RSS 2.0 document, elements are matched on their local name so namespaced feeds work too
+pub fn parse_feed(xml: &str) -> Result<Vec<FeedEntry>, ResolveError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut entries: Vec<FeedEntry> = Vec::new();
+    let mut current: Option<FeedEntry> = None;
+    let mut field: Option<Field> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| ResolveError::Parse(format!("Invalid feed XML: {e}")))?;
+        match event {
+            Event::Start(element) => match local_name(&element).as_str() {
+                "entry" | "item" => current = Some(FeedEntry::default()),
+                name => {
+                    if let Some(entry) = current.as_mut() {
+                        field = field_for(name);
+                        push_link_attributes(entry, &element);
+                    }
+                }
+            },
+            Event::Empty(element) => {
+                if let Some(entry) = current.as_mut() {
+                    push_link_attributes(entry, &element);
+                }
+            }
+            Event::Text(text) => {
+                if let (Some(entry), Some(field)) = (current.as_mut(), field) {
+                    let text = text
+                        .unescape()
+                        .map_err(|e| ResolveError::Parse(format!("Invalid feed XML: {e}")))?;
+                    push_text(entry, field, &text);
+                }
+            }
+            Event::CData(data) => {
+                if let (Some(entry), Some(field)) = (current.as_mut(), field) {
+                    push_text(entry, field, &String::from_utf8_lossy(&data));
+                }
+            }
+            Event::End(element) => match local_name_of(element.local_name().as_ref()).as_str() {
+                "entry" | "item" => {
+                    if let Some(entry) = current.take() {
+                        entries.push(entry);
+                    }
+                    field = None;
+                }
+                _ => field = None,
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+fn local_name(element: &BytesStart) -> String {
+    local_name_of(element.local_name().as_ref())
+}
+
+fn local_name_of(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).to_ascii_lowercase()
+}
+
+fn field_for(name: &str) -> Option<Field> {
+    match name {
+        "title" => Some(Field::Title),
+        "id" | "guid" => Some(Field::Id),
+        "link" => Some(Field::Link),
+        _ => None,
+    }
+}
+
+// Atom links carry their url in href, RSS enclosures in url
+fn push_link_attributes(entry: &mut FeedEntry, element: &BytesStart) {
+    let name = local_name(element);
+    let attribute = match name.as_str() {
+        "link" => "href",
+        "enclosure" => "url",
+        _ => return,
+    };
+    if let Some(value) = element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == attribute.as_bytes())
+        .and_then(|attr| attr.unescape_value().ok())
+    {
+        entry.links.push(value.to_string());
+    }
+}
+
+fn push_text(entry: &mut FeedEntry, field: Field, text: &str) {
+    match field {
+        Field::Title => entry.title.push_str(text),
+        Field::Id => entry.id.push_str(text),
+        Field::Link => entry.links.push(text.to_string()),
+    }
+}
+
+// Which part of an entry the version is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionSource {
+    Title,
+    Id,
+    Link,
+}
+
+impl VersionSource {
+    pub fn parse(value: &str) -> Option<VersionSource> {
+        match value {
+            "title" => Some(VersionSource::Title),
+            "id" => Some(VersionSource::Id),
+            "link" => Some(VersionSource::Link),
+            _ => None,
+        }
+    }
+}
+
+// Reads the newest version from a release feed and builds the download url from a template,
+// catalog entries use it with resolver = "feed", url (the feed), version_from, version_pattern and url_template
+#[derive(Debug, Clone)]
+pub struct FeedResolver {
+    pub url: String,
+    pub version_from: VersionSource,
+    // The first capture group (or the whole match when there is none) is the version
+    pub version_pattern: Option<String>,
+    // {version} is replaced with the version
+    pub url_template: String,
+}
+
+impl LinkResolver for FeedResolver {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        let (_, xml) = http.get_text(&self.url)?;
+        let entries = parse_feed(&xml)?;
+        let version = self.latest_version(&entries)?;
+        let url = self.url_template.replace("{version}", &version);
+        Ok(ResolvedDownload::direct(url).with_version(version))
+    }
+}
+
+impl FeedResolver {
+    // The version of the first entry the pattern matches, entries that don't match (announcements, nightlies) are skipped
+    pub fn latest_version(&self, entries: &[FeedEntry]) -> Result<String, ResolveError> {
+        let pattern = self
+            .version_pattern
+            .as_deref()
+            .unwrap_or(DEFAULT_VERSION_PATTERN);
+        let regex = Regex::new(pattern)
+            .map_err(|e| ResolveError::Parse(format!("Invalid version_pattern: {e}")))?;
+
+        entries
+            .iter()
+            .find_map(|entry| {
+                let sources: Vec<&str> = match self.version_from {
+                    VersionSource::Title => vec![entry.title.as_str()],
+                    VersionSource::Id => vec![entry.id.as_str()],
+                    VersionSource::Link => entry.links.iter().map(String::as_str).collect(),
+                };
+                sources.into_iter().find_map(|source| {
+                    let captures = regex.captures(source.trim())?;
+                    let version = captures.get(1).or_else(|| captures.get(0))?;
+                    Some(version.as_str().to_string())
+                })
+            })
+            .ok_or_else(|| {
+                ResolveError::SelectorMiss(format!(
+                    "No entry of {} matches the version pattern {pattern}",
+                    self.url
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::stub::{self, StubServer};
+
+    const LIBREWOLF: &str = include_str!("../tests/fixtures/feeds/librewolf-releases.atom");
+    const RSS: &str = include_str!("../tests/fixtures/feeds/releases.rss");
+    const SEAMONKEY: &str = include_str!("../tests/fixtures/feeds/seamonkey-news.rss");
+
+    fn resolver(version_from: VersionSource, version_pattern: Option<&str>) -> FeedResolver {
+        FeedResolver {
+            url: String::from("https://example.org/releases.rss"),
+            version_from,
+            version_pattern: version_pattern.map(String::from),
+            url_template: String::from("https://example.org/{version}/setup.exe"),
+        }
+    }
+
+    #[test]
+    fn atom_entries_are_read_in_order() {
+        let entries = parse_feed(LIBREWOLF).unwrap();
+        let titles: Vec<&str> = entries.iter().map(|entry| entry.title.as_str()).collect();
+        assert_eq!(titles, ["136.0.4-1", "136.0.3-1", "136.0.2-1"]);
+        assert_eq!(
+            entries[0].id,
+            "https://gitlab.com/librewolf-community/browser/bsys6/-/releases/136.0.4-1"
+        );
+        assert_eq!(entries[0].links, [entries[0].id.clone()]);
+    }
+
+    #[test]
+    fn librewolf_catalog_entry_resolves_from_its_feed() {
+        let catalog = Catalog::embedded();
+        let librewolf = catalog.get("Librewolf").unwrap().feed_resolver().unwrap();
+        let version = librewolf
+            .latest_version(&parse_feed(LIBREWOLF).unwrap())
+            .unwrap();
+        assert_eq!(version, "136.0.4-1");
+    }
+
+    // The newest news item announces a beta, the catalog's pattern only takes final releases
+    #[test]
+    fn seamonkey_catalog_entry_skips_betas() {
+        let catalog = Catalog::embedded();
+        let seamonkey = catalog
+            .get("Seamonkey-Experimental")
+            .unwrap()
+            .feed_resolver()
+            .unwrap();
+        let version = seamonkey
+            .latest_version(&parse_feed(SEAMONKEY).unwrap())
+            .unwrap();
+        assert_eq!(version, "2.53.20");
+    }
+
+    #[test]
+    fn rss_items_skip_entries_without_a_version() {
+        let entries = parse_feed(RSS).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].title, "Nightly build & test channel");
+        assert_eq!(entries[1].title, "Example Editor 4.12.1 <stable>");
+        assert_eq!(
+            entries[1].links,
+            [
+                "https://example.org/editor/news/4.12.1/",
+                "https://downloads.example.org/editor/ExampleEditor-4.12.1-x64.exe",
+            ]
+        );
+
+        // The channel's description mentions 9.9, only items count
+        let by_title = resolver(VersionSource::Title, None);
+        assert_eq!(by_title.latest_version(&entries).unwrap(), "4.12.1");
+        let by_link = resolver(
+            VersionSource::Link,
+            Some(r"ExampleEditor-([\d.]+)-x64\.exe$"),
+        );
+        assert_eq!(by_link.latest_version(&entries).unwrap(), "4.12.1");
+        let by_id = resolver(VersionSource::Id, Some(r"/news/([\d.]+)/$"));
+        assert_eq!(by_id.latest_version(&entries).unwrap(), "4.12.1");
+    }
+
+    #[test]
+    fn no_matching_entry_is_a_selector_miss() {
+        let entries = parse_feed(RSS).unwrap();
+        let e = resolver(VersionSource::Title, Some(r"Other Editor ([\d.]+)"))
+            .latest_version(&entries)
+            .unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+    }
+
+    #[test]
+    fn broken_xml_is_a_parse_error() {
+        let truncated = &LIBREWOLF[..LIBREWOLF.find("<title>136.0.3-1").unwrap()];
+        let broken = format!("{truncated}</feed></entry>");
+        let e = parse_feed(&broken).unwrap_err();
+        assert!(matches!(e, ResolveError::Parse(_)), "{e}");
+    }
+
+    #[test]
+    fn resolves_through_the_feed_url() {
+        let server = StubServer::start(|_| {
+            stub::response(
+                200,
+                &[("Content-Type", "application/atom+xml")],
+                LIBREWOLF.as_bytes(),
+            )
+        });
+        let feed = FeedResolver {
+            url: server.url("/-/releases.atom"),
+            version_from: VersionSource::Id,
+            version_pattern: Some(String::from("/releases/([^/]+)$")),
+            url_template: String::from("https://example.org/librewolf-{version}.zip"),
+        };
+        let resolved = feed.resolve(&server.client()).unwrap();
+        assert_eq!(resolved.url, "https://example.org/librewolf-136.0.4-1.zip");
+        assert_eq!(resolved.version.as_deref(), Some("136.0.4-1"));
+    }
+}
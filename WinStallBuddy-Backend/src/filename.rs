@@ -0,0 +1,358 @@
+use url::Url;
+use urlencoding::decode;
+
+// Longest name most Windows filesystems accept for a single path component
+const MAX_FILENAME_LENGTH: usize = 255;
+
+// Device names Windows refuses as filenames, with or without an extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// Query parameters some vendors use to carry the installer's name, e.g. download?filename=Setup.exe
+const FILENAME_QUERY_KEYS: [&str; 3] = ["filename", "file", "installer"];
+
+// Extensions of server side scripts, download.php?file=... names the script rather than the download
+const SCRIPT_EXTENSIONS: [&str; 5] = ["php", "asp", "aspx", "jsp", "cgi"];
+
+// Picks the name a download is saved under, in order of priority:
+// 1. the name set by the resolver or the catalog entry
+// 2. the Content-Disposition header (filename*= first, then filename=)
+// 3. the final url after redirects, then the url that was requested
+// Every candidate is sanitized for Windows, None is returned when no candidate yields a usable name.
+pub fn resolve_filename(
+    override_name: Option<&str>,
+    content_disposition: Option<&str>,
+    final_url: &str,
+    request_url: &str,
+) -> Option<String> {
+    if let Some(name) = override_name.and_then(sanitize) {
+        return Some(name);
+    }
+    if let Some(name) = content_disposition
+        .and_then(parse_content_disposition)
+        .and_then(|name| sanitize(&name))
+    {
+        return Some(name);
+    }
+    filename_from_url(final_url).or_else(|| filename_from_url(request_url))
+}
+
+// Extracts the filename from a Content-Disposition value (RFC 6266).
+// The extended filename*=charset'language'percent-encoded form takes priority over the plain filename= parameter.
+pub fn parse_content_disposition(value: &str) -> Option<String> {
+    let mut plain: Option<String> = None;
+    let mut extended: Option<String> = None;
+
+    for parameter in split_parameters(value).into_iter().skip(1) {
+        let Some((name, raw_value)) = parameter.split_once('=') else {
+            continue;
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "filename*" => extended = decode_extended_value(raw_value.trim()),
+            "filename" => plain = Some(unquote(raw_value.trim())),
+            _ => {}
+        }
+    }
+
+    extended.or(plain).filter(|name| !name.is_empty())
+}
+
+// Splits on ';' outside of quoted strings, the first part is the disposition type
+fn split_parameters(value: &str) -> Vec<String> {
+    let mut parameters: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                current.push(c);
+                escaped = false;
+            }
+            '\\' if in_quotes => {
+                current.push(c);
+                escaped = true;
+            }
+            '"' => {
+                current.push(c);
+                in_quotes = !in_quotes;
+            }
+            ';' if !in_quotes => parameters.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parameters.push(current);
+    parameters
+}
+
+// Removes the quotes of a quoted-string and resolves its backslash escapes, tokens are returned as-is
+fn unquote(value: &str) -> String {
+    let Some(inner) = value
+        .strip_prefix('"')
+        .map(|inner| inner.strip_suffix('"').unwrap_or(inner))
+    else {
+        return value.to_string();
+    };
+    let mut unquoted = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                unquoted.push(escaped);
+            }
+        } else {
+            unquoted.push(c);
+        }
+    }
+    unquoted
+}
+
+// Decodes an RFC 5987 ext-value: charset'language'percent-encoded-bytes.
+// UTF-8 is decoded strictly, ISO-8859-1 maps every byte to the code point of the same value.
+fn decode_extended_value(value: &str) -> Option<String> {
+    let value = unquote(value); // Some servers quote the ext-value even though the RFC doesn't allow it
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?.to_ascii_lowercase();
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+    let bytes = percent_decode(encoded)?;
+    match charset.as_str() {
+        "utf-8" => String::from_utf8(bytes).ok(),
+        "iso-8859-1" => Some(bytes.into_iter().map(char::from).collect()),
+        _ => None,
+    }
+}
+
+fn percent_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut input = encoded.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let high = (input.next()? as char).to_digit(16)?;
+            let low = (input.next()? as char).to_digit(16)?;
+            bytes.push((high * 16 + low) as u8);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    Some(bytes)
+}
+
+// Uses the last path segment when it looks like a file (has an extension that isn't a server script),
+// otherwise a filename carried in the query string
+pub fn filename_from_url(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(decode_url);
+    if let Some(name) = segment
+        .filter(|name| has_extension(name) && !is_script(name))
+        .and_then(|name| sanitize(&name))
+    {
+        return Some(name);
+    }
+
+    url.query_pairs()
+        .filter(|(key, _)| FILENAME_QUERY_KEYS.contains(&key.to_ascii_lowercase().as_str()))
+        .map(|(_, value)| value.to_string())
+        .find(|name| has_extension(name))
+        .and_then(|name| sanitize(&name))
+}
+
+fn decode_url(encoded: &str) -> String {
+    match decode(encoded) {
+        Ok(decoded) => decoded.to_string(),
+        Err(_) => encoded.to_string(), // Not valid UTF-8 once decoded, the encoded name is still usable
+    }
+}
+
+fn has_extension(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(stem, extension)| !stem.is_empty() && !extension.is_empty())
+}
+
+fn is_script(name: &str) -> bool {
+    name.rsplit_once('.').is_some_and(|(_, extension)| {
+        SCRIPT_EXTENSIONS
+            .iter()
+            .any(|script| extension.eq_ignore_ascii_case(script))
+    })
+}
+
+// Makes a name safe to create on Windows: only the last path component is kept, reserved and control characters become '_',
+// trailing dots and spaces are removed, reserved device names are prefixed and the length is capped (keeping the extension).
+pub fn sanitize(name: &str) -> Option<String> {
+    // Only the last component counts, a header like filename="..\..\evil.exe" must not escape the applications folder
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    sanitized = sanitized
+        .trim_end_matches(['.', ' '])
+        .trim_start()
+        .to_string();
+    if sanitized.is_empty() || sanitized.chars().all(|c| c == '.') {
+        return None;
+    }
+
+    let stem = sanitized.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        sanitized.insert(0, '_');
+    }
+
+    if sanitized.len() > MAX_FILENAME_LENGTH {
+        sanitized = truncate(&sanitized);
+    }
+    Some(sanitized)
+}
+
+// Shortens the stem so the name (including its extension) fits in MAX_FILENAME_LENGTH bytes
+fn truncate(name: &str) -> String {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if extension.len() < 16 => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    let mut end = MAX_FILENAME_LENGTH
+        .saturating_sub(extension.len())
+        .min(stem.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{extension}", &stem[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::links::map_config_key_to_function_name;
+    use crate::resolver::LinkResolver;
+    use crate::stub::{self, StubServer};
+
+    const RECORDED: &str = include_str!("../tests/fixtures/filenames.tsv");
+
+    // A column of the fixture, - is empty
+    fn column(value: &str) -> Option<&str> {
+        Some(value).filter(|value| *value != "-")
+    }
+
+    #[test]
+    fn every_catalog_app_gets_its_recorded_filename() {
+        let catalog = Catalog::embedded();
+        let rows: Vec<Vec<&str>> = RECORDED
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .map(|line| line.split('\t').collect())
+            .collect();
+
+        for entry in catalog.entries() {
+            assert!(
+                rows.iter().any(|row| row[0] == entry.key),
+                "no recorded download for {}",
+                entry.key
+            );
+        }
+        for row in &rows {
+            let [
+                key,
+                resolver_name,
+                content_disposition,
+                url,
+                final_url,
+                expected,
+            ] = row[..]
+            else {
+                panic!("expected 6 columns, found {row:?}");
+            };
+            let entry = catalog
+                .get(key)
+                .unwrap_or_else(|| panic!("{key} isn't in the catalog"));
+            let override_name = column(resolver_name).or(entry.filename.as_deref());
+            // A resolver that answers without asking anything has to produce the recorded request
+            let server = StubServer::start(|_| stub::response(404, &[], b""));
+            if let Some(resolver) = map_config_key_to_function_name(&catalog, key)
+                && let Ok(resolved) = resolver.resolve(&server.client())
+                && server.requests().is_empty()
+            {
+                assert_eq!(resolved.url, url, "{key}");
+                assert_eq!(resolved.filename.as_deref(), override_name, "{key}");
+            }
+            let filename = resolve_filename(
+                override_name,
+                column(content_disposition),
+                column(final_url).unwrap_or(url),
+                url,
+            );
+            assert_eq!(filename.as_deref(), Some(expected), "{key}");
+        }
+    }
+
+    #[test]
+    fn content_disposition_forms() {
+        let cases = [
+            ("attachment; filename=setup.exe", Some("setup.exe")),
+            (
+                "attachment; filename=\"my setup.exe\"",
+                Some("my setup.exe"),
+            ),
+            ("attachment; filename=\"a\\\"b.exe\"", Some("a\"b.exe")),
+            (
+                "attachment; filename=\"semi;colon.exe\"",
+                Some("semi;colon.exe"),
+            ),
+            (
+                "attachment; filename=\"fallback.exe\"; filename*=UTF-8''%E2%82%AC%20rates.exe",
+                Some("€ rates.exe"),
+            ),
+            (
+                "attachment; filename*=iso-8859-1'en'%E9t%E9.exe",
+                Some("été.exe"),
+            ),
+            ("attachment; FILENAME=upper.exe", Some("upper.exe")),
+            ("attachment; filename=\"\"", None),
+            ("inline", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(
+                parse_content_disposition(value).as_deref(),
+                expected,
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn names_are_made_safe_for_windows() {
+        let cases = [
+            ("..\\..\\evil.exe", Some("evil.exe")),
+            ("../../evil.exe", Some("evil.exe")),
+            ("what?.exe", Some("what_.exe")),
+            ("setup.exe. ", Some("setup.exe")),
+            ("CON.exe", Some("_CON.exe")),
+            ("nul", Some("_nul")),
+            ("...", None),
+            ("", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(sanitize(name).as_deref(), expected, "{name}");
+        }
+        let long = format!("{}.exe", "a".repeat(300));
+        let truncated = sanitize(&long).unwrap();
+        assert_eq!(truncated.len(), MAX_FILENAME_LENGTH);
+        assert!(truncated.ends_with(".exe"));
+    }
+}
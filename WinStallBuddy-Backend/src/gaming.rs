@@ -1,8 +1,10 @@
+use crate::http::HttpClient;
 use crate::links;
+use crate::resolver::{LinkResolver, ResolveError, ResolvedDownload};
 
 #[derive(Debug)]
 #[allow(dead_code)]
-
+#[allow(clippy::upper_case_acronyms)]
 pub enum Gaming {
     AMDAutoDetect,
     BattleNetLauncher,
@@ -15,7 +17,6 @@ pub enum Gaming {
     HWMonitor,
     MSIAfterburner,
     NvidiaApp,
-    OBSStudio,
     Parsec,
     PingPlotter,
     ProcessLasso,
@@ -30,30 +31,59 @@ pub enum Gaming {
 
 #[allow(dead_code)]
 impl Gaming {
-    pub fn get_link(&self) -> Option<String> {
+    // Maps the `builtin` name of a catalog entry to its compiled in resolver
+    pub fn from_name(name: &str) -> Option<Gaming> {
+        match name {
+            "AMDAutoDetect" => Some(Gaming::AMDAutoDetect),
+            "BattleNetLauncher" => Some(Gaming::BattleNetLauncher),
+            "BluestacksEmulator" => Some(Gaming::BluestacksEmulator),
+            "CPUZ" => Some(Gaming::CPUZ),
+            "CurseForge" => Some(Gaming::CurseForge),
+            "EpicGamesLauncher" => Some(Gaming::EpicGamesLauncher),
+            "GPUZ" => Some(Gaming::GPUZ),
+            "HWiNFO" => Some(Gaming::HWiNFO),
+            "HWMonitor" => Some(Gaming::HWMonitor),
+            "MSIAfterburner" => Some(Gaming::MSIAfterburner),
+            "NvidiaApp" => Some(Gaming::NvidiaApp),
+            "Parsec" => Some(Gaming::Parsec),
+            "PingPlotter" => Some(Gaming::PingPlotter),
+            "ProcessLasso" => Some(Gaming::ProcessLasso),
+            "RazerCortex" => Some(Gaming::RazerCortex),
+            "Reshade" => Some(Gaming::Reshade),
+            "RockstarLauncher" => Some(Gaming::RockstarLauncher),
+            "RobloxLauncher" => Some(Gaming::RobloxLauncher),
+            "StreamlabsOBS" => Some(Gaming::StreamlabsOBS),
+            "SteamLauncher" => Some(Gaming::SteamLauncher),
+            "WTFast" => Some(Gaming::WTFast),
+            _ => None,
+        }
+    }
+}
+
+impl LinkResolver for Gaming {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
         match self {
-            Gaming::AMDAutoDetect => Some(links::get_amd_autodetect_link()),
-            Gaming::BattleNetLauncher => Some(links::get_battlenet_link()),
-            Gaming::BluestacksEmulator => Some(links::get_bluestacks_link()),
-            Gaming::CPUZ => Some(links::get_cpuz_link()),
-            Gaming::CurseForge => Some(links::get_curseforge_link()),
-            Gaming::EpicGamesLauncher => Some(links::get_epic_games_link()),
-            Gaming::GPUZ => Some(links::get_gpuz_link()),
-            Gaming::HWiNFO => Some(links::get_hwinfo_link()),
-            Gaming::HWMonitor => Some(links::get_hwmonitor_link()),
-            Gaming::MSIAfterburner => Some(links::get_msi_afterburner_link()),
-            Gaming::NvidiaApp => Some(links::get_nvda_app_link()),
-            Gaming::OBSStudio => Some(links::get_obs_studio_link()),
-            Gaming::Parsec => Some(links::get_parsec_link()),
-            Gaming::PingPlotter => Some(links::get_pingplotter_link()),
-            Gaming::ProcessLasso => Some(links::get_process_lasso_link()),
-            Gaming::RazerCortex => Some(links::get_razer_cortex_link()),
-            Gaming::Reshade => Some(links::get_reshade_link()),
-            Gaming::RockstarLauncher => Some(links::get_rockstar_launcher_link()),
-            Gaming::RobloxLauncher => Some(links::get_roblox_launcher_link()),
-            Gaming::StreamlabsOBS => Some(links::get_streamlabs_obs_link()),
-            Gaming::SteamLauncher => Some(links::get_steam_link()),
-            Gaming::WTFast => Some(links::get_wtfast_link()),
+            Gaming::AMDAutoDetect => links::get_amd_autodetect_link(http),
+            Gaming::BattleNetLauncher => links::get_battlenet_link(),
+            Gaming::BluestacksEmulator => links::get_bluestacks_link(),
+            Gaming::CPUZ => links::get_cpuz_link(),
+            Gaming::CurseForge => links::get_curseforge_link(),
+            Gaming::EpicGamesLauncher => links::get_epic_games_link(),
+            Gaming::GPUZ => links::get_gpuz_link(),
+            Gaming::HWiNFO => links::get_hwinfo_link(),
+            Gaming::HWMonitor => links::get_hwmonitor_link(),
+            Gaming::MSIAfterburner => links::get_msi_afterburner_link(),
+            Gaming::NvidiaApp => links::get_nvda_app_link(),
+            Gaming::Parsec => links::get_parsec_link(),
+            Gaming::PingPlotter => links::get_pingplotter_link(),
+            Gaming::ProcessLasso => links::get_process_lasso_link(),
+            Gaming::RazerCortex => links::get_razer_cortex_link(),
+            Gaming::Reshade => links::get_reshade_link(),
+            Gaming::RockstarLauncher => links::get_rockstar_launcher_link(),
+            Gaming::RobloxLauncher => links::get_roblox_launcher_link(http),
+            Gaming::StreamlabsOBS => links::get_streamlabs_obs_link(),
+            Gaming::SteamLauncher => links::get_steam_link(),
+            Gaming::WTFast => links::get_wtfast_link(),
         }
     }
 }
@@ -0,0 +1,463 @@
+use crate::checksum;
+use crate::http::{HttpClient, HttpConfig, HttpError};
+use crate::resolver::{Arch, LinkResolver, ResolveError, ResolvedDownload};
+use ini::Ini;
+use regex::Regex;
+use reqwest::StatusCode;
+use reqwest::blocking::Response;
+use reqwest::header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue};
+use serde_json::Value;
+use std::env;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const API_URL: &str = "https://api.github.com";
+const API_HOST: &str = "api.github.com";
+// Environment variables checked for a token when config.ini doesn't set one
+const TOKEN_ENV_VARS: [&str; 2] = ["WSB_GITHUB_TOKEN", "GITHUB_TOKEN"];
+// A rate limit resetting sooner than this is waited out, later resets fail the app
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+// How many releases are looked at when prereleases are allowed
+const RELEASES_PER_PAGE: u32 = 10;
+// Keys read from the [github] section, anything else is reported by `wsb config check`
+pub const CONFIG_KEYS: [&str; 1] = ["token"];
+
+// Token from the [github] section of config.ini, unauthenticated requests are limited to 60 per hour:
+//
+// [github]
+// token = ghp_...
+//
+// It is sent as the Authorization header of api.github.com requests, an Authorization set in
+// [http.headers.api.github.com] is left as it is.
+pub fn add_token(config: &Ini, http_config: &mut HttpConfig) {
+    let token = config
+        .section(Some("github"))
+        .and_then(|section| section.get("token"))
+        .map(String::from)
+        .into_iter()
+        .chain(TOKEN_ENV_VARS.iter().filter_map(|name| env::var(name).ok()))
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty());
+    let Some(token) = token else {
+        return;
+    };
+    let headers = http_config
+        .host_headers
+        .entry(String::from(API_HOST))
+        .or_default();
+    if !headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case(AUTHORIZATION.as_str()))
+    {
+        headers.push((AUTHORIZATION.to_string(), format!("Bearer {token}")));
+    }
+}
+
+// How an asset is picked from a release
+#[derive(Debug, Clone)]
+pub enum AssetPattern {
+    // Shell style, * matches any run of characters and ? a single one
+    Glob(String),
+    Regex(String),
+}
+
+impl AssetPattern {
+    fn to_regex(&self) -> Result<Regex, ResolveError> {
+        let pattern = match self {
+            AssetPattern::Glob(glob) => glob_to_regex(glob),
+            AssetPattern::Regex(regex) => regex.clone(),
+        };
+        Regex::new(&pattern).map_err(|e| ResolveError::Parse(format!("Invalid asset pattern: {e}")))
+    }
+}
+
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("(?i)^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+// Resolves the newest release of a GitHub repository to one of its assets, catalog entries use it
+// with resolver = "github:<owner>/<repo>" and asset (glob) or asset_regex
+#[derive(Debug, Clone)]
+pub struct GitHubResolver {
+    pub repo: String,
+    pub asset: AssetPattern,
+    pub prerelease: bool,
+    pub arch: Option<Arch>,
+}
+
+impl LinkResolver for GitHubResolver {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        let mut notes: Vec<String> = Vec::new();
+        let release = self.find_release(http, &mut notes)?;
+        let tag = release["tag_name"].as_str().ok_or_else(|| {
+            ResolveError::Parse(format!("{}: release without a tag_name", self.repo))
+        })?;
+        let assets: &[Value] = release["assets"].as_array().map_or(&[], Vec::as_slice);
+
+        let (name, url) = self.select_asset(assets)?;
+        let version = tag.strip_prefix('v').unwrap_or(tag);
+        let mut resolved = ResolvedDownload::direct(url)
+            .with_version(version)
+            .with_filename(name.clone());
+        resolved.notes = notes;
+        Ok(checksum::attach_sha256(
+            resolved,
+            checksum::github_asset_sha256(http, assets, &name),
+        ))
+    }
+}
+
+impl GitHubResolver {
+    // /releases/latest never returns drafts or prereleases, the release list is only needed when prereleases are wanted
+    fn find_release(
+        &self,
+        http: &HttpClient,
+        notes: &mut Vec<String>,
+    ) -> Result<Value, ResolveError> {
+        if !self.prerelease {
+            return api_get(
+                http,
+                &format!("{API_URL}/repos/{}/releases/latest", self.repo),
+                notes,
+            );
+        }
+        let releases = api_get(
+            http,
+            &format!(
+                "{API_URL}/repos/{}/releases?per_page={RELEASES_PER_PAGE}",
+                self.repo
+            ),
+            notes,
+        )?;
+        releases
+            .as_array()
+            .and_then(|releases| {
+                releases
+                    .iter()
+                    .find(|release| !release["draft"].as_bool().unwrap_or(false))
+            })
+            .cloned()
+            .ok_or_else(|| {
+                ResolveError::SelectorMiss(format!("{} has no published releases", self.repo))
+            })
+    }
+
+    // Assets matching the pattern, narrowed to the requested architecture: assets naming another architecture are dropped
+    // and assets naming this one are preferred over ones that don't name any.
+    fn select_asset(&self, assets: &[Value]) -> Result<(String, String), ResolveError> {
+        let pattern = self.asset.to_regex()?;
+        let mut candidates: Vec<(String, String)> = assets
+            .iter()
+            .filter_map(|asset| {
+                let name = asset["name"].as_str()?;
+                let url = asset["browser_download_url"].as_str()?;
+                pattern
+                    .is_match(name)
+                    .then(|| (name.to_string(), url.to_string()))
+            })
+            .collect();
+
+        if let Some(arch) = self.arch {
+            candidates.retain(|(name, _)| {
+                Arch::ALL
+                    .iter()
+                    .filter(|other| **other != arch)
+                    .all(|other| !other.mentioned_in(name) || arch.mentioned_in(name))
+            });
+            if let Some(index) = candidates
+                .iter()
+                .position(|(name, _)| arch.mentioned_in(name))
+            {
+                return Ok(candidates.swap_remove(index));
+            }
+        }
+
+        candidates.into_iter().next().ok_or_else(|| {
+            ResolveError::SelectorMiss(format!(
+                "{}: no release asset matches {:?}{}",
+                self.repo,
+                self.asset,
+                self.arch
+                    .map(|arch| format!(" for {arch}"))
+                    .unwrap_or_default()
+            ))
+        })
+    }
+}
+
+fn api_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        ACCEPT,
+        HeaderValue::from_static("application/vnd.github+json"),
+    );
+    headers
+}
+
+// GETs an API url, waiting out a rate limit that resets within MAX_RATE_LIMIT_WAIT. The wait is added to notes.
+fn api_get(http: &HttpClient, url: &str, notes: &mut Vec<String>) -> Result<Value, ResolveError> {
+    let mut response = http.get_any_status(url, api_headers())?;
+    if let Some(wait) = rate_limit_wait(&response) {
+        if wait > MAX_RATE_LIMIT_WAIT {
+            return Err(ResolveError::Network(format!(
+                "GitHub API rate limit exceeded, it resets in {} minutes. Set WSB_GITHUB_TOKEN, GITHUB_TOKEN or token in the [github] section of config.ini to raise the limit",
+                wait.as_secs().div_ceil(60)
+            )));
+        }
+        notes.push(format!(
+            "GitHub API rate limit reached, waited {} seconds for it to reset",
+            wait.as_secs()
+        ));
+        thread::sleep(wait);
+        response = http.get_any_status(url, api_headers())?;
+    }
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(HttpError::Status(status, url.to_string()).into());
+    }
+    let body = response.text().map_err(HttpError::from)?;
+    serde_json::from_str(&body)
+        .map_err(|e| ResolveError::Parse(format!("Invalid GitHub API response from {url}: {e}")))
+}
+
+// GitHub answers 403 or 429 once the limit is used up, x-ratelimit-reset holds the epoch second it resets at
+// and retry-after is sent for the secondary (abuse) limits
+fn rate_limit_wait(response: &Response) -> Option<Duration> {
+    let status = response.status();
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    if let Some(seconds) = header("retry-after") {
+        return Some(Duration::from_secs(seconds));
+    }
+    if header("x-ratelimit-remaining") != Some(0) {
+        return None;
+    }
+    let reset = header("x-ratelimit-reset")?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(reset.saturating_sub(now) + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::stub::{self, StubServer};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const OBS_LATEST: &str = include_str!("../tests/fixtures/github/obs-studio-latest.json");
+    const OBS_RELEASES: &str = include_str!("../tests/fixtures/github/obs-studio-releases.json");
+    const VSCODIUM_LATEST: &str = include_str!("../tests/fixtures/github/vscodium-latest.json");
+    const VSCODIUM_SHA256: &str =
+        include_str!("../tests/fixtures/github/VSCodiumSetup-x64-1.98.2.25078.exe.sha256");
+
+    // Serves the fixtures under the paths api.github.com and github.com use for them
+    fn github() -> StubServer {
+        StubServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/repos/obsproject/obs-studio/releases/latest" => OBS_LATEST,
+                "/repos/obsproject/obs-studio/releases?per_page=10" => OBS_RELEASES,
+                "/repos/VSCodium/vscodium/releases/latest" => VSCODIUM_LATEST,
+                "/VSCodium/vscodium/releases/download/1.98.2.25078/VSCodiumSetup-x64-1.98.2.25078.exe.sha256" => {
+                    VSCODIUM_SHA256
+                }
+                _ => return stub::response(404, &[], b"{\"message\": \"Not Found\"}"),
+            };
+            stub::response(
+                200,
+                &[("Content-Type", "application/json")],
+                body.as_bytes(),
+            )
+        })
+    }
+
+    fn catalog_resolver(key: &str) -> GitHubResolver {
+        Catalog::embedded()
+            .get(key)
+            .unwrap()
+            .github_resolver()
+            .unwrap()
+    }
+
+    fn asset_name(resolver: &GitHubResolver) -> Result<String, ResolveError> {
+        let assets: Value = serde_json::from_str(OBS_LATEST).unwrap();
+        let assets = assets["assets"].as_array().unwrap();
+        resolver.select_asset(assets).map(|(name, _)| name)
+    }
+
+    #[test]
+    fn globs_match_whole_names() {
+        let regex = Regex::new(&glob_to_regex("rufus-*_x86.exe")).unwrap();
+        assert!(regex.is_match("rufus-4.6_x86.exe"));
+        assert!(regex.is_match("Rufus-4.6_X86.EXE"));
+        assert!(!regex.is_match("rufus-4.6_x86.exe.sig"));
+        assert!(!regex.is_match("rufus-4.6_x86Xexe"));
+        let regex = Regex::new(&glob_to_regex("setup-?.msi")).unwrap();
+        assert!(regex.is_match("setup-1.msi"));
+        assert!(!regex.is_match("setup-12.msi"));
+    }
+
+    #[test]
+    fn catalog_entry_resolves_the_latest_release() {
+        let server = github();
+        let resolved = catalog_resolver("OBSStudio")
+            .resolve(&server.client())
+            .unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://github.com/obsproject/obs-studio/releases/download/31.0.2/OBS-Studio-31.0.2-Windows-x64-Installer.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("31.0.2"));
+        assert_eq!(
+            resolved.filename.as_deref(),
+            Some("OBS-Studio-31.0.2-Windows-x64-Installer.exe")
+        );
+        // GitHub's own digest, no checksum file is fetched
+        assert_eq!(
+            resolved.sha256.as_deref(),
+            Some("9b1f2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9")
+        );
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/repos/obsproject/obs-studio/releases/latest"]);
+    }
+
+    #[test]
+    fn checksum_asset_is_used_without_a_digest() {
+        let server = github();
+        let resolved = catalog_resolver("VSCodium")
+            .resolve(&server.client())
+            .unwrap();
+        assert_eq!(
+            resolved.filename.as_deref(),
+            Some("VSCodiumSetup-x64-1.98.2.25078.exe")
+        );
+        assert_eq!(
+            resolved.sha256.as_deref(),
+            VSCODIUM_SHA256.split_whitespace().next()
+        );
+    }
+
+    #[test]
+    fn arch_picks_among_matching_assets() {
+        let mut obs = catalog_resolver("OBSStudio");
+        assert_eq!(
+            asset_name(&obs).unwrap(),
+            "OBS-Studio-31.0.2-Windows-x64-Installer.exe"
+        );
+        obs.arch = Some(Arch::Arm64);
+        assert_eq!(
+            asset_name(&obs).unwrap(),
+            "OBS-Studio-31.0.2-Windows-arm64-Installer.exe"
+        );
+        // Without an arch the first match wins
+        obs.arch = None;
+        assert_eq!(
+            asset_name(&obs).unwrap(),
+            "OBS-Studio-31.0.2-Windows-arm64-Installer.exe"
+        );
+        // Only assets naming another architecture are dropped, x86 has none here
+        obs.arch = Some(Arch::X86);
+        assert!(matches!(
+            asset_name(&obs),
+            Err(ResolveError::SelectorMiss(_))
+        ));
+    }
+
+    #[test]
+    fn regex_patterns_and_misses() {
+        let mut obs = catalog_resolver("OBSStudio");
+        obs.asset = AssetPattern::Regex(String::from(r"^OBS-Studio-[\d.]+-Windows-x64\.zip$"));
+        assert_eq!(
+            asset_name(&obs).unwrap(),
+            "OBS-Studio-31.0.2-Windows-x64.zip"
+        );
+
+        obs.asset = AssetPattern::Glob(String::from("*.msi"));
+        let e = asset_name(&obs).unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+        assert!(e.to_string().contains("no release asset matches"), "{e}");
+        assert!(e.to_string().contains("for x64"), "{e}");
+
+        obs.asset = AssetPattern::Regex(String::from("(unclosed"));
+        assert!(matches!(asset_name(&obs), Err(ResolveError::Parse(_))));
+    }
+
+    // /releases/latest leaves out drafts and prereleases, with prerelease = true the list is read and only drafts are skipped
+    #[test]
+    fn prereleases_come_from_the_release_list() {
+        let server = github();
+        let mut obs = catalog_resolver("OBSStudio");
+        obs.prerelease = true;
+        let resolved = obs.resolve(&server.client()).unwrap();
+        assert_eq!(resolved.version.as_deref(), Some("32.0.0-beta1"));
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/repos/obsproject/obs-studio/releases?per_page=10"]);
+    }
+
+    #[test]
+    fn short_rate_limits_are_waited_out() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counted = requests.clone();
+        let server = StubServer::start(move |_| {
+            if counted.fetch_add(1, Ordering::SeqCst) == 0 {
+                stub::response(403, &[("retry-after", "0")], b"{}")
+            } else {
+                stub::response(200, &[], OBS_LATEST.as_bytes())
+            }
+        });
+        let resolved = catalog_resolver("OBSStudio")
+            .resolve(&server.client())
+            .unwrap();
+        assert_eq!(resolved.version.as_deref(), Some("31.0.2"));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(
+            resolved.notes[0].starts_with("GitHub API rate limit reached"),
+            "{:?}",
+            resolved.notes
+        );
+    }
+
+    #[test]
+    fn long_rate_limits_fail_the_app() {
+        let reset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600;
+        let server = StubServer::start(move |_| {
+            let reset = reset.to_string();
+            stub::response(
+                403,
+                &[
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", &reset),
+                ],
+                b"{\"message\": \"API rate limit exceeded\"}",
+            )
+        });
+        let e = catalog_resolver("OBSStudio")
+            .resolve(&server.client())
+            .unwrap_err();
+        assert!(matches!(e, ResolveError::Network(_)), "{e}");
+        assert!(e.to_string().contains("rate limit exceeded"), "{e}");
+        assert_eq!(server.requests().len(), 1);
+    }
+}
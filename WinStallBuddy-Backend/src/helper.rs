@@ -0,0 +1,508 @@
+use crate::progress::{ProgressEvent, ProgressSubscriber, TransferMeter};
+use crate::resolver::ResolveError;
+use ini::Ini;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use std::{env, fmt, io};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdout, Command};
+use tokio::time::timeout;
+
+// Version of the JSON-lines protocol spoken with helpers, see docs/helper-protocol.md
+pub const PROTOCOL_VERSION: u32 = 1;
+const DEFAULT_HELPER: &str = "DU_CLI.exe";
+// Keys read from the [helper] section, anything else is reported by `wsb config check`
+pub const CONFIG_KEYS: [&str; 2] = ["path", "timeout"];
+// Windows refuses to start an executable built for another architecture with ERROR_EXE_MACHINE_TYPE_MISMATCH
+const ERROR_EXE_MACHINE_TYPE_MISMATCH: i32 = 216;
+const BUILD_HINT: &str = "Open DU_CLI.sln and publish it with:\ndotnet clean\ndotnet publish -c Release -r win-x86 /p:PublishSingleFile=true --self-contained true\nthen place DU_CLI.exe and selenium-manager next to wsb.exe, or set path in the [helper] section of config.ini";
+
+// Settings read from the [helper] section of config.ini:
+//
+// [helper]
+// path = DU_CLI.exe   ; a bare name is looked up next to wsb.exe, then on PATH
+// timeout = 600       ; seconds a helper may run before it is killed
+#[derive(Debug, Clone)]
+pub struct HelperConfig {
+    pub path: String,
+    pub timeout: Duration,
+}
+
+impl Default for HelperConfig {
+    fn default() -> HelperConfig {
+        HelperConfig {
+            path: String::from(DEFAULT_HELPER),
+            timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+impl HelperConfig {
+    pub fn from_ini(config: &Ini) -> Result<HelperConfig, Box<dyn Error>> {
+        let mut helper_config = HelperConfig::default();
+
+        if let Some(section) = config.section(Some("helper")) {
+            if let Some(path) = section.get("path").map(str::trim)
+                && !path.is_empty()
+            {
+                helper_config.path = path.to_string();
+            }
+            if let Some(seconds) = section.get("timeout") {
+                helper_config.timeout = match seconds.trim().parse::<u64>() {
+                    Ok(seconds) if seconds > 0 => Duration::from_secs(seconds),
+                    _ => {
+                        return Err(format!(
+                            "[helper] timeout must be a whole number of seconds above 0, found \"{seconds}\""
+                        )
+                        .into());
+                    }
+                };
+            }
+        }
+
+        Ok(helper_config)
+    }
+}
+
+// Written to the helper's stdin as a single line once it has started
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    protocol: u32,
+    action: &'a str,
+    app: &'a str,
+    output_dir: &'a str,
+}
+
+// Lines the helper writes to stdout
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Progress {
+        bytes: u64,
+        total: Option<u64>,
+    },
+    Log {
+        message: String,
+    },
+    Result {
+        ok: bool,
+        path: Option<String>,
+        version: Option<String>,
+        error: Option<String>,
+    },
+}
+
+// What a successful helper reported, helpers predating the protocol report nothing
+#[derive(Debug, Default)]
+pub struct HelperOutcome {
+    pub path: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum HelperError {
+    // Neither next to wsb.exe nor on PATH
+    NotFound(String),
+    WrongArchitecture(PathBuf),
+    Spawn(PathBuf, io::Error),
+    TimedOut(Duration),
+    // A line starting with { that isn't a protocol message
+    Malformed(String),
+    // The helper reported a failure or exited with a failure status
+    Failed(String),
+}
+
+impl fmt::Display for HelperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HelperError::NotFound(path) => {
+                write!(
+                    f,
+                    "{path} was not found next to wsb.exe or on PATH.\n{BUILD_HINT}"
+                )
+            }
+            HelperError::WrongArchitecture(path) => write!(
+                f,
+                "{} was built for another CPU architecture or is corrupted.\n{BUILD_HINT}",
+                path.display()
+            ),
+            HelperError::Spawn(path, e) => write!(f, "unable to start {}: {e}", path.display()),
+            HelperError::TimedOut(limit) => write!(
+                f,
+                "the helper didn't finish within {} seconds and was stopped",
+                limit.as_secs()
+            ),
+            HelperError::Malformed(message) => {
+                write!(f, "the helper sent a malformed message: {message}")
+            }
+            HelperError::Failed(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl Error for HelperError {}
+
+// The helper resolves and downloads in one step, so its failures are reported like a resolver's
+impl From<HelperError> for ResolveError {
+    fn from(e: HelperError) -> ResolveError {
+        match e {
+            HelperError::NotFound(_)
+            | HelperError::WrongArchitecture(_)
+            | HelperError::Spawn(..) => ResolveError::Unsupported(e.to_string()),
+            HelperError::TimedOut(_) | HelperError::Failed(_) => {
+                ResolveError::Network(e.to_string())
+            }
+            HelperError::Malformed(_) => ResolveError::Parse(e.to_string()),
+        }
+    }
+}
+
+// Resolves the configured helper: paths are used as given (relative ones from wsb.exe's directory),
+// bare names are looked up next to wsb.exe first and then on PATH
+pub fn find_helper(path: &str, exe_dir: &Path) -> Option<PathBuf> {
+    let candidate = Path::new(path);
+    if candidate.components().count() > 1 || candidate.is_absolute() {
+        let resolved = if candidate.is_absolute() {
+            candidate.to_path_buf()
+        } else {
+            exe_dir.join(candidate)
+        };
+        return resolved.is_file().then_some(resolved);
+    }
+
+    let beside_exe = exe_dir.join(candidate);
+    if beside_exe.is_file() {
+        return Some(beside_exe);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(candidate))
+        .find(|path| path.is_file())
+}
+
+// Exit statuses of the protocol, anything else is reported with its code
+fn describe_exit(status: ExitStatus) -> String {
+    match status.code() {
+        Some(1) => String::from("the helper failed to download the app"),
+        Some(2) => String::from("the helper rejected the request (usage or protocol version)"),
+        Some(3) => String::from("the helper doesn't support this app"),
+        Some(code) => format!("the helper exited with code {code}"),
+        None => String::from("the helper was terminated"),
+    }
+}
+
+// Runs `<helper> <action> <app>`, sends the request line and follows the helper's messages until it exits.
+// Progress is reported under key, the app's config key. The helper is killed when it runs past the configured timeout or when the returned future is dropped (cancelled).
+pub async fn run(
+    key: &str,
+    action: &str,
+    app: &str,
+    output_dir: &Path,
+    config: &HelperConfig,
+    exe_dir: &Path,
+    progress: &dyn ProgressSubscriber,
+) -> Result<HelperOutcome, HelperError> {
+    let program = find_helper(&config.path, exe_dir)
+        .ok_or_else(|| HelperError::NotFound(config.path.clone()))?;
+    let mut child = Command::new(&program)
+        .args([action, app])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.raw_os_error() {
+            Some(ERROR_EXE_MACHINE_TYPE_MISMATCH) => {
+                HelperError::WrongArchitecture(program.clone())
+            }
+            _ => HelperError::Spawn(program.clone(), e),
+        })?;
+
+    let request = Request {
+        kind: "request",
+        protocol: PROTOCOL_VERSION,
+        action,
+        app,
+        output_dir: &output_dir.display().to_string(),
+    };
+    if let Some(mut stdin) = child.stdin.take()
+        && let Ok(line) = serde_json::to_string(&request)
+    {
+        // Helpers predating the protocol never read stdin, a closed pipe is expected from them
+        let _ = stdin.write_all(format!("{line}\n").as_bytes()).await;
+    }
+
+    let Some(stdout) = child.stdout.take() else {
+        return Err(HelperError::Failed(String::from(
+            "the helper's output could not be read",
+        )));
+    };
+    match timeout(config.timeout, converse(&mut child, stdout, key, progress)).await {
+        Ok(result) => result,
+        Err(_) => {
+            let _ = child.kill().await;
+            Err(HelperError::TimedOut(config.timeout))
+        }
+    }
+}
+
+async fn converse(
+    child: &mut Child,
+    stdout: ChildStdout,
+    app: &str,
+    progress: &dyn ProgressSubscriber,
+) -> Result<HelperOutcome, HelperError> {
+    let mut lines = BufReader::new(stdout).lines();
+    let mut result: Option<(bool, HelperOutcome, Option<String>)> = None;
+    let mut meter: Option<TransferMeter> = None;
+    let mut last_bytes: u64 = 0;
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| HelperError::Failed(format!("unable to read the helper's output: {e}")))?
+    {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let log = |message: String| {
+            progress.on_event(&ProgressEvent::Message {
+                app: app.to_string(),
+                message,
+            });
+        };
+        // Plain text is passed through, that is all a helper predating the protocol prints
+        if !line.starts_with('{') {
+            log(line.to_string());
+            continue;
+        }
+        match serde_json::from_str::<Message>(line) {
+            Ok(Message::Log { message }) => log(message),
+            Ok(Message::Progress { bytes, total }) => {
+                last_bytes = bytes;
+                let meter = meter.get_or_insert_with(|| {
+                    progress.on_event(&ProgressEvent::Started {
+                        app: app.to_string(),
+                        filename: app.to_string(),
+                        total,
+                        resumed_from: 0,
+                    });
+                    TransferMeter::new(0)
+                });
+                if let Some(bytes_per_second) = meter.tick(bytes) {
+                    progress.on_event(&ProgressEvent::Progress {
+                        app: app.to_string(),
+                        bytes,
+                        total,
+                        bytes_per_second,
+                    });
+                }
+            }
+            Ok(Message::Result {
+                ok,
+                path,
+                version,
+                error,
+            }) => result = Some((ok, HelperOutcome { path, version }, error)),
+            // The helper is killed when its Child is dropped, a garbled conversation can't be trusted to finish
+            Err(e) => return Err(HelperError::Malformed(format!("{e}: {line}"))),
+        }
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| HelperError::Failed(format!("unable to wait for the helper: {e}")))?;
+    let outcome = match result {
+        Some((true, outcome, _)) if status.success() => outcome,
+        None if status.success() => HelperOutcome::default(),
+        Some((_, _, Some(error))) => return Err(HelperError::Failed(error)),
+        _ if status.success() => {
+            return Err(HelperError::Failed(String::from(
+                "the helper reported a failure without a reason",
+            )));
+        }
+        _ => return Err(HelperError::Failed(describe_exit(status))),
+    };
+
+    if meter.is_some() {
+        let filename = outcome
+            .path
+            .as_deref()
+            .and_then(|path| Path::new(path).file_name())
+            .map_or_else(
+                || app.to_string(),
+                |name| name.to_string_lossy().to_string(),
+            );
+        progress.on_event(&ProgressEvent::Finished {
+            app: app.to_string(),
+            filename,
+            bytes: last_bytes,
+            verified: false,
+        });
+    }
+    Ok(outcome)
+}
+
+// The fake helpers are shell scripts, so these only run where sh does
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<ProgressEvent>>);
+
+    impl ProgressSubscriber for Recorder {
+        fn on_event(&self, event: &ProgressEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    impl Recorder {
+        // The helper's log lines and plain text output, passed on as Message events
+        fn messages(&self) -> Vec<String> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|event| match event {
+                    ProgressEvent::Message { message, .. } => Some(message.clone()),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn transfer_events(&self) -> Vec<ProgressEvent> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| !matches!(event, ProgressEvent::Message { .. }))
+                .cloned()
+                .collect()
+        }
+    }
+
+    async fn run_fixture(
+        script: &str,
+        timeout: Duration,
+        progress: &Recorder,
+    ) -> Result<HelperOutcome, HelperError> {
+        let config = HelperConfig {
+            path: format!(
+                "{}/tests/fixtures/helper/{script}",
+                env!("CARGO_MANIFEST_DIR")
+            ),
+            timeout,
+        };
+        run(
+            "Vivaldi",
+            "download",
+            "Vivaldi",
+            &env::temp_dir(),
+            &config,
+            Path::new("."),
+            progress,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn successful_helper_reports_path_version_and_progress() {
+        let progress = Recorder::default();
+        let outcome = run_fixture("ok.sh", Duration::from_secs(10), &progress)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.path.as_deref(),
+            Some("/tmp/Vivaldi.7.1.3570.39.x64.exe")
+        );
+        assert_eq!(outcome.version.as_deref(), Some("7.1.3570.39"));
+
+        assert_eq!(
+            progress.messages(),
+            ["Starting Vivaldi", "Waiting for the download button"]
+        );
+        let events = progress.transfer_events();
+        assert!(matches!(
+            events.first(),
+            Some(ProgressEvent::Started {
+                total: Some(1024),
+                ..
+            })
+        ));
+        assert!(matches!(
+            events.last(),
+            Some(ProgressEvent::Finished { filename, bytes: 1024, .. })
+                if filename == "Vivaldi.7.1.3570.39.x64.exe"
+        ));
+    }
+
+    #[tokio::test]
+    async fn helper_predating_the_protocol_succeeds_on_exit_status() {
+        let progress = Recorder::default();
+        let outcome = run_fixture("legacy.sh", Duration::from_secs(10), &progress)
+            .await
+            .unwrap();
+        assert!(outcome.path.is_none() && outcome.version.is_none());
+        assert_eq!(progress.messages(), ["Downloading Vivaldi", "Done"]);
+        assert!(progress.transfer_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reported_failure_carries_the_helpers_error() {
+        let e = run_fixture("fails.sh", Duration::from_secs(10), &Recorder::default())
+            .await
+            .unwrap_err();
+        assert_eq!(e.to_string(), "The download page didn't load");
+        assert!(matches!(ResolveError::from(e), ResolveError::Network(_)));
+    }
+
+    #[tokio::test]
+    async fn failure_without_a_result_is_described_by_the_exit_status() {
+        let e = run_fixture(
+            "unsupported.sh",
+            Duration::from_secs(10),
+            &Recorder::default(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(e.to_string(), "the helper doesn't support this app");
+    }
+
+    #[tokio::test]
+    async fn helper_running_past_the_timeout_is_stopped() {
+        let e = run_fixture("hangs.sh", Duration::from_millis(500), &Recorder::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(e, HelperError::TimedOut(_)), "{e}");
+        assert!(matches!(ResolveError::from(e), ResolveError::Network(_)));
+    }
+
+    #[tokio::test]
+    async fn malformed_message_fails_the_app() {
+        let e = run_fixture("garbage.sh", Duration::from_secs(10), &Recorder::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(e, HelperError::Malformed(_)), "{e}");
+        assert!(matches!(ResolveError::from(e), ResolveError::Parse(_)));
+    }
+
+    #[tokio::test]
+    async fn missing_helper_is_unsupported() {
+        let e = run_fixture("missing.sh", Duration::from_secs(10), &Recorder::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(e, HelperError::NotFound(_)), "{e}");
+        assert!(matches!(
+            ResolveError::from(e),
+            ResolveError::Unsupported(_)
+        ));
+    }
+}
@@ -0,0 +1,604 @@
+use ini::Ini;
+use reqwest::blocking::{Client, ClientBuilder, Response};
+use reqwest::header::{
+    CONTENT_LENGTH, CONTENT_RANGE, HeaderMap, HeaderName, HeaderValue, LOCATION, RANGE, USER_AGENT,
+};
+use reqwest::{Certificate, Proxy, StatusCode, redirect};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+use std::{fs, thread};
+use url::Url;
+
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
+
+// Keys read from the [http] section, anything else is reported by `wsb config check`
+pub const CONFIG_KEYS: [&str; 8] = [
+    "user_agent",
+    "timeout",
+    "connect_timeout",
+    "proxy",
+    "ca_bundle",
+    "max_redirects",
+    "retries",
+    "retry_delay",
+];
+
+// Settings read from the [http] section of config.ini, per-host headers come from [http.headers.<host>] sections:
+//
+// [http]
+// user_agent = wsb
+// timeout = 120
+// connect_timeout = 30
+// proxy = http://proxy.example.com:8080
+// ca_bundle = C:\certs\corporate-root.pem
+// max_redirects = 10
+// retries = 2
+// retry_delay = 2
+//
+// [http.headers.api.github.com]
+// Authorization = Bearer <token>
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub user_agent: String,
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub proxy: Option<String>,
+    pub ca_bundle: Option<String>,
+    pub max_redirects: usize,
+    pub retries: u32,
+    pub retry_delay: Duration,
+    pub host_headers: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> HttpConfig {
+        HttpConfig {
+            user_agent: String::from(DEFAULT_USER_AGENT),
+            timeout: Duration::from_secs(120),
+            connect_timeout: Duration::from_secs(30),
+            proxy: None,
+            ca_bundle: None,
+            max_redirects: 10,
+            retries: 2,
+            retry_delay: Duration::from_secs(2),
+            host_headers: HashMap::new(),
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn from_ini(config: &Ini) -> Result<HttpConfig, Box<dyn Error>> {
+        let mut http_config = HttpConfig::default();
+
+        if let Some(section) = config.section(Some("http")) {
+            if let Some(user_agent) = section.get("user_agent") {
+                http_config.user_agent = user_agent.to_string();
+            }
+            if let Some(timeout) = section.get("timeout") {
+                http_config.timeout = Duration::from_secs(parse_number(timeout, "timeout")?);
+            }
+            if let Some(timeout) = section.get("connect_timeout") {
+                http_config.connect_timeout =
+                    Duration::from_secs(parse_number(timeout, "connect_timeout")?);
+            }
+            http_config.proxy = section.get("proxy").map(String::from);
+            http_config.ca_bundle = section.get("ca_bundle").map(String::from);
+            if let Some(max_redirects) = section.get("max_redirects") {
+                http_config.max_redirects =
+                    usize::try_from(parse_number(max_redirects, "max_redirects")?)
+                        .map_err(|_| too_large(max_redirects, "max_redirects"))?;
+            }
+            if let Some(retries) = section.get("retries") {
+                http_config.retries = u32::try_from(parse_number(retries, "retries")?)
+                    .map_err(|_| too_large(retries, "retries"))?;
+            }
+            if let Some(retry_delay) = section.get("retry_delay") {
+                http_config.retry_delay =
+                    Duration::from_secs(parse_number(retry_delay, "retry_delay")?);
+            }
+        }
+
+        for (name, section) in config.iter() {
+            let Some(host) = name.and_then(|name| name.strip_prefix("http.headers.")) else {
+                continue;
+            };
+            let headers = section
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            http_config
+                .host_headers
+                .insert(host.to_ascii_lowercase(), headers);
+        }
+
+        Ok(http_config)
+    }
+}
+
+fn parse_number(value: &str, key: &str) -> Result<u64, Box<dyn Error>> {
+    value
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("[http] {key} must be a whole number, found \"{value}\"").into())
+}
+
+fn too_large(value: &str, key: &str) -> String {
+    format!("[http] {key} is too large, found \"{value}\"")
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    // The request never got a response (dns, connection, tls, timeout)
+    Request(reqwest::Error),
+    // The server responded with a non success status
+    Status(StatusCode, String),
+    // A redirect without a usable Location or past max_redirects, only reported by probe()
+    Redirect(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Request(e) => write!(f, "{e}"),
+            HttpError::Status(status, url) => {
+                write!(f, "Request to {url} failed with status {status}")
+            }
+            HttpError::Redirect(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl Error for HttpError {}
+
+impl From<reqwest::Error> for HttpError {
+    fn from(e: reqwest::Error) -> HttpError {
+        HttpError::Request(e)
+    }
+}
+
+// Response headers of a url fetched without its body, see HttpClient::probe
+#[derive(Debug)]
+pub struct Probe {
+    // Every url that answered with a redirect, in the order they were followed
+    pub redirects: Vec<String>,
+    pub url: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    // The server rejected HEAD and was asked for the first byte instead
+    pub ranged_get: bool,
+}
+
+impl Probe {
+    pub fn header(&self, name: HeaderName) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    // Size of the whole file, a ranged response carries it in Content-Range (bytes 0-0/46137344)
+    pub fn content_length(&self) -> Option<u64> {
+        if self.status == StatusCode::PARTIAL_CONTENT {
+            return self
+                .header(CONTENT_RANGE)?
+                .rsplit_once('/')?
+                .1
+                .trim()
+                .parse()
+                .ok();
+        }
+        self.header(CONTENT_LENGTH)?.trim().parse().ok()
+    }
+}
+
+pub struct HttpClient {
+    client: Client,
+    // Same settings as client but redirects aren't followed, probe() follows them itself to record the chain
+    probe_client: Client,
+    max_redirects: usize,
+    retries: u32,
+    retry_delay: Duration,
+    host_headers: HashMap<String, HeaderMap>,
+    // Origin every request is sent to instead of the url's own, path and query kept. Only the tests set it
+    // (see HttpClient::with_origin), so resolvers with a hard coded vendor url can be run against a stub server
+    origin: Option<String>,
+}
+
+impl HttpClient {
+    pub fn new(config: &HttpConfig) -> Result<HttpClient, Box<dyn Error>> {
+        let client = client_builder(config)?
+            .redirect(redirect::Policy::limited(config.max_redirects))
+            .build()?;
+        let probe_client = client_builder(config)?
+            .redirect(redirect::Policy::none())
+            .build()?;
+
+        let mut host_headers: HashMap<String, HeaderMap> = HashMap::new();
+        for (host, headers) in &config.host_headers {
+            let mut header_map = HeaderMap::new();
+            for (name, value) in headers {
+                header_map.insert(
+                    HeaderName::from_bytes(name.as_bytes())?,
+                    HeaderValue::from_str(value)?,
+                );
+            }
+            host_headers.insert(host.clone(), header_map);
+        }
+
+        Ok(HttpClient {
+            client,
+            probe_client,
+            max_redirects: config.max_redirects,
+            retries: config.retries,
+            retry_delay: config.retry_delay,
+            host_headers,
+            origin: None,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn with_origin(mut self, origin: &str) -> HttpClient {
+        self.origin = Some(origin.to_string());
+        self
+    }
+
+    // Where a request for url is sent, url itself unless an origin was set
+    fn target(&self, url: &str) -> String {
+        match (&self.origin, Url::parse(url)) {
+            (Some(origin), Ok(parsed)) => {
+                format!("{origin}{}", &parsed[url::Position::BeforePath..])
+            }
+            _ => url.to_string(),
+        }
+    }
+
+    // Headers configured for the url's host, a host entry also applies to its subdomains.
+    // Matching hosts are applied from the least to the most specific, so dl.example.com overrides example.com.
+    fn headers_for(&self, url: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+        else {
+            return headers;
+        };
+        let mut matching: Vec<(&String, &HeaderMap)> = self
+            .host_headers
+            .iter()
+            .filter(|(configured_host, _)| {
+                host == **configured_host || host.ends_with(&format!(".{configured_host}"))
+            })
+            .collect();
+        // Every match is a suffix of host, a longer one is a subdomain of a shorter one
+        matching.sort_by_key(|(configured_host, _)| configured_host.len());
+        for (_, host_headers) in matching {
+            headers.extend(host_headers.clone());
+        }
+        headers
+    }
+
+    // Sends a GET request, retrying connection failures, timeouts, 429 and 5xx responses.
+    // Any other non success status is returned as HttpError::Status.
+    pub fn get_with_headers(&self, url: &str, headers: HeaderMap) -> Result<Response, HttpError> {
+        let response = self.get_any_status(url, headers)?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(response)
+        } else {
+            Err(HttpError::Status(status, url.to_string()))
+        }
+    }
+
+    // Same retries as get_with_headers, but the response is returned whatever its final status,
+    // for callers that need the headers of an error response (e.g. GitHub's rate limit headers)
+    pub fn get_any_status(&self, url: &str, headers: HeaderMap) -> Result<Response, HttpError> {
+        let mut attempt: u32 = 0;
+        loop {
+            let mut request_headers = self.headers_for(url);
+            request_headers.extend(headers.clone());
+            let result = self
+                .client
+                .get(self.target(url))
+                .headers(request_headers)
+                .send();
+
+            let retryable = match &result {
+                Ok(response) => {
+                    response.status().is_server_error()
+                        || response.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => e.is_timeout() || e.is_connect(),
+            };
+            if retryable && attempt < self.retries {
+                attempt += 1;
+                thread::sleep(self.retry_delay * attempt);
+                continue;
+            }
+
+            return Ok(result?);
+        }
+    }
+
+    // Fetches the headers the download of url would get, without its body. Redirects are followed one hop at a time
+    // so the chain can be reported. Every hop is asked with HEAD first, servers that reject it (405, 403 from some CDNs)
+    // or fail are asked for the first byte with a ranged GET instead.
+    pub fn probe(&self, url: &str, headers: HeaderMap) -> Result<Probe, HttpError> {
+        let mut redirects: Vec<String> = Vec::new();
+        let mut current = url.to_string();
+        loop {
+            let mut request_headers = self.headers_for(&current);
+            request_headers.extend(headers.clone());
+
+            let head = self
+                .probe_client
+                .head(self.target(&current))
+                .headers(request_headers.clone())
+                .send();
+            let (response, ranged_get) = match head {
+                Ok(response)
+                    if !response.status().is_client_error()
+                        && !response.status().is_server_error() =>
+                {
+                    (response, false)
+                }
+                _ => {
+                    request_headers.insert(RANGE, HeaderValue::from_static("bytes=0-0"));
+                    let ranged = self
+                        .probe_client
+                        .get(self.target(&current))
+                        .headers(request_headers)
+                        .send()?;
+                    (ranged, true)
+                }
+            };
+
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or_else(|| {
+                        HttpError::Redirect(format!("{current} redirected without a Location"))
+                    })?;
+                let next = Url::parse(&current)
+                    .and_then(|base| base.join(location))
+                    .map_err(|e| {
+                        HttpError::Redirect(format!(
+                            "{current} redirected to an invalid url {location}: {e}"
+                        ))
+                    })?;
+                if redirects.len() >= self.max_redirects {
+                    return Err(HttpError::Redirect(format!(
+                        "{url} redirected more than {} times",
+                        self.max_redirects
+                    )));
+                }
+                redirects.push(current);
+                current = next.to_string();
+                continue;
+            }
+            if !status.is_success() {
+                return Err(HttpError::Status(status, current));
+            }
+            return Ok(Probe {
+                redirects,
+                url: current,
+                status,
+                headers: response.headers().clone(),
+                ranged_get,
+            });
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Result<Response, HttpError> {
+        self.get_with_headers(url, HeaderMap::new())
+    }
+
+    pub fn get_text(&self, url: &str) -> Result<(StatusCode, String), HttpError> {
+        let response = self.get(url)?;
+        let status = response.status();
+        Ok((status, response.text()?))
+    }
+}
+
+// Settings shared by the regular and the probe client, the redirect policy is left to the caller
+fn client_builder(config: &HttpConfig) -> Result<ClientBuilder, Box<dyn Error>> {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_str(&config.user_agent)?);
+
+    let mut builder = Client::builder()
+        .default_headers(headers)
+        .timeout(config.timeout)
+        .connect_timeout(config.connect_timeout);
+
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+    if let Some(ca_bundle) = &config.ca_bundle {
+        let pem = fs::read(ca_bundle)
+            .map_err(|e| format!("Unable to read ca_bundle {ca_bundle}: {e}"))?;
+        for certificate in Certificate::from_pem_bundle(&pem)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stub::{self, StubServer};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn client(server: &StubServer, config: HttpConfig) -> HttpClient {
+        HttpClient::new(&HttpConfig {
+            retry_delay: Duration::ZERO,
+            ..config
+        })
+        .unwrap()
+        .with_origin(&server.origin)
+    }
+
+    // Answers with the given statuses in turn, the last one from then on
+    fn statuses(statuses: &'static [u16]) -> (StubServer, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counted = requests.clone();
+        let server = StubServer::start(move |_| {
+            let n = counted.fetch_add(1, Ordering::SeqCst);
+            let status = statuses[n.min(statuses.len() - 1)];
+            stub::response(status, &[], b"body")
+        });
+        (server, requests)
+    }
+
+    fn from_ini(config: &str) -> Result<HttpConfig, String> {
+        HttpConfig::from_ini(&Ini::load_from_str(config).unwrap()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn server_errors_are_retried_up_to_retries() {
+        let (server, requests) = statuses(&[503]);
+        let e = client(&server, HttpConfig::default())
+            .get("https://example.com/app.exe")
+            .unwrap_err();
+        assert!(
+            matches!(e, HttpError::Status(StatusCode::SERVICE_UNAVAILABLE, _)),
+            "{e}"
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let (server, requests) = statuses(&[429, 500, 200]);
+        let (status, body) = client(&server, HttpConfig::default())
+            .get_text("https://example.com/app.exe")
+            .unwrap();
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "body"));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let (server, requests) = statuses(&[502]);
+        let no_retries = HttpConfig {
+            retries: 0,
+            ..HttpConfig::default()
+        };
+        assert!(
+            client(&server, no_retries)
+                .get("https://example.com/")
+                .is_err()
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        for status in [&[404][..], &[403]] {
+            let (server, requests) = statuses(status);
+            let e = client(&server, HttpConfig::default())
+                .get("https://example.com/app.exe")
+                .unwrap_err();
+            assert!(
+                matches!(e, HttpError::Status(s, _) if s.as_u16() == status[0]),
+                "{e}"
+            );
+            assert_eq!(requests.load(Ordering::SeqCst), 1);
+        }
+        // get_any_status hands the error response back instead
+        let (server, _) = statuses(&[404]);
+        let response = client(&server, HttpConfig::default())
+            .get_any_status("https://example.com/", HeaderMap::new())
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn host_headers_are_only_sent_to_their_host() {
+        let config = from_ini(
+            "[http.headers.API.GitHub.com]\nAuthorization = Bearer secret\n\n[http.headers.example.com]\nX-Team = wsb\n",
+        )
+        .unwrap();
+        let server = StubServer::start(|_| stub::response(200, &[], b""));
+        let http = client(&server, config);
+        for url in [
+            "https://api.github.com/repos/a/b",
+            "https://uploads.api.github.com/a",
+            "https://github.com/a/b",
+            "https://notapi.github.com/a",
+            "https://www.example.com/a",
+        ] {
+            http.get(url).unwrap();
+        }
+
+        let requests = server.requests();
+        let sent = |header: &str| -> Vec<bool> {
+            requests
+                .iter()
+                .map(|request| request.header(header).is_some())
+                .collect()
+        };
+        assert_eq!(sent("authorization"), [true, true, false, false, false]);
+        assert_eq!(sent("x-team"), [false, false, false, false, true]);
+        assert_eq!(requests[0].header("authorization"), Some("Bearer secret"));
+    }
+
+    #[test]
+    fn subdomain_headers_win_over_their_parent() {
+        let config = from_ini(
+            "[http.headers.example.com]\nX-Token = parent\nX-Team = wsb\n\n[http.headers.dl.example.com]\nX-Token = mirror\n",
+        )
+        .unwrap();
+        let server = StubServer::start(|_| stub::response(200, &[], b""));
+        let http = client(&server, config);
+        for url in [
+            "https://files.dl.example.com/a",
+            "https://dl.example.com/a",
+            "https://www.example.com/a",
+        ] {
+            http.get(url).unwrap();
+        }
+
+        let requests = server.requests();
+        let tokens: Vec<Option<&str>> = requests
+            .iter()
+            .map(|request| request.header("x-token"))
+            .collect();
+        assert_eq!(tokens, [Some("mirror"), Some("mirror"), Some("parent")]);
+        assert!(
+            requests
+                .iter()
+                .all(|request| request.header("x-team") == Some("wsb"))
+        );
+    }
+
+    #[test]
+    fn from_ini_reads_settings() {
+        let config = from_ini(
+            "[http]\nuser_agent = wsb\ntimeout = 5\nretries = 4\nretry_delay = 0\nmax_redirects = 3\n",
+        )
+        .unwrap();
+        assert_eq!(config.user_agent, "wsb");
+        assert_eq!(config.timeout, Duration::from_secs(5));
+        assert_eq!(config.retries, 4);
+        assert_eq!(config.retry_delay, Duration::ZERO);
+        assert_eq!(config.max_redirects, 3);
+        assert_eq!(config.connect_timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn from_ini_rejects_bad_values() {
+        for (setting, key) in [
+            ("timeout = soon", "timeout"),
+            ("connect_timeout = 1.5", "connect_timeout"),
+            ("retries = -1", "retries"),
+            ("retry_delay =", "retry_delay"),
+            ("max_redirects = ten", "max_redirects"),
+        ] {
+            let e = from_ini(&format!("[http]\n{setting}\n")).unwrap_err();
+            assert!(
+                e.contains(&format!("[http] {key} must be a whole number")),
+                "{e}"
+            );
+        }
+        // Would wrap around as a u32
+        let e = from_ini("[http]\nretries = 4294967297\n").unwrap_err();
+        assert_eq!(e, "[http] retries is too large, found \"4294967297\"");
+    }
+}
@@ -0,0 +1,523 @@
+use crate::checksum;
+use crate::http::HttpClient;
+use crate::resolver::{LinkResolver, ResolveError, ResolvedDownload};
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::cmp::Ordering;
+use url::Url;
+use urlencoding::decode;
+
+// How many of the newest matching directories are opened when looking for file_pattern,
+// a release directory is often created a while before its installers are uploaded
+const MAX_DIRECTORIES_TRIED: usize = 3;
+
+// One link of a directory listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    // Last path segment, percent-decoded and without the trailing '/' of directories
+    pub name: String,
+    pub url: String,
+    pub is_dir: bool,
+}
+
+// Reads the entries of an Apache, nginx or IIS autoindex page. Only links pointing below base_url are kept,
+// which drops the parent directory, the column sort links (?C=N;O=D) and anything the page links to elsewhere.
+pub fn parse_index(html: &str, base_url: &str) -> Result<Vec<IndexEntry>, ResolveError> {
+    let base = Url::parse(base_url)
+        .map_err(|e| ResolveError::Parse(format!("Invalid index url {base_url}: {e}")))?;
+    let selector = Selector::parse("a[href]")
+        .map_err(|e| ResolveError::Parse(format!("Invalid selector a[href]: {e:?}")))?;
+    let html = Html::parse_document(html);
+
+    let mut entries: Vec<IndexEntry> = Vec::new();
+    for href in html
+        .select(&selector)
+        .filter_map(|element| element.value().attr("href"))
+    {
+        if href.starts_with('?') || href.starts_with('#') {
+            continue;
+        }
+        let Ok(mut url) = base.join(href) else {
+            continue;
+        };
+        url.set_fragment(None);
+        if url.query().is_some()
+            || url.host_str() != base.host_str()
+            || !url.path().starts_with(base.path())
+            || url.path().len() <= base.path().len()
+        {
+            continue;
+        }
+
+        let relative = &url.path()[base.path().len()..];
+        let is_dir = relative.ends_with('/');
+        let segment = relative.trim_end_matches('/');
+        // Only direct children, IIS links nested paths from its breadcrumbs
+        if segment.is_empty() || segment.contains('/') {
+            continue;
+        }
+        let name = match decode(segment) {
+            Ok(decoded) => decoded.to_string(),
+            Err(_) => segment.to_string(),
+        };
+        let entry = IndexEntry {
+            name,
+            url: url.to_string(),
+            is_dir,
+        };
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Text(String),
+}
+
+fn tokenize(version: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = version.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Number(digits.parse().unwrap_or(u64::MAX)));
+        } else if c.is_alphabetic() {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphabetic()) {
+                text.push(c.to_ascii_lowercase());
+                chars.next();
+            }
+            tokens.push(Token::Text(text));
+        } else {
+            chars.next(); // Separators (. - _ +) only split tokens
+        }
+    }
+    tokens
+}
+
+// Compares versions number by number, so 3.10 is newer than 3.9 and build 1000 newer than build 999.
+// Letters mark a prerelease: 3.13.0rc1 sorts below 3.13.0 but above 3.12.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (tokenize(a), tokenize(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(i), b.get(i)) {
+            (Some(Token::Number(x)), Some(Token::Number(y))) => x.cmp(y),
+            (Some(Token::Text(x)), Some(Token::Text(y))) => x.cmp(y),
+            (Some(Token::Number(_)), Some(Token::Text(_))) => Ordering::Greater,
+            (Some(Token::Text(_)), Some(Token::Number(_))) => Ordering::Less,
+            // 3.13.0 vs 3.13.0rc1: the release is newer, 3.13 vs 3.13.1: the longer one is
+            (None, Some(Token::Text(_))) | (Some(Token::Number(_)), None) => Ordering::Greater,
+            (Some(Token::Text(_)), None) | (None, Some(Token::Number(_))) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn is_prerelease(version: &str) -> bool {
+    tokenize(version)
+        .iter()
+        .any(|token| matches!(token, Token::Text(_)))
+}
+
+// Picks the newest entry of a directory listing, catalog entries use it with resolver = "index", url (the listing)
+// and version_pattern. The matched entry is the download unless the entry also sets one of:
+//   file_pattern - the matched entry is a directory, the download is the newest file in it matching file_pattern
+//   url_template - the download url is the template with {version} replaced
+#[derive(Debug, Clone)]
+pub struct DirectoryIndexResolver {
+    pub url: String,
+    // Matched against entry names, the first capture group (or the whole match when there is none) is the version
+    pub version_pattern: String,
+    pub file_pattern: Option<String>,
+    pub url_template: Option<String>,
+    // Versions containing letters (rc1, b2, alpha) are skipped unless this is set
+    pub prerelease: bool,
+    // Checksum file published with the download, {url} is replaced with the download url (e.g. "{url}.sha256")
+    pub sha256_url: Option<String>,
+}
+
+impl LinkResolver for DirectoryIndexResolver {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        let resolved = self.newest(http)?;
+        let Some(sha256_url) = &self.sha256_url else {
+            return Ok(resolved);
+        };
+        let checksum_url = sha256_url.replace("{url}", &resolved.url);
+        let filename = match &resolved.filename {
+            Some(filename) => filename.clone(),
+            None => decode(resolved.url.rsplit('/').next().unwrap_or_default())
+                .map(|name| name.to_string())
+                .unwrap_or_default(),
+        };
+        let sha256 = checksum::fetch_sha256_from(http, &checksum_url, &filename);
+        Ok(checksum::attach_sha256(resolved, sha256))
+    }
+}
+
+impl DirectoryIndexResolver {
+    fn newest(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        let base_url = with_trailing_slash(&self.url);
+        let (_, html) = http.get_text(&base_url)?;
+        let entries = parse_index(&html, &base_url)?;
+        let candidates = matching(&entries, &self.version_pattern, self.prerelease)?;
+
+        if let Some(template) = &self.url_template {
+            let (version, _) = candidates.into_iter().next().ok_or_else(|| self.miss())?;
+            let url = template.replace("{version}", &version);
+            return Ok(ResolvedDownload::direct(url).with_version(version));
+        }
+
+        let Some(file_pattern) = &self.file_pattern else {
+            let (version, entry) = candidates.into_iter().next().ok_or_else(|| self.miss())?;
+            return Ok(ResolvedDownload::direct(entry.url)
+                .with_version(version)
+                .with_filename(entry.name));
+        };
+
+        let mut last_error: Option<ResolveError> = None;
+        for (version, directory) in candidates
+            .into_iter()
+            .filter(|(_, entry)| entry.is_dir)
+            .take(MAX_DIRECTORIES_TRIED)
+        {
+            let pattern = file_pattern.replace("{version}", &regex::escape(&version));
+            // A directory a mirror is still syncing can be missing or forbidden, the older ones are tried instead
+            let files = match http
+                .get_text(&directory.url)
+                .map_err(ResolveError::from)
+                .and_then(|(_, html)| parse_index(&html, &directory.url))
+            {
+                Ok(files) => files,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            // The version was already checked on the directory, the file pattern rarely captures one
+            if let Some((_, file)) = matching(&files, &pattern, true)?
+                .into_iter()
+                .find(|(_, entry)| !entry.is_dir)
+            {
+                return Ok(ResolvedDownload::direct(file.url)
+                    .with_version(version)
+                    .with_filename(file.name));
+            }
+        }
+        let mut message = format!(
+            "No directory of {} matching {} holds a file matching {file_pattern}",
+            self.url, self.version_pattern
+        );
+        if let Some(e) = last_error {
+            message.push_str(&format!(
+                ", the last one that couldn't be listed failed with {e}"
+            ));
+        }
+        Err(ResolveError::SelectorMiss(message))
+    }
+
+    fn miss(&self) -> ResolveError {
+        ResolveError::SelectorMiss(format!(
+            "No entry of {} matches {}",
+            self.url, self.version_pattern
+        ))
+    }
+}
+
+// Entries matching the pattern with their version, newest first
+pub fn matching(
+    entries: &[IndexEntry],
+    pattern: &str,
+    prerelease: bool,
+) -> Result<Vec<(String, IndexEntry)>, ResolveError> {
+    let regex = Regex::new(pattern)
+        .map_err(|e| ResolveError::Parse(format!("Invalid index pattern {pattern}: {e}")))?;
+    let mut matches: Vec<(String, IndexEntry)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let captures = regex.captures(&entry.name)?;
+            let version = captures.get(1).or_else(|| captures.get(0))?.as_str();
+            Some((version.to_string(), entry.clone()))
+        })
+        .filter(|(version, _)| prerelease || !is_prerelease(version))
+        .collect();
+    matches.sort_by(|(a, _), (b, _)| compare_versions(b, a));
+    Ok(matches)
+}
+
+// Relative hrefs only resolve below the listing when its url ends with '/'
+fn with_trailing_slash(url: &str) -> String {
+    if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{url}/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::stub::{self, StubServer};
+
+    const VLC: &str = include_str!("../tests/fixtures/index/vlc-apache.html");
+    const OKULAR: &str = include_str!("../tests/fixtures/index/okular-nginx.html");
+    const PYTHON: &str = include_str!("../tests/fixtures/index/python-iis.html");
+    const PYTHON_3_13_1: &str = include_str!("../tests/fixtures/index/python-iis-3.13.1.html");
+    const PYTHON_3_12_9: &str = include_str!("../tests/fixtures/index/python-iis-3.12.9.html");
+    const LIBREOFFICE: &str = include_str!("../tests/fixtures/index/libreoffice-stable.html");
+    const LIBREOFFICE_SHA256: &str =
+        include_str!("../tests/fixtures/index/LibreOffice_25.2.2_Win_x86-64.msi.sha256");
+
+    fn names(entries: &[IndexEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn apache_listing_keeps_only_its_children() {
+        let base = "https://download.videolan.org/pub/videolan/vlc/";
+        let entries = parse_index(VLC, base).unwrap();
+        // No sort links, parent directory or mirror link
+        assert_eq!(
+            names(&entries),
+            [
+                "2.2.8",
+                "3.0.9",
+                "3.0.9.2",
+                "3.0.18",
+                "3.0.20",
+                "3.0.21",
+                "4.0.0-rc1",
+                "last",
+                "README.txt"
+            ]
+        );
+        assert!(entries[0].is_dir);
+        assert_eq!(entries[0].url, format!("{base}2.2.8/"));
+        assert!(!entries[8].is_dir);
+    }
+
+    #[test]
+    fn nginx_listing_names_are_decoded() {
+        let base = "https://cdn.kde.org/ci-builds/graphics/okular/master/windows/";
+        let entries = parse_index(OKULAR, base).unwrap();
+        assert_eq!(entries.len(), 8);
+        assert_eq!(entries[7].name, "okular notes.txt");
+        assert_eq!(entries[7].url, format!("{base}okular%20notes.txt"));
+    }
+
+    // IIS links every entry by absolute path and lists nested directories next to the children
+    #[test]
+    fn iis_listing_skips_breadcrumbs_and_nested_paths() {
+        let entries = parse_index(PYTHON, "https://www.python.org/ftp/python/").unwrap();
+        assert_eq!(
+            names(&entries),
+            [
+                "3.9.13",
+                "3.10.11",
+                "3.12.9",
+                "3.13.1",
+                "3.14.0b1",
+                "index-windows.json"
+            ]
+        );
+        assert_eq!(entries[1].url, "https://www.python.org/ftp/python/3.10.11/");
+    }
+
+    #[test]
+    fn versions_compare_by_number() {
+        assert_eq!(compare_versions("3.10", "3.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1000", "999"), Ordering::Greater);
+        assert_eq!(compare_versions("3.0.21", "3.0.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("3.13.0", "3.13.0rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("3.13.0rc1", "3.12"), Ordering::Greater);
+        assert_eq!(compare_versions("3.13.1", "3.13"), Ordering::Greater);
+        assert_eq!(compare_versions("4.0.0-rc1", "4.0.0rc1"), Ordering::Equal);
+    }
+
+    // Sorted as text 3.0.9 would beat 3.0.21 and build 999 would beat build 1000
+    #[test]
+    fn newest_is_not_the_lexically_last() {
+        let vlc = parse_index(VLC, "https://download.videolan.org/pub/videolan/vlc/").unwrap();
+        let versions: Vec<String> = matching(&vlc, r"^(\d+\.\d+\.\d+)$", false)
+            .unwrap()
+            .into_iter()
+            .map(|(version, _)| version)
+            .collect();
+        assert_eq!(versions, ["3.0.21", "3.0.20", "3.0.18", "3.0.9", "2.2.8"]);
+
+        let okular = parse_index(
+            OKULAR,
+            "https://cdn.kde.org/ci-builds/graphics/okular/master/windows/",
+        )
+        .unwrap();
+        let catalog = Catalog::embedded();
+        let pattern = catalog.get("Okular").unwrap().index_resolver().unwrap();
+        let newest = matching(&okular, &pattern.version_pattern, false).unwrap();
+        assert_eq!(newest.len(), 3);
+        assert_eq!(newest[0].0, "1000");
+        assert_eq!(
+            newest[0].1.name,
+            "okular-master-1000-windows-cl-msvc2022-x86_64.exe"
+        );
+    }
+
+    #[test]
+    fn prereleases_are_skipped_unless_asked_for() {
+        let python = parse_index(PYTHON, "https://www.python.org/ftp/python/").unwrap();
+        let stable = matching(&python, r"^\d[\w.]*$", false).unwrap();
+        assert_eq!(stable[0].0, "3.13.1");
+        let any = matching(&python, r"^\d[\w.]*$", true).unwrap();
+        assert_eq!(any[0].0, "3.14.0b1");
+    }
+
+    #[test]
+    fn vlc_catalog_entry_resolves_from_its_listing() {
+        let server = StubServer::start(|request| match request.path.as_str() {
+            "/pub/videolan/vlc/" => {
+                stub::response(200, &[("Content-Type", "text/html")], VLC.as_bytes())
+            }
+            _ => stub::response(404, &[], b""),
+        });
+        let catalog = Catalog::embedded();
+        let vlc = catalog.get("VLC").unwrap().index_resolver().unwrap();
+        let resolved = vlc.resolve(&server.client()).unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://get.videolan.org/vlc/3.0.21/win32/vlc-3.0.21-win32.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("3.0.21"));
+    }
+
+    #[test]
+    fn libreoffice_catalog_entry_is_verified_against_its_checksum_file() {
+        let server = StubServer::start(|request| {
+            let page = match request.path.as_str() {
+                "/libreoffice/stable/" => LIBREOFFICE,
+                "/libreoffice/stable/25.2.2/win/x86_64/LibreOffice_25.2.2_Win_x86-64.msi.sha256" => {
+                    LIBREOFFICE_SHA256
+                }
+                _ => return stub::response(404, &[], b""),
+            };
+            stub::response(200, &[("Content-Type", "text/html")], page.as_bytes())
+        });
+        let catalog = Catalog::embedded();
+        let libreoffice = catalog
+            .get("LibreOffice")
+            .unwrap()
+            .index_resolver()
+            .unwrap();
+        let resolved = libreoffice.resolve(&server.client()).unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://download.documentfoundation.org/libreoffice/stable/25.2.2/win/x86_64/LibreOffice_25.2.2_Win_x86-64.msi"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("25.2.2"));
+        assert_eq!(
+            resolved.sha256.as_deref(),
+            LIBREOFFICE_SHA256.split_whitespace().next()
+        );
+    }
+
+    // 3.13.1 has no installer yet, the next newest directory holding one is used and the beta is never opened
+    #[test]
+    fn file_pattern_looks_into_the_newest_directories() {
+        let server = StubServer::start(|request| {
+            let page = match request.path.as_str() {
+                "/ftp/python/" => PYTHON,
+                "/ftp/python/3.13.1/" => PYTHON_3_13_1,
+                "/ftp/python/3.12.9/" => PYTHON_3_12_9,
+                _ => return stub::response(404, &[], b""),
+            };
+            stub::response(200, &[("Content-Type", "text/html")], page.as_bytes())
+        });
+        let python = DirectoryIndexResolver {
+            url: server.url("/ftp/python"),
+            version_pattern: String::from(r"^(\d+\.\d+\.\d+)$"),
+            file_pattern: Some(String::from(r"^python-{version}-amd64\.exe$")),
+            url_template: None,
+            prerelease: false,
+            sha256_url: None,
+        };
+        let resolved = python.resolve(&server.client()).unwrap();
+        assert_eq!(
+            resolved.url,
+            server.url("/ftp/python/3.12.9/python-3.12.9-amd64.exe")
+        );
+        assert_eq!(resolved.version.as_deref(), Some("3.12.9"));
+        assert_eq!(
+            resolved.filename.as_deref(),
+            Some("python-3.12.9-amd64.exe")
+        );
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            ["/ftp/python/", "/ftp/python/3.13.1/", "/ftp/python/3.12.9/"]
+        );
+    }
+
+    #[test]
+    fn no_directory_with_the_file_is_a_selector_miss() {
+        let server = StubServer::start(|request| {
+            let page = match request.path.as_str() {
+                "/ftp/python/" => PYTHON,
+                _ => PYTHON_3_13_1,
+            };
+            stub::response(200, &[("Content-Type", "text/html")], page.as_bytes())
+        });
+        let python = DirectoryIndexResolver {
+            url: server.url("/ftp/python/"),
+            version_pattern: String::from(r"^(\d+\.\d+\.\d+)$"),
+            file_pattern: Some(String::from(r"^python-{version}-arm64\.exe$")),
+            url_template: None,
+            prerelease: false,
+            sha256_url: None,
+        };
+        let e = python.resolve(&server.client()).unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+        // Only the three newest directories are opened
+        assert_eq!(server.requests().len(), 1 + MAX_DIRECTORIES_TRIED);
+    }
+
+    // The newest directory isn't on the mirror yet, the next one still holds the installer
+    #[test]
+    fn unreadable_directories_are_skipped() {
+        let server = StubServer::start(|request| {
+            let page = match request.path.as_str() {
+                "/ftp/python/" => PYTHON,
+                "/ftp/python/3.12.9/" => PYTHON_3_12_9,
+                _ => return stub::response(404, &[], b""),
+            };
+            stub::response(200, &[("Content-Type", "text/html")], page.as_bytes())
+        });
+        let mut python = DirectoryIndexResolver {
+            url: server.url("/ftp/python/"),
+            version_pattern: String::from(r"^(\d+\.\d+\.\d+)$"),
+            file_pattern: Some(String::from(r"^python-{version}-amd64\.exe$")),
+            url_template: None,
+            prerelease: false,
+            sha256_url: None,
+        };
+        let resolved = python.resolve(&server.client()).unwrap();
+        assert_eq!(
+            resolved.url,
+            server.url("/ftp/python/3.12.9/python-3.12.9-amd64.exe")
+        );
+
+        python.file_pattern = Some(String::from(r"^python-{version}-arm64\.exe$"));
+        let e = python.resolve(&server.client()).unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+        assert!(e.to_string().contains("404"), "{e}");
+    }
+}
@@ -0,0 +1,199 @@
+use crate::downloader;
+use crate::http::HttpClient;
+use crate::resolver::{ResolutionKind, ResolvedDownload};
+use crate::sniff::FileType;
+use reqwest::header::LAST_MODIFIED;
+use serde::Serialize;
+use std::error::Error;
+
+// What `wsb info` reports about an app, gathered without downloading it
+#[derive(Debug, Serialize)]
+pub struct AppInfo {
+    pub app: String,
+    // direct, helper (DU_CLI.exe) or manual
+    pub kind: &'static str,
+    // The url the resolver returned
+    pub url: String,
+    pub redirects: Vec<String>,
+    pub final_url: Option<String>,
+    pub filename: Option<String>,
+    pub content_length: Option<u64>,
+    pub last_modified: Option<String>,
+    pub version: Option<String>,
+    pub installer_type: Option<FileType>,
+    // Published by the vendor or pinned in the catalog, the download is checked against it
+    pub sha256: Option<String>,
+    // The server rejected HEAD, the headers come from a GET of the first byte
+    pub ranged_get: bool,
+}
+
+// Probes the resolved url with the same headers the download would send (see HttpClient::probe).
+// Helper and manual downloads aren't probed, wsb never fetches those itself.
+pub fn probe(
+    http: &HttpClient,
+    app: &str,
+    resolved: &ResolvedDownload,
+) -> Result<AppInfo, Box<dyn Error>> {
+    let kind = match resolved.kind {
+        ResolutionKind::Direct => "direct",
+        ResolutionKind::ExternalHelper => "helper",
+        ResolutionKind::Manual => "manual",
+    };
+    let mut info = AppInfo {
+        app: app.to_string(),
+        kind,
+        url: resolved.url.clone(),
+        redirects: Vec::new(),
+        final_url: None,
+        filename: resolved.filename.clone(),
+        content_length: None,
+        last_modified: None,
+        version: resolved.version.clone(),
+        installer_type: None,
+        sha256: resolved.sha256.clone(),
+        ranged_get: false,
+    };
+
+    if resolved.kind == ResolutionKind::Direct {
+        let probe = http.probe(&resolved.url, downloader::request_headers(resolved)?)?;
+        info.filename = downloader::response_filename(resolved, &probe.headers, &probe.url);
+        info.content_length = probe.content_length();
+        info.last_modified = probe.header(LAST_MODIFIED).map(String::from);
+        info.ranged_get = probe.ranged_get;
+        info.redirects = probe.redirects;
+        info.final_url = Some(probe.url);
+    }
+    // The same rule the downloader checks the file against
+    info.installer_type = resolved
+        .file_type
+        .or_else(|| info.filename.as_deref().and_then(FileType::from_extension));
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stub::{self, StubServer};
+
+    const LENGTH: usize = 46137344;
+
+    // /start -> 302 to another host -> 302 relative -> the installer. /head-rejected answers HEAD with 405 like some CDNs.
+    fn vendor() -> StubServer {
+        StubServer::start(
+            |request| match (request.method.as_str(), request.path.as_str()) {
+                (_, "/start") => {
+                    stub::response(302, &[("Location", "https://mirror.example/mid")], b"")
+                }
+                (_, "/mid") => stub::response(302, &[("Location", "/files/app-1.2.exe")], b""),
+                ("HEAD", "/files/app-1.2.exe") => stub::truncated_response(
+                    200,
+                    &[("Last-Modified", "Tue, 04 Feb 2025 12:00:00 GMT")],
+                    b"",
+                    LENGTH,
+                ),
+                ("HEAD", "/head-rejected") => stub::response(405, &[], b""),
+                ("GET", "/head-rejected") if request.header("range") == Some("bytes=0-0") => {
+                    stub::response(
+                        206,
+                        &[
+                            ("Content-Range", &format!("bytes 0-0/{LENGTH}")),
+                            (
+                                "Content-Disposition",
+                                "attachment; filename=\"Setup 2.0.msi\"",
+                            ),
+                        ],
+                        b"\xd0",
+                    )
+                }
+                _ => stub::response(404, &[], b""),
+            },
+        )
+    }
+
+    #[test]
+    fn redirects_are_recorded_in_order() {
+        let server = vendor();
+        let resolved = ResolvedDownload::direct("https://example.com/start").with_version("1.2");
+        let info = probe(&server.client(), "App", &resolved).unwrap();
+        assert_eq!(
+            info.redirects,
+            ["https://example.com/start", "https://mirror.example/mid"]
+        );
+        assert_eq!(
+            info.final_url.as_deref(),
+            Some("https://mirror.example/files/app-1.2.exe")
+        );
+        assert_eq!(info.filename.as_deref(), Some("app-1.2.exe"));
+        assert_eq!(info.installer_type, Some(FileType::Exe));
+        assert_eq!(info.content_length, Some(LENGTH as u64));
+        assert_eq!(
+            info.last_modified.as_deref(),
+            Some("Tue, 04 Feb 2025 12:00:00 GMT")
+        );
+        assert!(!info.ranged_get);
+        // Every hop was answered by HEAD, nothing was downloaded
+        assert!(server.requests().iter().all(|r| r.method == "HEAD"));
+    }
+
+    #[test]
+    fn rejected_head_falls_back_to_a_ranged_get() {
+        let server = vendor();
+        let resolved = ResolvedDownload::direct("https://example.com/head-rejected");
+        let info = probe(&server.client(), "App", &resolved).unwrap();
+        assert!(info.ranged_get);
+        // The whole file's size from Content-Range, not the 1 byte Content-Length
+        assert_eq!(info.content_length, Some(LENGTH as u64));
+        assert_eq!(info.filename.as_deref(), Some("Setup 2.0.msi"));
+        assert_eq!(info.installer_type, Some(FileType::Msi));
+        assert!(info.redirects.is_empty());
+        let methods: Vec<String> = server.requests().into_iter().map(|r| r.method).collect();
+        assert_eq!(methods, ["HEAD", "GET"]);
+    }
+
+    #[test]
+    fn redirect_loops_and_missing_files_fail() {
+        let server = StubServer::start(|request| match request.path.as_str() {
+            "/loop" => stub::response(302, &[("Location", "/loop")], b""),
+            _ => stub::response(404, &[], b""),
+        });
+        let http = server.client();
+        let e = probe(
+            &http,
+            "App",
+            &ResolvedDownload::direct("https://example.com/loop"),
+        )
+        .unwrap_err();
+        assert!(
+            e.to_string().contains("redirected more than 10 times"),
+            "{e}"
+        );
+        let e = probe(
+            &http,
+            "App",
+            &ResolvedDownload::direct("https://example.com/gone"),
+        )
+        .unwrap_err();
+        assert!(e.to_string().contains("404"), "{e}");
+    }
+
+    #[test]
+    fn helper_and_manual_downloads_are_not_probed() {
+        let server = vendor();
+        let info = probe(
+            &server.client(),
+            "Vivaldi",
+            &ResolvedDownload::external_helper("Vivaldi"),
+        )
+        .unwrap();
+        assert_eq!(info.kind, "helper");
+        assert_eq!(info.final_url, None);
+        let info = probe(
+            &server.client(),
+            "App",
+            &ResolvedDownload::manual("https://example.com/login"),
+        )
+        .unwrap();
+        assert_eq!(info.kind, "manual");
+        assert!(server.requests().is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::catalog::{Catalog, Category};
+use serde_json::Value;
+use std::error::Error;
+use std::process::{self, Command};
+use std::{env, fs};
+
+// Appended after the app sections, every setting commented out at its default or an example value
+const SETTINGS_TEMPLATE: &str = "\
+; Optional network settings, timeouts and retry_delay are in seconds, user_agent defaults to a desktop browser's
+; [http]
+; user_agent = wsb
+; timeout = 120
+; connect_timeout = 30
+; proxy = http://proxy.example.com:8080
+; ca_bundle = C:\\certs\\corporate-root.pem
+; max_redirects = 10
+; retries = 2
+; retry_delay = 2
+
+; Optional headers sent to a host and its subdomains, one section per host
+; [http.headers.example.com]
+; X-Api-Key = ...
+
+; Optional download limits: vendor pages scraped at the same time (resolve_jobs), downloads running at the same time
+; (jobs) and against a single host (per_host), progress is bars, json (one event per line on stdout) or none
+; [download]
+; resolve_jobs = 8
+; jobs = 4
+; per_host = 2
+; progress = bars
+
+; Optional GitHub API token for the github: resolvers, WSB_GITHUB_TOKEN or GITHUB_TOKEN work as well
+; [github]
+; token = ghp_...
+
+; Optional external helper settings, see docs/helper-protocol.md
+; [helper]
+; path = DU_CLI.exe
+; timeout = 600
+";
+
+// config.ini with every catalog app in its category's section, set to false unless its key is in enabled.
+// Each key is preceded by a comment with the app's display name, homepage and anything worth knowing before downloading it.
+pub fn render(catalog: &Catalog, enabled: &[String]) -> String {
+    let mut config = String::from(
+        "; wsb configuration, written by `wsb init`\n\
+         ; Set an app to true to have `wsb download` fetch it, `wsb config check` reports typos and invalid values.\n\
+         ; `wsb list` shows every app, `wsb info <app>` what a download would be.\n\
+         ; A line `include = base.ini` above the first section reads a shared file first, the values here override it.\n\
+         ; Options go in a table named after the app, [browser.Firefox] with channel = esr, the comment above each app\n\
+         ; lists the ones it takes besides enabled and output_name (the file name to save the download as).\n",
+    );
+    for category in Category::ALL {
+        config.push_str(&format!("\n[{category}]\n"));
+        for (i, entry) in catalog.in_category(category).enumerate() {
+            if i > 0 {
+                config.push('\n');
+            }
+            match &entry.homepage {
+                Some(homepage) => config.push_str(&format!("; {} - {homepage}\n", entry.name)),
+                None => config.push_str(&format!("; {}\n", entry.name)),
+            }
+            if let Some(notes) = &entry.notes {
+                config.push_str(&format!("; Note: {notes}\n"));
+            }
+            if entry.needs_helper() {
+                config.push_str("; Note: downloaded by DU_CLI.exe, see docs/helper-protocol.md\n");
+            }
+            let options: Vec<&str> = entry
+                .supported_options()
+                .iter()
+                .copied()
+                .filter(|option| !["enabled", "output_name"].contains(option))
+                .collect();
+            if !options.is_empty() {
+                config.push_str(&format!("; Options: {}\n", options.join(", ")));
+            }
+            if let Some(hint) = entry.value_hint() {
+                config.push_str(&format!(
+                    "; Value: {hint}, comma separated for several side by side\n"
+                ));
+            }
+            let value = enabled.contains(&entry.key);
+            config.push_str(&format!("{} = {value}\n", entry.key));
+        }
+    }
+    config.push('\n');
+    config.push_str(SETTINGS_TEMPLATE);
+    config
+}
+
+// Config keys of the catalog apps winget reports as installed, for `wsb init --from-installed`
+pub fn installed_apps(catalog: &Catalog) -> Result<Vec<String>, Box<dyn Error>> {
+    let packages = installed_winget_packages()?;
+    Ok(catalog
+        .entries()
+        .filter(|entry| packages.iter().any(|id| entry.is_winget_package(id)))
+        .map(|entry| entry.key.clone())
+        .collect())
+}
+
+// Package identifiers winget knows are installed. `winget export` is used because `winget list` prints a table whose
+// headers and column widths depend on the locale and the terminal.
+fn installed_winget_packages() -> Result<Vec<String>, Box<dyn Error>> {
+    let path = env::temp_dir().join(format!("wsb-winget-export-{}.json", process::id()));
+    let output = Command::new("winget")
+        .arg("export")
+        .arg("--output")
+        .arg(&path)
+        .arg("--accept-source-agreements")
+        .output()
+        .map_err(|e| {
+            format!(
+                "winget isn't available ({e}), --from-installed reads the installed apps from it"
+            )
+        })?;
+    // winget exits with an error when some installed packages have no source, the file is written regardless
+    let contents = fs::read_to_string(&path).map_err(|_| {
+        format!(
+            "winget export failed: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        )
+    })?;
+    let _ = fs::remove_file(&path);
+
+    let export: Value = serde_json::from_str(contents.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Invalid winget export: {e}"))?;
+    Ok(export["Sources"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice)
+        .iter()
+        .flat_map(|source| source["Packages"].as_array().map_or(&[][..], Vec::as_slice))
+        .filter_map(|package| package["PackageIdentifier"].as_str())
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::{self, Severity};
+    use ini::{Ini, ParseOption};
+
+    #[test]
+    fn every_app_is_listed_once_in_its_section() {
+        let catalog = Catalog::embedded();
+        let enabled = vec!["Firefox".to_string(), "Python".to_string()];
+        let config = Ini::load_from_str_opt(
+            &render(&catalog, &enabled),
+            ParseOption {
+                enabled_escape: false,
+                ..ParseOption::default()
+            },
+        )
+        .unwrap();
+
+        for category in Category::ALL {
+            let section = config.section(Some(category.to_string())).unwrap();
+            let keys: Vec<&str> = section.iter().map(|(key, _)| key).collect();
+            let expected: Vec<&str> = catalog
+                .in_category(category)
+                .map(|entry| entry.key.as_str())
+                .collect();
+            assert_eq!(keys, expected);
+            for (key, value) in section.iter() {
+                let expected = if enabled.iter().any(|e| e == key) {
+                    "true"
+                } else {
+                    "false"
+                };
+                assert_eq!(value, expected, "{key}");
+            }
+        }
+        let errors: Vec<String> = lint::check(&catalog, &config)
+            .into_iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .map(|finding| finding.to_string())
+            .collect();
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    // The generated file is the only reference most users have for these sections
+    #[test]
+    fn every_setting_is_listed() {
+        for (section, keys) in lint::SETTINGS_SECTIONS {
+            assert!(
+                SETTINGS_TEMPLATE.contains(&format!("; [{section}]\n")),
+                "{section}"
+            );
+            for key in keys {
+                assert!(
+                    SETTINGS_TEMPLATE.contains(&format!("\n; {key} = ")),
+                    "[{section}] {key}"
+                );
+            }
+        }
+    }
+}
@@ -1,17 +1,24 @@
-use crate::browser::Browser;
+use crate::browser::{Browser, FirefoxChannel, FirefoxRelease};
+use crate::catalog::{Catalog, CatalogEntry, Category, ResolverKind};
+use crate::checksum;
+use crate::feed::{self, FeedResolver};
 use crate::gaming::Gaming;
+use crate::github::GitHubResolver;
+use crate::http::HttpClient;
+use crate::index::DirectoryIndexResolver;
+use crate::node::{self, NodeResolver, NodeSelector, NodeVariant};
+use crate::options::{AppOptions, Variant};
 use crate::programming::{FilezillaType, Programming};
+use crate::python::{self, PythonResolver, VersionSpec};
+use crate::resolver::{Arch, LinkResolver, ResolveError, ResolvedDownload};
+use crate::sniff::FileType;
 use crate::system::Utility;
 
+use regex::Regex;
 use reqwest::StatusCode;
-use reqwest::blocking::{Client, Response, get};
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 use scraper::{Html, Selector};
-use serde_json::{Value, from_value};
-use std::error::Error;
-use std::process::exit;
+use serde_json::Value;
 use tl::{ParserOptions, parse};
-use xmltojson::to_json;
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -20,251 +27,346 @@ pub enum KeyType {
     Gaming(Gaming),
     Programming(Programming),
     Utility(Utility),
-}
+    Direct(String),
+    // App name passed to DU_CLI.exe
+    ExternalHelper(String),
+    GitHub(GitHubResolver),
+    Feed(FeedResolver),
+    Index(DirectoryIndexResolver),
+    Python(PythonResolver),
+    Node(NodeResolver),
+    // Any of the above with values set on its catalog entry, they take priority over what the resolver found
+    Overridden(Box<KeyType>, EntryOverrides),
+}
+
+// The resolvers of one app, labelled with the version they were asked for when versions were given
+pub type LabelledResolvers = Vec<(Option<String>, KeyType)>;
+
+// Values a catalog entry can force onto the resolved download
+#[derive(Debug, Clone, Default)]
+pub struct EntryOverrides {
+    pub sha256: Option<String>,
+    pub filename: Option<String>,
+    pub file_type: Option<FileType>,
+}
+
+impl EntryOverrides {
+    fn from_entry(entry: &CatalogEntry) -> Option<EntryOverrides> {
+        if entry.sha256.is_none() && entry.filename.is_none() && entry.file_type.is_none() {
+            return None;
+        }
+        Some(EntryOverrides {
+            sha256: entry.sha256.clone(),
+            filename: entry.filename.clone(),
+            file_type: entry.file_type,
+        })
+    }
 
-impl KeyType {
-    pub fn get_download_link(&self) -> Option<String> {
-        match self {
-            KeyType::Browser(b) => b.get_link(),
-            KeyType::Gaming(g) => g.get_link(),
-            KeyType::Programming(p) => p.get_link(),
-            KeyType::Utility(u) => u.get_link(),
+    fn apply(&self, mut resolved: ResolvedDownload) -> ResolvedDownload {
+        if let Some(sha256) = &self.sha256 {
+            resolved = resolved.with_sha256(sha256.clone());
         }
+        if let Some(filename) = &self.filename {
+            resolved = resolved.with_filename(filename.clone());
+        }
+        if let Some(file_type) = self.file_type {
+            resolved = resolved.with_file_type(file_type);
+        }
+        resolved
     }
 }
 
-fn make_web_request(url: &str) -> Result<(StatusCode, String), Box<dyn Error>> {
-    let response: Response = get(url)?;
-    let status: StatusCode = response.status();
-    if status.is_success() {
-        let content: String = response.text()?;
-        Ok((status, content))
-    } else {
-        let status: StatusCode = response.status();
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Request failed with status: {}", status),
-        )))
+impl LinkResolver for KeyType {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        match self {
+            KeyType::Browser(b) => b.resolve(http),
+            KeyType::Gaming(g) => g.resolve(http),
+            KeyType::Programming(p) => p.resolve(http),
+            KeyType::Utility(u) => u.resolve(http),
+            KeyType::Direct(url) => Ok(ResolvedDownload::direct(url.clone())),
+            KeyType::ExternalHelper(app) => Ok(ResolvedDownload::external_helper(app.clone())),
+            KeyType::GitHub(resolver) => resolver.resolve(http),
+            KeyType::Feed(resolver) => resolver.resolve(http),
+            KeyType::Index(resolver) => resolver.resolve(http),
+            KeyType::Python(resolver) => resolver.resolve(http),
+            KeyType::Node(resolver) => resolver.resolve(http),
+            KeyType::Overridden(key_type, overrides) => {
+                Ok(overrides.apply(key_type.resolve(http)?))
+            }
+        }
     }
 }
 
-pub fn make_web_request_v2(url: &str) -> Result<(StatusCode, String, HeaderMap), Box<dyn Error>> {
-    let client = Client::new();
-
-    let mut headers = HeaderMap::new();
-    headers.insert(USER_AGENT, HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36"));
+// Selector::parse's error borrows the selector string, so it's converted to an owned ResolveError here
+fn parse_selector(selector: &str) -> Result<Selector, ResolveError> {
+    Selector::parse(selector)
+        .map_err(|e| ResolveError::Parse(format!("Invalid selector {selector}: {e:?}")))
+}
+
+// Resolves a config.ini key to its resolver through the catalog, returns None for unknown keys
+pub fn map_config_key_to_function_name(catalog: &Catalog, config_key: &str) -> Option<KeyType> {
+    let entry: &CatalogEntry = catalog.get(config_key)?;
+    let key_type = match entry.resolver_kind()? {
+        ResolverKind::Direct => entry.url.clone().map(KeyType::Direct),
+        ResolverKind::Helper(app) => Some(KeyType::ExternalHelper(app)),
+        ResolverKind::GitHub(_) => entry.github_resolver().ok().map(KeyType::GitHub),
+        ResolverKind::Feed => entry.feed_resolver().ok().map(KeyType::Feed),
+        ResolverKind::Index => entry.index_resolver().ok().map(KeyType::Index),
+        ResolverKind::Python => Some(KeyType::Python(PythonResolver {
+            spec: VersionSpec::Latest,
+            arch: entry.arch().ok()?.unwrap_or(Arch::X64),
+        })),
+        ResolverKind::Node => Some(KeyType::Node(NodeResolver {
+            selector: NodeSelector::Lts,
+            variant: NodeVariant::Msi,
+            arch: entry.arch().ok()?.unwrap_or(Arch::X64),
+        })),
+        ResolverKind::Builtin => {
+            let name: &str = entry.builtin_name();
+            match entry.category {
+                Category::Browser => Browser::from_name(name).map(KeyType::Browser),
+                Category::Gaming => Gaming::from_name(name).map(KeyType::Gaming),
+                Category::Programming => Programming::from_name(name).map(KeyType::Programming),
+                Category::System => Utility::from_name(name).map(KeyType::Utility),
+            }
+        }
+    }?;
+    Some(with_overrides(entry, key_type))
+}
 
-    let response = client.get(url).headers(headers).send()?;
-    let status = response.status();
-    let resp_headers = response.headers().clone();
-    if status.is_success() {
-        let body = response.text()?;
-        Ok((status, body, resp_headers))
-    } else {
-        let status: StatusCode = response.status();
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Request failed with status {}, please try again", status),
-        )))
+fn with_overrides(entry: &CatalogEntry, key_type: KeyType) -> KeyType {
+    match EntryOverrides::from_entry(entry) {
+        Some(overrides) => KeyType::Overridden(Box::new(key_type), overrides),
+        None => key_type,
     }
 }
 
-#[allow(dead_code)]
-pub fn map_config_key_to_function_name(config_key: &str) -> KeyType {
-    match config_key {
-        "Brave" => KeyType::Browser(Browser::Brave),
-        "Chrome" => KeyType::Browser(Browser::Chrome),
-        "Chromium" => KeyType::Browser(Browser::Chromium),
-        "Edge" => KeyType::Browser(Browser::Edge),
-        "Firefox" => KeyType::Browser(Browser::Firefox),
-        "Librewolf" => KeyType::Browser(Browser::Librewolf),
-        "Opera" => KeyType::Browser(Browser::Opera),
-        "OperaGX" => KeyType::Browser(Browser::OperaGX),
-        "PaleMoon" => KeyType::Browser(Browser::PaleMoon),
-        "Seamonkey-Experimental" => KeyType::Browser(Browser::SeaMonkey),
-        "Tor" => KeyType::Browser(Browser::Tor),
-        "Vivaldi-Experimental" => KeyType::Browser(Browser::Vivaldi),
-        "Waterfox" => KeyType::Browser(Browser::Waterfox),
-        "AMDAutoDetect" => KeyType::Gaming(Gaming::AMDAutoDetect),
-        "BattleNetLauncher" => KeyType::Gaming(Gaming::BattleNetLauncher),
-        "BluestacksEmulator" => KeyType::Gaming(Gaming::BluestacksEmulator),
-        "CPUZ" => KeyType::Gaming(Gaming::CPUZ),
-        "CurseForge" => KeyType::Gaming(Gaming::CurseForge),
-        "EpicGamesLauncher" => KeyType::Gaming(Gaming::EpicGamesLauncher),
-        "GPUZ" => KeyType::Gaming(Gaming::GPUZ),
-        "HWiNFO" => KeyType::Gaming(Gaming::HWiNFO),
-        "HWMonitor" => KeyType::Gaming(Gaming::HWMonitor),
-        "MSIAfterburner" => KeyType::Gaming(Gaming::MSIAfterburner),
-        "NvidiaApp" => KeyType::Gaming(Gaming::NvidiaApp),
-        "OBSStudio" => KeyType::Gaming(Gaming::OBSStudio),
-        "Parsec" => KeyType::Gaming(Gaming::Parsec),
-        "PingPlotter" => KeyType::Gaming(Gaming::PingPlotter),
-        "ProcessLasso" => KeyType::Gaming(Gaming::ProcessLasso),
-        "RazerCortex" => KeyType::Gaming(Gaming::RazerCortex),
-        "Reshade" => KeyType::Gaming(Gaming::Reshade),
-        "RockstarLauncher" => KeyType::Gaming(Gaming::RockstarLauncher),
-        "RobloxLauncher" => KeyType::Gaming(Gaming::RobloxLauncher),
-        "StreamlabsOBS" => KeyType::Gaming(Gaming::StreamlabsOBS),
-        "SteamLauncher" => KeyType::Gaming(Gaming::SteamLauncher),
-        "WTFast" => KeyType::Gaming(Gaming::WTFast),
-        "AndroidStudio" => KeyType::Programming(Programming::AndroidStudio),
-        "AzureDataStudio" => KeyType::Programming(Programming::AzureDataStudio),
-        "BurpSuite" => KeyType::Programming(Programming::BurpSuite),
-        "DockerDesktop" => KeyType::Programming(Programming::DockerDesktop),
-        "FileZillaClient" => KeyType::Programming(Programming::FileZilla(FilezillaType::Client)),
-        "FileZillaServer" => KeyType::Programming(Programming::FileZilla(FilezillaType::Server)),
-        "GithubDesktop" => KeyType::Programming(Programming::GithubDesktop),
-        "EclipseIDE" => KeyType::Programming(Programming::EclipseIDE),
-        "FiddlerClassic" => KeyType::Programming(Programming::FiddlerClassic),
-        "FiddlerEverywhere" => KeyType::Programming(Programming::FiddlerEverywhere),
-        "Kubernetes" => KeyType::Programming(Programming::Kubernetes),
-        "MongoDB" => KeyType::Programming(Programming::MongoDB),
-        "MySQLWorkbench" => KeyType::Programming(Programming::MySQLWorkbench),
-        "Nmap" => KeyType::Programming(Programming::Nmap),
-        "NodeJS" => KeyType::Programming(Programming::NodeJS),
-        "NotepadPlusPlus" => KeyType::Programming(Programming::NotepadPlusPlus),
-        "Npcap" => KeyType::Programming(Programming::Npcap),
-        "Ollama" => KeyType::Programming(Programming::Ollama),
-        "OracleVirtualBox" => KeyType::Programming(Programming::OracleVirtualBox),
-        "OracleVirtualBoxExtPack" => KeyType::Programming(Programming::OracleVirtualBoxExtPack),
-        "OWASPZAP" => KeyType::Programming(Programming::OWASPZAP),
-        "PostgreSQL" => KeyType::Programming(Programming::PostgreSQL),
-        "Postman" => KeyType::Programming(Programming::Postman),
-        "Python2_7_18" => KeyType::Programming(Programming::Python2_7_18),
-        "Python3_8_10" => KeyType::Programming(Programming::Python3_8_10),
-        "Python3_9_10" => KeyType::Programming(Programming::Python3_9_10),
-        "Python3_10_10" => KeyType::Programming(Programming::Python3_10_10),
-        "Python3_11_10" => KeyType::Programming(Programming::Python3_11_10),
-        "Python3_12_9" => KeyType::Programming(Programming::Python3_12_9),
-        "Python3_13_2" => KeyType::Programming(Programming::Python3_13_2),
-        "PuTTY" => KeyType::Programming(Programming::PuTTY),
-        "SublimeText" => KeyType::Programming(Programming::SublimeText),
-        "UTM" => KeyType::Programming(Programming::UTM),
-        "VisualStudio" => KeyType::Programming(Programming::VisualStudio),
-        "VisualStudioCode" => KeyType::Programming(Programming::VisualStudioCode),
-        "VSCodium" => KeyType::Programming(Programming::VSCodium),
-        "WebStorm" => KeyType::Programming(Programming::WebStorm),
-        "WinGet" => KeyType::Programming(Programming::WinGet),
-        "WireShark" => KeyType::Programming(Programming::WireShark),
-        "XAMPP" => KeyType::Programming(Programming::XAMPP),
-        "7Zip" => KeyType::Utility(Utility::SevenZip),
-        "Audacious" => KeyType::Utility(Utility::Audacious),
-        "LibreOffice" => KeyType::Utility(Utility::LibreOffice),
-        "ModernCSV" => KeyType::Utility(Utility::ModernCSV),
-        "NoMacs" => KeyType::Utility(Utility::NoMacs),
-        "Okular" => KeyType::Utility(Utility::Okular),
-        "Rufus" => KeyType::Utility(Utility::Rufus),
-        "VLC" => KeyType::Utility(Utility::VLC),
-        "WinRAR" => KeyType::Utility(Utility::WinRAR),
+// Resolves an app selected in config.ini to one resolver per requested version, a single one when no version is asked for.
+// Versioned resolvers are labelled with the version ("3.12", "lts zip"). Options the app doesn't support and invalid
+// values are errors. Returns None for unknown keys and builtins missing from this build.
+pub fn map_app_options(
+    catalog: &Catalog,
+    config_key: &str,
+    options: &AppOptions,
+) -> Option<Result<LabelledResolvers, String>> {
+    let entry: &CatalogEntry = catalog.get(config_key)?;
+    let supported = entry.supported_options();
+    if let Some(option) = options
+        .set_options()
+        .into_iter()
+        .find(|option| !supported.contains(option))
+    {
+        return Some(Err(format!(
+            "{} doesn't support {option}, only {}",
+            entry.key,
+            supported.join(", ")
+        )));
+    }
+
+    let default_arch = match entry.arch() {
+        Ok(arch) => options.arch.or(arch),
+        Err(e) => return Some(Err(e)),
+    };
+    let resolvers: Result<LabelledResolvers, String> = match entry.resolver_kind()? {
+        ResolverKind::Python => python_resolvers(options, default_arch.unwrap_or(Arch::X64)),
+        ResolverKind::Node => node_resolvers(options, default_arch.unwrap_or(Arch::X64)),
+        ResolverKind::GitHub(_) => github_resolver(entry, options),
+        ResolverKind::Builtin if entry.builtin_name() == "Firefox" => firefox_release(options)
+            .map(|release| vec![(None, KeyType::Browser(Browser::Firefox(release)))]),
+        // Takes no options besides output_name, the entry's overrides are already applied
         _ => {
-            println!("Unable to find app with the entered name.");
-            exit(0);
+            let key_type = map_config_key_to_function_name(catalog, config_key)?;
+            return Some(with_output_name(vec![(None, key_type)], options));
         }
+    };
+    Some(resolvers.and_then(|resolvers| {
+        let resolvers = resolvers
+            .into_iter()
+            .map(|(label, key_type)| (label, with_overrides(entry, key_type)))
+            .collect();
+        with_output_name(resolvers, options)
+    }))
+}
+
+fn with_output_name(
+    resolvers: LabelledResolvers,
+    options: &AppOptions,
+) -> Result<LabelledResolvers, String> {
+    let Some(output_name) = &options.output_name else {
+        return Ok(resolvers);
+    };
+    if resolvers.len() > 1 {
+        return Err(String::from(
+            "output_name can't be used with several versions, they would overwrite each other",
+        ));
     }
+    Ok(resolvers
+        .into_iter()
+        .map(|(label, key_type)| {
+            let overrides = EntryOverrides {
+                filename: Some(output_name.clone()),
+                ..EntryOverrides::default()
+            };
+            (label, KeyType::Overridden(Box::new(key_type), overrides))
+        })
+        .collect())
 }
 
-// region: Browser Download Functions
-pub fn get_brave_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://github.com/brave/brave-browser/releases/latest").unwrap();
-    let raw_html = result.1;
-    if result.0 != StatusCode::OK {
-        return String::from("No download link found");
-    } else {
-        let html: Html = Html::parse_document(&raw_html);
-        let selector: Selector = Selector::parse("span.ml-1").unwrap();
-        if let Some(raw_version) = html
-            .select(&selector)
-            .filter_map(|href| href.text().next())
-            .next()
-        {
-            let version = raw_version.trim();
-            let link = format!(
-                "https://github.com/brave/brave-browser/releases/download/{version}/BraveBrowserSetup.exe"
-            );
-            //println!("{}", version);
-            link
-        } else {
-            String::from("No download link found")
+fn python_resolvers(options: &AppOptions, default_arch: Arch) -> Result<LabelledResolvers, String> {
+    if default_arch == Arch::X86 {
+        return Err(String::from(
+            "unsupported arch \"x86\", expected x64 or arm64",
+        ));
+    }
+    let Some(version) = &options.version else {
+        return Ok(vec![(
+            None,
+            KeyType::Python(PythonResolver {
+                spec: VersionSpec::Latest,
+                arch: default_arch,
+            }),
+        )]);
+    };
+    python::parse_specs(version, default_arch).map(|specs| {
+        specs
+            .into_iter()
+            .map(|(label, resolver)| (Some(label), KeyType::Python(resolver)))
+            .collect()
+    })
+}
+
+// channel picks lts or current like version does, a version range and a channel can't both be set
+fn node_resolvers(options: &AppOptions, default_arch: Arch) -> Result<LabelledResolvers, String> {
+    let variant = match options.variant {
+        None | Some(Variant::Installer | Variant::Msi) => NodeVariant::Msi,
+        Some(Variant::Portable | Variant::Zip) => NodeVariant::Zip,
+        Some(Variant::Exe) => NodeVariant::Exe,
+    };
+    let selector = match (&options.version, &options.channel) {
+        (Some(_), Some(_)) => {
+            return Err(String::from(
+                "set either version or channel, channel is the same as version = lts or current",
+            ));
+        }
+        (None, Some(channel)) if channel != "lts" && channel != "current" => {
+            return Err(format!(
+                "unknown channel \"{channel}\", expected lts or current"
+            ));
+        }
+        (Some(selector), None) | (None, Some(selector)) => selector,
+        (None, None) => {
+            return Ok(vec![(
+                None,
+                KeyType::Node(NodeResolver {
+                    selector: NodeSelector::Lts,
+                    variant,
+                    arch: default_arch,
+                }),
+            )]);
+        }
+    };
+    node::parse_specs(selector, variant, default_arch).map(|specs| {
+        specs
+            .into_iter()
+            .map(|(label, resolver)| (Some(label), KeyType::Node(resolver)))
+            .collect()
+    })
+}
+
+fn github_resolver(
+    entry: &CatalogEntry,
+    options: &AppOptions,
+) -> Result<LabelledResolvers, String> {
+    let mut resolver = entry.github_resolver()?;
+    match options.channel.as_deref() {
+        None => {}
+        Some("stable") => resolver.prerelease = false,
+        Some("prerelease") => resolver.prerelease = true,
+        Some(channel) => {
+            return Err(format!(
+                "unknown channel \"{channel}\", expected stable or prerelease"
+            ));
         }
     }
+    if options.arch.is_some() {
+        resolver.arch = options.arch;
+    }
+    Ok(vec![(None, KeyType::GitHub(resolver))])
 }
 
-pub fn get_chrome_link() -> String {
-    "https://dl.google.com/chrome/install/ChromeStandaloneSetup64.exe".to_string()
-}
-
-pub fn get_chromium_link() -> String {
-    "https://download-chromium.appspot.com/dl/Win_x64?type=snapshots".to_string()
+fn firefox_release(options: &AppOptions) -> Result<FirefoxRelease, String> {
+    let mut release = FirefoxRelease::default();
+    if let Some(channel) = &options.channel {
+        release.channel = FirefoxChannel::parse(channel).ok_or_else(|| {
+            format!(
+                "unknown channel \"{channel}\", expected release, beta, esr, devedition or nightly"
+            )
+        })?;
+    }
+    release.arch = options.arch;
+    release.msi = match options.variant {
+        // The Firefox installer is an .exe
+        None | Some(Variant::Installer | Variant::Exe) => false,
+        Some(Variant::Msi) => true,
+        Some(variant) => {
+            return Err(format!(
+                "Firefox has no {variant} download, expected installer, exe or msi"
+            ));
+        }
+    };
+    if let Some(locale) = &options.locale {
+        release.locale = locale.clone();
+    }
+    Ok(release)
 }
 
-pub fn get_edge_link() -> String {
-    "https://msedge.sf.dl.delivery.mp.microsoft.com/filestreamingservice/files/f9137856-3c6a-4a53-8a62-6c3b539149b0/MicrosoftEdgeEnterpriseX64.msi".to_string()
+// region: Browser Download Functions
+pub fn get_chrome_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://dl.google.com/chrome/install/ChromeStandaloneSetup64.exe",
+    ))
 }
 
-pub fn get_opera_link() -> String {
-    "https://net.geo.opera.com/opera/stable/windows".to_string()
+pub fn get_chromium_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://download-chromium.appspot.com/dl/Win_x64?type=snapshots",
+    ))
 }
 
-pub fn get_operagx_link() -> String {
-    "https://net.geo.opera.com/opera_gx/stable/windows".to_string()
+pub fn get_edge_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://msedge.sf.dl.delivery.mp.microsoft.com/filestreamingservice/files/f9137856-3c6a-4a53-8a62-6c3b539149b0/MicrosoftEdgeEnterpriseX64.msi",
+    ))
 }
 
-pub fn get_firefox_link() -> String {
-    "https://download.mozilla.org/?product=firefox-stub&os=win&lang=en-US".to_string()
+pub fn get_opera_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://net.geo.opera.com/opera/stable/windows",
+    ))
 }
 
-pub fn get_librewolf_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://gitlab.com/librewolf-community/browser/bsys6/-/releases.atom")
-            .unwrap();
-    //let status: StatusCode = result.0; // Request status
-    let html: String = result.1; // Request response (HTML)
-    let converted_obj: Result<Value, xmltojson::Error> = to_json(&html);
-    let val: Value = converted_obj.unwrap();
-    let parsed_obj: Value = from_value(val).unwrap();
-    let release_id: String = parsed_obj["feed"]["entry"][0]["id"].to_string();
-    let problem_text: String = '"'.to_string();
-    let new_text: String = String::new();
-    let release_dir: &str = release_id.split("/").last().unwrap();
-    let version: String = release_dir.replace(&problem_text, &new_text);
-    let url: String = format!(
-        "https://gitlab.com/api/v4/projects/44042130/packages/generic/librewolf/{version}/librewolf-{version}-windows-x86_64-package.zip"
-    );
-
-    //println!("{}", url);
-    url
+pub fn get_operagx_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://net.geo.opera.com/opera_gx/stable/windows",
+    ))
 }
 
-pub fn get_palemoon_link() -> String {
-    "https://www.palemoon.org/download.php?mirror=us&bits=64&type=installer".to_string()
+pub fn get_firefox_link(release: &FirefoxRelease) -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(release.url()))
 }
 
-pub fn get_seamonkey_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://www.seamonkey-project.org/releases/").unwrap();
-    //let status: StatusCode = result.0; // Request status
-    let html: String = result.1; // Request response (HTML)
-    let document: Html = Html::parse_document(&html);
-    let selector: Selector = Selector::parse(".curVersion").unwrap();
-    let version = document
-        .select(&selector) // Returns an Iterator of all <a> Element objects from the parsed tree
-        .filter_map(|element| element.text().next()) // Filters the element objects, returning the first element's text
-        .map(|href: &str| href.to_string()) // Maps the hrefs to a String Iterator
-        .next()
-        .unwrap();
-    format!(
-        "https://archive.seamonkey-project.org/releases/{version}/win32/en-US/seamonkey-{version}.en-US.win32.installer.exe"
-    )
+pub fn get_palemoon_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://www.palemoon.org/download.php?mirror=us&bits=64&type=installer",
+    ))
 }
 
-pub fn get_tor_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://www.torproject.org/download/tor/").unwrap();
+pub fn get_tor_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result: (StatusCode, String) = http.get_text("https://www.torproject.org/download/tor/")?;
     //let status: StatusCode = result.0; // Request status
     let html: String = result.1; // Request response (HTML)
     let document: Html = Html::parse_document(&html);
-    let selector: Selector = Selector::parse("a.downloadLink").unwrap();
+    let selector: Selector = parse_selector("a.downloadLink")?;
     let temp_link = document
         .select(&selector)
         .filter_map(|element| {
@@ -275,22 +377,29 @@ pub fn get_tor_link() -> String {
         })
         .map(|href| href.to_string())
         .next()
-        .unwrap();
+        .ok_or_else(|| {
+            ResolveError::SelectorMiss(String::from("No x86_64 a.downloadLink found"))
+        })?;
     //println!("{}", temp_link);
-    let version = temp_link.split("/").nth(5).unwrap();
+    let version = temp_link.split("/").nth(5).ok_or_else(|| {
+        ResolveError::Parse(format!("Unable to read the version from {temp_link}"))
+    })?;
     //println!("{}", version); //
-    let url: String = format!(
-        "https://www.torproject.org/dist/torbrowser/{version}/tor-browser-windows-x86_64-portable-{version}.exe"
-    );
-    url
+    let filename = format!("tor-browser-windows-x86_64-portable-{version}.exe");
+    let url: String = format!("https://www.torproject.org/dist/torbrowser/{version}/{filename}");
+    let sha256 = checksum::tor_sha256(http, version, &filename);
+    Ok(checksum::attach_sha256(
+        ResolvedDownload::direct(url).with_version(version),
+        sha256,
+    ))
 }
 
-pub fn _get_vivaldi_link_old() -> String {
-    let result: (StatusCode, String) = make_web_request("https://vivaldi.com/download/").unwrap();
+pub fn _get_vivaldi_link_old(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result: (StatusCode, String) = http.get_text("https://vivaldi.com/download/")?;
     //let status: StatusCode = result.0; // Request status
     let html: String = result.1; // Request response (HTML)
     let document: Html = Html::parse_document(&html); // Parses the provided html string into an Html tree object
-    let selector: Selector = Selector::parse("a").unwrap(); // Creates a Selector object to parse all <a> Elements
+    let selector: Selector = parse_selector("a")?; // Creates a Selector object to parse all <a> Elements
     document
         .select(&selector) // Returns an Iterator of all <a> Element objects from the parsed tree
         .filter_map(|element| {
@@ -300,156 +409,187 @@ pub fn _get_vivaldi_link_old() -> String {
                 .filter(|href| href.contains(".exe"))
                 .filter(|href| href.contains("x64"))
         }) // Filters the element objects, returning only href links containing ".exe" and the defined PLATFORM "amd64"
-        .map(|href: &str| href.to_string()) // Maps the hrefs to a String Iterator
+        .map(ResolvedDownload::direct) // Maps the hrefs to a ResolvedDownload Iterator
         .next()
-        .unwrap()
-    // Returns the String Iterator
-}
-
-pub fn get_vivaldi_link() -> String {
-    String::from("DU_CLI.exe download Vivaldi")
+        .ok_or_else(|| ResolveError::SelectorMiss(String::from("No x64 .exe link found")))
+}
+
+// Vivaldi's Sparkle appcast lists the current release as an enclosure, e.g.
+// https://downloads.vivaldi.com/stable-auto/Vivaldi.7.1.3570.39.x64.exe
+// The installer offered on vivaldi.com/download is published under stable/ with the same name.
+pub fn get_vivaldi_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let (_, xml) = http.get_text("https://update.vivaldi.com/update/1.0/public/appcast.x64.xml")?;
+    let entries = feed::parse_feed(&xml)?;
+    let pattern = Regex::new(r"Vivaldi\.(\d+(?:\.\d+)+)\.x64\.exe$")
+        .map_err(|e| ResolveError::Parse(e.to_string()))?;
+    let version = entries
+        .iter()
+        .flat_map(|entry| entry.links.iter())
+        .find_map(|link| pattern.captures(link)?.get(1))
+        .map(|version| version.as_str().to_string())
+        .ok_or_else(|| {
+            ResolveError::SelectorMiss(String::from("No x64 installer in the Vivaldi appcast"))
+        })?;
+    Ok(ResolvedDownload::direct(format!(
+        "https://downloads.vivaldi.com/stable/Vivaldi.{version}.x64.exe"
+    ))
+    .with_version(version))
 }
 
-pub fn get_waterfox_link() -> String {
-    let result: (StatusCode, String) = make_web_request("https://waterfox.net/download/").unwrap();
+pub fn get_waterfox_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result: (StatusCode, String) = http.get_text("https://waterfox.net/download/")?;
     //let status: StatusCode = result.0; // Request status, used for debugging
     let raw_html: String = result.1; // Request response (HTML)
     //println!("{}", raw_html); // Used for debugging
     let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse("#release-version").unwrap();
+    let selector: Selector = parse_selector("#release-version")?;
     let version: String = html
         .select(&selector)
         .flat_map(|element| element.text())
         .collect();
+    if version.is_empty() {
+        return Err(ResolveError::SelectorMiss(String::from(
+            "#release-version not found",
+        )));
+    }
     let link: String = format!(
         "https://cdn1.waterfox.net/waterfox/releases/{version}/WINNT_x86_64/Waterfox%20Setup%20{version}.exe"
     );
-    link
+    Ok(ResolvedDownload::direct(link).with_version(version))
 }
 // endregion: Browser Download Functions
 
 // region: Gaming Download Functions
 
 //Refactor this function to scrape the newest version dynamically via https://www.amd.com/en/support/download/drivers.html
-pub fn _get_amd_autodetect_link_old() -> String {
-    "https://drivers.amd.com/drivers/installer/24.30/whql/amd-software-adrenalin-edition-25.3.1-minimalsetup-250312_web.exe".to_string()
-}
-
-pub fn get_amd_autodetect_link() -> String {
-    String::from("DU_CLI.exe download AMD")
+pub fn _get_amd_autodetect_link_old() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://drivers.amd.com/drivers/installer/24.30/whql/amd-software-adrenalin-edition-25.3.1-minimalsetup-250312_web.exe",
+    ))
 }
 
-pub fn get_battlenet_link() -> String {
-    String::from(
-        "https://downloader.battle.net/download/getInstaller?os=win&installer=Battle.net-Setup.exe",
+// The drivers page links the current minimal setup (the auto-detect installer), drivers.amd.com answers 403
+// unless the request comes from amd.com, so the download carries the page as its Referer.
+pub fn get_amd_autodetect_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let page = "https://www.amd.com/en/support/download/drivers.html";
+    let (_, raw_html) = http.get_text(page)?;
+    let pattern = Regex::new(
+        r#"https://drivers\.amd\.com/drivers/installer/[^"'\s<>]*?adrenalin-edition-([\d.]+)-minimalsetup-[^"'\s<>]*\.exe"#,
     )
+    .map_err(|e| ResolveError::Parse(e.to_string()))?;
+    let captures = pattern.captures(&raw_html).ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from(
+            "No minimal setup link found on the AMD drivers page",
+        ))
+    })?;
+    let link = captures.get(0).map_or("", |link| link.as_str());
+    let version = captures.get(1).map_or("", |version| version.as_str());
+    Ok(ResolvedDownload::direct(link)
+        .with_version(version)
+        .with_referer(page))
+}
+
+pub fn get_battlenet_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://downloader.battle.net/download/getInstaller?os=win&installer=Battle.net-Setup.exe",
+    ))
 }
 
-pub fn get_bluestacks_link() -> String {
-    String::from(
+pub fn get_bluestacks_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://cloud.bluestacks.com/api/getdownloadnow?platform=win&win_version=10&bluestacks_version=bs5",
-    )
+    ))
 }
 
 // Refactor this function to handle cases for the custom installations
-pub fn get_cpuz_link() -> String {
-    String::from("https://www.cpuid.com/downloads/cpu-z/cpu-z_2.15-en.exe")
+pub fn get_cpuz_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://www.cpuid.com/downloads/cpu-z/cpu-z_2.15-en.exe",
+    ))
 }
 
-pub fn get_curseforge_link() -> String {
-    String::from(
+pub fn get_curseforge_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://download.overwolf.com/install/Download?ExtensionId=cfiahnpaolfnlgaihhmobmnjdafknjnjdpdabpcm",
-    )
+    ))
 }
 
-pub fn get_epic_games_link() -> String {
-    String::from(
+pub fn get_epic_games_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://launcher-public-service-prod06.ol.epicgames.com/launcher/api/installer/download/EpicGamesLauncherInstaller.msi",
-    )
+    ))
 }
 
 // Refactor this to dynamically download the latest version and offer the ROG themed alternative
-pub fn get_gpuz_link() -> String {
-    String::from(
+pub fn get_gpuz_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://us9-dl.techpowerup.com/files/kv4QONlJeD527EtQMrtjYQ/1743512769/GPU-Z.2.64.0.exe",
-    )
+    ))
 }
 
 // Refactor the dynamically download the latest version using the selector div.col-md-6:nth-child(1) > div:nth-child(1) > sub:nth-child(3)
-pub fn get_hwinfo_link() -> String {
-    String::from("https://www.hwinfo.com/files/hwi64_822.exe")
+pub fn get_hwinfo_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://www.hwinfo.com/files/hwi64_822.exe",
+    ))
 }
 
-pub fn get_hwmonitor_link() -> String {
-    String::from("https://www.cpuid.com/downloads/hwmonitor/hwmonitor_1.56.exe")
+pub fn get_hwmonitor_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://www.cpuid.com/downloads/hwmonitor/hwmonitor_1.56.exe",
+    ))
 }
 
-pub fn get_msi_afterburner_link() -> String {
-    String::from(
+pub fn get_msi_afterburner_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://www.guru3d.com/getdownload/2c1b2414f56a6594ffef91236a87c0e976d52e0518b43f3846bab016c2f20c7c4d6ce7dfe1991cc241d59b5c8cb07e5018b083a5902ac6c67fbe3b852ca022b0f73541638028a2d270eb576309b5208d7642bced763e8806fd9c5a9bca00d71e03e3f895d9924372aebbd01f8d3b8f4f270059bd6d5516b53f1cebbb3340fa764f68932d48b5bb538878337e2e92244ec842c6bc8fbe77fb2097b27ac094473cbbffdfdca7be83b46c55febb094e360b65a50d97cc2f5ebe7b2f727003a739d719662666b53ff47a62585c6739",
-    )
+    ))
 }
 
-pub fn get_nvda_app_link() -> String {
-    String::from(
+pub fn get_nvda_app_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://us.download.nvidia.com/nvapp/client/11.0.3.213/NVIDIA_app_beta_v11.0.3.213.exe",
-    )
-}
-
-pub fn get_obs_studio_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://github.com/obsproject/obs-studio/releases/latest").unwrap();
-    let raw_html = result.1;
-    if result.0 != StatusCode::OK {
-        return String::from("No download link found");
-    } else {
-        let html: Html = Html::parse_document(&raw_html);
-        let selector: Selector = Selector::parse("span.ml-1").unwrap();
-        if let Some(raw_version) = html
-            .select(&selector)
-            .filter_map(|href| href.text().next())
-            .next()
-        {
-            let version = raw_version.trim();
-            let link = format!(
-                "https://github.com/obsproject/obs-studio/releases/download/{version}/OBS-Studio-{version}-Windows-Installer.exe"
-            );
-            //println!("{}", version);
-            link
-        } else {
-            String::from("No download link found")
-        }
-    }
+    ))
 }
 
-pub fn get_parsec_link() -> String {
-    String::from("https://builds.parsec.app/package/parsec-windows.exe")
+pub fn get_parsec_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://builds.parsec.app/package/parsec-windows.exe",
+    ))
 }
 
-pub fn get_pingplotter_link() -> String {
-    String::from("https://www.pingplotter.com/downloads/pingplotter_install.exe")
+pub fn get_pingplotter_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://www.pingplotter.com/downloads/pingplotter_install.exe",
+    ))
 }
 
-pub fn get_process_lasso_link() -> String {
-    String::from("https://dl.bitsum.com/files/processlassosetup64.exe")
+pub fn get_process_lasso_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://dl.bitsum.com/files/processlassosetup64.exe",
+    ))
 }
 
-pub fn get_razer_cortex_link() -> String {
-    String::from("https://dl.razerzone.com/drivers/GameBooster/RazerCortexInstaller.exe")
+pub fn get_razer_cortex_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://dl.razerzone.com/drivers/GameBooster/RazerCortexInstaller.exe",
+    ))
 }
 
-pub fn get_reshade_link() -> String {
-    String::from("https://reshade.me/downloads/ReShade_Setup_6.4.1.exe")
+pub fn get_reshade_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://reshade.me/downloads/ReShade_Setup_6.4.1.exe",
+    ))
 }
 
-pub fn get_rockstar_launcher_link() -> String {
-    String::from(
+pub fn get_rockstar_launcher_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://gamedownloads.rockstargames.com/public/installer/Rockstar-Games-Launcher.exe",
-    )
+    ))
 }
 
 pub fn _get_roblox_launcher_link_old() -> String {
-    // let result = make_web_request_v2("https://www.roblox.com/download/client?os=win").unwrap();
+    // let result = http::get_text("https://www.roblox.com/download/client?os=win")?;
     // let header_value = result.2;
     // for (key, value) in header_value {
     //     print!("{:?}", key.unwrap().as_str());
@@ -459,44 +599,69 @@ pub fn _get_roblox_launcher_link_old() -> String {
     String::from("https://www.roblox.com/download/client?os=win")
 }
 
-pub fn get_roblox_launcher_link() -> String {
-    String::from("DU_CLI.exe download Roblox")
+// Roblox's client settings API names the current player build, its bootstrapper is served from setup.rbxcdn.com:
+// {"version":"0.650.0.6500767","clientVersionUpload":"version-8ef1d5e8e1e44b1f",...}
+pub fn get_roblox_launcher_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let (_, body) =
+        http.get_text("https://clientsettings.roblox.com/v2/client-version/WindowsPlayer")?;
+    let settings: Value = serde_json::from_str(&body)
+        .map_err(|e| ResolveError::Parse(format!("Invalid Roblox client settings: {e}")))?;
+    let upload = settings["clientVersionUpload"].as_str().ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from(
+            "clientVersionUpload missing from the Roblox client settings",
+        ))
+    })?;
+    let mut resolved =
+        ResolvedDownload::direct(format!("https://setup.rbxcdn.com/{upload}-Roblox.exe"))
+            .with_filename("RobloxPlayerInstaller.exe");
+    if let Some(version) = settings["version"].as_str() {
+        resolved = resolved.with_version(version);
+    }
+    Ok(resolved)
 }
-pub fn get_streamlabs_obs_link() -> String {
-    String::from("https://streamlabs.com/streamlabs-desktop/download")
+pub fn get_streamlabs_obs_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://streamlabs.com/streamlabs-desktop/download",
+    ))
 }
 
-pub fn get_steam_link() -> String {
-    String::from("https://cdn.fastly.steamstatic.com/client/installer/SteamSetup.exe")
+pub fn get_steam_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://cdn.fastly.steamstatic.com/client/installer/SteamSetup.exe",
+    ))
 }
 
-pub fn get_wtfast_link() -> String {
-    String::from("https://download.wtfast.com/product/wtfast/")
+pub fn get_wtfast_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://download.wtfast.com/product/wtfast/",
+    ))
 }
 
 // endregion: Gaming Download Functions
 
 // region: Programming Download Functions
 
-pub fn get_android_studio_link() -> String {
-    String::from(
+pub fn get_android_studio_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://redirector.gvt1.com/edgedl/android/studio/install/2024.3.1.14/android-studio-2024.3.1.14-windows.exe",
-    )
+    ))
 }
 
-pub fn get_azure_link() -> String {
-    String::from("https://go.microsoft.com/fwlink/?linkid=2302007")
+pub fn get_azure_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://go.microsoft.com/fwlink/?linkid=2302007",
+    ))
 }
 
-pub fn get_burp_suite_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://portswigger.net/burp/releases").unwrap();
+pub fn get_burp_suite_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result: (StatusCode, String) = http.get_text("https://portswigger.net/burp/releases")?;
     // let status: StatusCode = result.0; // Request status
     // println!("{}", status);
     let html: String = result.1; // Request response (HTML)
-    let dom = parse(&html, ParserOptions::default()).unwrap(); // Uses tl to parse the html into dom
+    let dom =
+        parse(&html, ParserOptions::default()).map_err(|e| ResolveError::Parse(e.to_string()))?; // Uses tl to parse the html into dom
     let parser = dom.parser(); // Initalizes a parser object from the dom
-    let mut link: String = String::new();
+    let mut link: Option<String> = None;
     let mut links_iterated = 0;
 
     // Iterates through the div tag and if it's found continues, if not returns "No class found"
@@ -504,7 +669,9 @@ pub fn get_burp_suite_link() -> String {
         node.as_tag()
             .and_then(|tag| tag.attributes().get("class"))
             .map(|class| {
-                class.as_ref().expect("No class found").as_utf8_str() == "noscript-postlist"
+                class
+                    .as_ref()
+                    .is_some_and(|class| class.as_utf8_str() == "noscript-postlist")
             })
             .unwrap_or(false)
     }) {
@@ -512,38 +679,43 @@ pub fn get_burp_suite_link() -> String {
         // The first tag contains a beta release, and the second tag contains the most recent stable release.
         if let Some(children) = noscript.as_tag().map(|tag| tag.children()) {
             for element in children.all(parser) {
-                if let Some(a_tag) = element.as_tag() {
-                    if let Some(href) = a_tag.attributes().get("href") {
-                        links_iterated += 1;
-
-                        // Continue checking until the second valid link is iterated
-                        if href.is_some() && links_iterated < 2 {
-                            link = String::from(href.as_ref().unwrap().as_utf8_str());
-                        } else if href.is_some() && links_iterated == 2 {
-                            link = String::from(href.as_ref().unwrap().as_utf8_str());
+                if let Some(a_tag) = element.as_tag()
+                    && let Some(href) = a_tag.attributes().get("href")
+                {
+                    links_iterated += 1;
+
+                    // Continue checking until the second valid link is iterated
+                    if let Some(href) = href.as_ref() {
+                        link = Some(String::from(href.as_utf8_str()));
+                        if links_iterated == 2 {
                             break;
-                        } else {
-                            link = String::from("No link found");
                         }
-
-                        // println!(
-                        //     "Found link: {}",
-                        //     href.as_ref().expect("No href found").as_utf8_str()
-                        // );
+                    } else {
+                        link = None;
                     }
+
+                    // println!(
+                    //     "Found link: {}",
+                    //     href.as_ref().expect("No href found").as_utf8_str()
+                    // );
                 }
             }
         }
     } else {
-        println!("No <div class='noscript-postlist'> found.");
-        link = String::from("No link found");
+        return Err(ResolveError::SelectorMiss(String::from(
+            "No <div class='noscript-postlist'> found.",
+        )));
     }
-    link
+    link.map(ResolvedDownload::direct).ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from(
+            "No release link found in <div class='noscript-postlist'>",
+        ))
+    })
 
     // let document = Html::parse_document(&html);
 
     // // Selector for the a tag containing the href link being parsed
-    // let selector = Selector::parse("//a[contains(text(),'Professional / Community']").unwrap();
+    // let selector = parse_selector("//a[contains(text(),'Professional / Community']")?;
 
     // let version = document
     //     .select(&selector)
@@ -556,33 +728,37 @@ pub fn get_burp_suite_link() -> String {
     // Returns the String Iterator
 }
 
-pub fn get_docker_desktop_link() -> String {
-    String::from("https://desktop.docker.com/win/main/amd64/Docker%20Desktop%20Installer.exe")
+pub fn get_docker_desktop_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://desktop.docker.com/win/main/amd64/Docker%20Desktop%20Installer.exe",
+    ))
 }
 
-pub fn get_filezilla_link(filezilla_type: FilezillaType) -> String {
+pub fn get_filezilla_link(filezilla_type: FilezillaType) -> Result<ResolvedDownload, ResolveError> {
     match filezilla_type {
-        FilezillaType::Client => String::from(
+        FilezillaType::Client => Ok(ResolvedDownload::direct(
             "https://download.filezilla-project.org/client/FileZilla_3.68.1_win64_sponsored2-setup.exe",
-        ),
-        FilezillaType::Server => String::from(
+        )),
+        FilezillaType::Server => Ok(ResolvedDownload::direct(
             "https://dl3.cdn.filezilla-project.org/server/FileZilla_Server_1.9.4_win64-setup.exe?h=Tkmnw8TQh-e_FicKR3lm4Q&x=1742954205",
-        ),
+        )),
     }
 }
 
-pub fn get_github_desktop_link() -> String {
-    String::from("https://central.github.com/deployments/desktop/desktop/latest/win32")
+pub fn get_github_desktop_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://central.github.com/deployments/desktop/desktop/latest/win32",
+    ))
 }
 
-pub fn get_eclipse_ide_link() -> String {
+pub fn get_eclipse_ide_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
     let result: (StatusCode, String) =
-        make_web_request("https://www.eclipse.org/downloads/packages/").unwrap();
+        http.get_text("https://www.eclipse.org/downloads/packages/")?;
     //let status: StatusCode = result.0; // Request status, used for debugging
     let raw_html: String = result.1; // Request response (HTML)
     //println!("{}", raw_html); // Used for debugging
     let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse("a[title='x86_64 Download']").unwrap();
+    let selector: Selector = parse_selector("a[title='x86_64 Download']")?;
     let link: Option<String> = html
         .select(&selector)
         .filter_map(|element| {
@@ -590,43 +766,42 @@ pub fn get_eclipse_ide_link() -> String {
                 .value()
                 .attr("href")
                 .filter(|element| element.contains(".exe"))
-                .map(|element| String::from(element))
+                .map(String::from)
         })
         .next();
     // println!("{:?}", link); // used for debugging
-    link.unwrap_or_else(|| String::from("No download link found")) // Not sure what the || operator does but rust-analyzer required it, will research later.
+    link.map(ResolvedDownload::direct).ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from("No x86_64 .exe download link found"))
+    }) // Not sure what the || operator does but rust-analyzer required it, will research later.
 }
 
-pub fn get_fiddler_classic_link() -> String {
-    String::from(
+pub fn get_fiddler_classic_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://downloads.getfiddler.com/fiddler-classic/FiddlerSetup.5.0.20251.1171-latest.exe",
-    ) // No longer updated as compared to fiddler everywhere
+    )) // No longer updated as compared to fiddler everywhere
 }
 
-pub fn get_fiddler_everywhere_link() -> String {
-    String::from("https://downloads.getfiddler.com/win/Fiddler%20Everywhere%206.3.0.exe")
+pub fn get_fiddler_everywhere_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://downloads.getfiddler.com/win/Fiddler%20Everywhere%206.3.0.exe",
+    ))
 }
 
-pub fn get_kubernetes_link() -> String {
-    let result: Result<(StatusCode, String), Box<dyn Error>> =
-        make_web_request("https://dl.k8s.io/release/stable.txt");
+pub fn get_kubernetes_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result: (StatusCode, String) = http.get_text("https://dl.k8s.io/release/stable.txt")?;
     //let status: StatusCode = result.0; // Request status, used for debugging
-    let version: String = match result {
-        Ok((_, html)) => html, // Extract HTML if request is successful
-        Err(_) => return String::from("No download link found"), // Return error message if request fails
-    };
-    String::from(format!(
-        "https://dl.k8s.io/release/{version}/bin/windows/amd64/kubectl.exe"
-    ))
+    let version: String = result.1.trim().to_string();
+    let link: String = format!("https://dl.k8s.io/release/{version}/bin/windows/amd64/kubectl.exe");
+    Ok(ResolvedDownload::direct(link).with_version(version))
 }
 
-pub fn get_mongodb_link() -> String {
+pub fn get_mongodb_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
     let result: (StatusCode, String) =
-        make_web_request("https://www.mongodb.com/try/download/compass").unwrap();
+        http.get_text("https://www.mongodb.com/try/download/compass")?;
     let raw_html: String = result.1; // Request response (HTML)
 
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse(r#"script[id="server-data"]"#).unwrap();
+    let selector = parse_selector(r#"script[id="server-data"]"#)?;
 
     if let Some(element) = html.select(&selector).next() {
         // If the selector is present
@@ -638,7 +813,7 @@ pub fn get_mongodb_link() -> String {
 
             let json: serde_json::Value = match serde_json::from_str(json_str) {
                 Ok(value) => value,
-                Err(_) => return String::from("No download link found."),
+                Err(e) => return Err(ResolveError::Parse(e.to_string())),
             };
 
             // Parses the msi installer link
@@ -663,23 +838,30 @@ pub fn get_mongodb_link() -> String {
                 .and_then(|element| element.get("msi"))
                 .and_then(|element| element.as_str())
             {
-                return link.to_string();
+                return Ok(ResolvedDownload::direct(link).with_version("2.4.2"));
             }
         }
-        return String::from("No download link found.");
+        return Err(ResolveError::SelectorMiss(String::from(
+            "Windows x64 msi not found in server-data",
+        )));
     }
-    return String::from("No download link found.");
+    Err(ResolveError::SelectorMiss(String::from(
+        r#"script[id="server-data"] not found"#,
+    )))
 }
 
-pub fn get_mysql_workbench_link() -> String {
-    String::from("https://dev.mysql.com/downloads/file/?id=536668")
+// The file page requires clicking "No thanks, just start my download."
+pub fn get_mysql_workbench_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::manual(
+        "https://dev.mysql.com/downloads/file/?id=536668",
+    ))
 }
 
-pub fn get_nmap_link() -> String {
-    let result = make_web_request("https://nmap.org/download.html#windows").unwrap();
+pub fn get_nmap_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://nmap.org/download.html#windows")?;
     let raw_html: String = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse("a").unwrap();
+    let selector = parse_selector("a")?;
     let elements: Vec<&str> = html
         .select(&selector)
         .filter_map(|element| {
@@ -692,42 +874,54 @@ pub fn get_nmap_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
-}
-
-pub fn get_nodejs_link() -> String {
-    String::from("https://nodejs.org/download/release/latest/win-x64/node.exe")
+    let link = elements.first().ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from(
+            "No download link matched on the download page",
+        ))
+    })?;
+    // e.g. https://nmap.org/dist/nmap-7.95-setup.exe
+    let filename = link.rsplit('/').next().unwrap_or_default();
+    let resolved = ResolvedDownload::direct(link.to_string());
+    let resolved = match filename
+        .strip_prefix("nmap-")
+        .and_then(|rest| rest.strip_suffix("-setup.exe"))
+    {
+        Some(version) => resolved.with_version(version),
+        None => resolved,
+    };
+    Ok(checksum::attach_sha256(
+        resolved,
+        checksum::nmap_sha256(http, filename),
+    ))
 }
 
-pub fn get_notepadplusplus_link() -> String {
-    let result = make_web_request("https://notepad-plus-plus.org/downloads/").unwrap();
+pub fn get_notepadplusplus_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://notepad-plus-plus.org/downloads/")?;
     let raw_html: String = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector =
-        Selector::parse(r#"a[href*="https://notepad-plus-plus.org/downloads/"]"#).unwrap();
+    let selector = parse_selector(r#"a[href*="https://notepad-plus-plus.org/downloads/"]"#)?;
     let elements: Vec<String> = html
         .select(&selector)
         .filter_map(|href| href.value().attr("href").map(|href| href.to_string()))
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
+    elements
+        .first()
+        .map(|link| ResolvedDownload::direct(link.to_string()))
+        .ok_or_else(|| {
+            ResolveError::SelectorMiss(String::from(
+                "No download link matched on the download page",
+            ))
+        })
 
     //.patterns-list > li:nth-child(1) > h2:nth-child(1) > a:nth-child(1)
 }
 
-pub fn get_npcap_link() -> String {
-    let result = make_web_request("https://nmap.org/download.html#windows").unwrap();
+pub fn get_npcap_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://nmap.org/download.html#windows")?;
     let raw_html: String = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse("a").unwrap();
+    let selector = parse_selector("a")?;
     let elements: Vec<&str> = html
         .select(&selector)
         .filter_map(|element| {
@@ -740,22 +934,27 @@ pub fn get_npcap_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
+    elements
+        .first()
+        .map(|link| ResolvedDownload::direct(link.to_string()))
+        .ok_or_else(|| {
+            ResolveError::SelectorMiss(String::from(
+                "No download link matched on the download page",
+            ))
+        })
 }
 
-pub fn get_ollama_link() -> String {
-    String::from("https://ollama.com/download/OllamaSetup.exe")
+pub fn get_ollama_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://ollama.com/download/OllamaSetup.exe",
+    ))
 }
 
-pub fn get_oracle_virtualbox_link() -> String {
-    let result = make_web_request("https://www.virtualbox.org/wiki/Downloads").unwrap();
+pub fn get_oracle_virtualbox_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://www.virtualbox.org/wiki/Downloads")?;
     let raw_html: String = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse(".ext-link").unwrap();
+    let selector = parse_selector(".ext-link")?;
 
     let elements: Vec<&str> = html
         .select(&selector)
@@ -769,18 +968,32 @@ pub fn get_oracle_virtualbox_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
+    let link = elements.first().ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from(
+            "No download link matched on the download page",
+        ))
+    })?;
+    Ok(virtualbox_download(http, link))
+}
+
+// e.g. https://download.virtualbox.org/virtualbox/7.1.6/VirtualBox-7.1.6-167084-Win.exe, the extension pack is published
+// in the same directory and both are listed in its SHA256SUMS
+fn virtualbox_download(http: &HttpClient, link: &str) -> ResolvedDownload {
+    let resolved = ResolvedDownload::direct(link);
+    let resolved = match link.rsplit('/').nth(1) {
+        Some(version) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+            resolved.with_version(version)
+        }
+        _ => resolved,
+    };
+    checksum::attach_sha256(resolved, checksum::virtualbox_sha256(http, link))
 }
 
-pub fn get_oracle_vbox_ext_pack_link() -> String {
-    let result = make_web_request("https://www.virtualbox.org/wiki/Downloads").unwrap();
+pub fn get_oracle_vbox_ext_pack_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://www.virtualbox.org/wiki/Downloads")?;
     let raw_html: String = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse(".license-button").unwrap();
+    let selector = parse_selector(".license-button")?;
 
     let elements: Vec<&str> = html
         .select(&selector)
@@ -794,18 +1007,19 @@ pub fn get_oracle_vbox_ext_pack_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
+    let link = elements.first().ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from(
+            "No download link matched on the download page",
+        ))
+    })?;
+    Ok(virtualbox_download(http, link))
 }
 
-pub fn get_owasp_zap_link() -> String {
-    let result = make_web_request("https://www.zaproxy.org/download/").unwrap();
+pub fn get_owasp_zap_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://www.zaproxy.org/download/")?;
     let raw_html: String = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse(r#"a[track-event="download.stable.win-64-i"]"#).unwrap();
+    let selector = parse_selector(r#"a[track-event="download.stable.win-64-i"]"#)?;
 
     let elements: Vec<String> = html
         .select(&selector)
@@ -819,25 +1033,26 @@ pub fn get_owasp_zap_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
+    elements
+        .first()
+        .map(|link| ResolvedDownload::direct(link.to_string()))
+        .ok_or_else(|| {
+            ResolveError::SelectorMiss(String::from(
+                "No download link matched on the download page",
+            ))
+        })
     //
 }
 
-pub fn get_postgresql_link() -> String {
+pub fn get_postgresql_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
     let result =
-        make_web_request_v2("https://www.enterprisedb.com/downloads/postgres-postgresql-downloads")
-            .unwrap();
+        http.get_text("https://www.enterprisedb.com/downloads/postgres-postgresql-downloads")?;
     let raw_html: String = result.1;
     let html: Html = Html::parse_document(&raw_html);
 
     // Selects only the most recent windows x64 build of postgresql verified by edb
     let selector =
-        Selector::parse("tr.border-y.border-white:nth-child(1) td.text-center.py-4:nth-child(5) a")
-            .unwrap();
+        parse_selector("tr.border-y.border-white:nth-child(1) td.text-center.py-4:nth-child(5) a")?;
 
     let elements: Vec<String> = html
         .select(&selector)
@@ -856,49 +1071,24 @@ pub fn get_postgresql_link() -> String {
     elements
         .first()
         .cloned()
-        .unwrap_or_else(|| String::from("No download link found"))
+        .map(ResolvedDownload::direct)
+        .ok_or_else(|| {
+            ResolveError::SelectorMiss(String::from("No EDB windows x64 download link found"))
+        })
     //tr.border-y:nth-child(1) > td:nth-child(5) > a:nth-child(1)
 }
 
-pub fn get_postman_link() -> String {
-    String::from("https://dl.pstmn.io/download/latest/win64")
-}
-
-pub fn get_python2_7_18_link() -> String {
-    String::from("https://www.python.org/ftp/python/2.7.18/python-2.7.18.amd64.msi")
-}
-
-pub fn get_python3_8_10_link() -> String {
-    String::from("https://www.python.org/ftp/python/3.8.10/python-3.8.10-amd64.exe")
-}
-
-pub fn get_python3_9_10_link() -> String {
-    String::from("https://www.python.org/ftp/python/3.9.10/python-3.9.10-amd64.exe")
-}
-
-pub fn get_python3_10_10_link() -> String {
-    String::from("https://www.python.org/ftp/python/3.10.10/python-3.10.10-amd64.exe")
-}
-
-pub fn get_python3_11_10_link() -> String {
-    String::from("https://www.python.org/ftp/python/3.11.10/python-3.11.10-amd64.exe")
-}
-
-pub fn get_python3_12_9_link() -> String {
-    String::from("https://www.python.org/ftp/python/3.12.9/python-3.12.9-amd64.exe")
-}
-
-pub fn get_python3_13_2_link() -> String {
-    String::from("https://www.python.org/ftp/python/3.13.2/python-3.13.2-amd64.exe")
+pub fn get_postman_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://dl.pstmn.io/download/latest/win64",
+    ))
 }
 
-pub fn get_putty_link() -> String {
-    let result =
-        make_web_request_v2("https://www.chiark.greenend.org.uk/~sgtatham/putty/latest.html")
-            .unwrap();
+pub fn get_putty_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://www.chiark.greenend.org.uk/~sgtatham/putty/latest.html")?;
     let raw_html = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse("span.downloadfile > a:nth-child(1)").unwrap();
+    let selector = parse_selector("span.downloadfile > a:nth-child(1)")?;
 
     let elements: Vec<&str> = html
         .select(&selector)
@@ -912,19 +1102,33 @@ pub fn get_putty_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
+    let link = elements.first().ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from(
+            "No download link matched on the download page",
+        ))
+    })?;
+    // e.g. https://the.earth.li/~sgtatham/putty/latest/w64/putty-64bit-0.83-installer.msi
+    let resolved = ResolvedDownload::direct(link.to_string());
+    let resolved = match link
+        .rsplit('/')
+        .next()
+        .and_then(|filename| filename.strip_prefix("putty-64bit-"))
+        .and_then(|rest| rest.strip_suffix("-installer.msi"))
+    {
+        Some(version) => resolved.with_version(version),
+        None => resolved,
+    };
+    Ok(checksum::attach_sha256(
+        resolved,
+        checksum::putty_sha256(http, link),
+    ))
 }
 
-pub fn get_sublime_text_link() -> String {
-    let result =
-        make_web_request_v2("https://www.sublimetext.com/download_thanks?target=win-x64").unwrap();
+pub fn get_sublime_text_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://www.sublimetext.com/download_thanks?target=win-x64")?;
     let raw_html = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse("a").unwrap();
+    let selector = parse_selector("a")?;
 
     let elements: Vec<&str> = html
         .select(&selector)
@@ -938,64 +1142,49 @@ pub fn get_sublime_text_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.first() {
-        link.to_string()
-    } else {
-        String::from("No download link found")
-    }
+    elements
+        .first()
+        .map(|link| ResolvedDownload::direct(link.to_string()))
+        .ok_or_else(|| {
+            ResolveError::SelectorMiss(String::from(
+                "No download link matched on the download page",
+            ))
+        })
 }
 
-pub fn get_utm_link() -> String {
-    String::from("https://getutm.app/downloads/utm-guest-tools-latest.iso")
+pub fn get_utm_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://getutm.app/downloads/utm-guest-tools-latest.iso",
+    ))
 }
 
-pub fn get_visual_studio_link() -> String {
-    String::from(
+pub fn get_visual_studio_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
         "https://c2rsetup.officeapps.live.com/c2r/downloadVS.aspx?sku=community&channel=Release&version=VS2022&source=VSLandingPage&cid=2030",
-    )
-}
-
-pub fn get_vscode_link() -> String {
-    String::from("https://code.visualstudio.com/sha/download?build=stable&os=win32-x64")
+    ))
 }
 
-pub fn get_vscodium_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://github.com/VSCodium/vscodium/releases/latest").unwrap();
-    let raw_html = result.1;
-    if result.0 != StatusCode::OK {
-        return String::from("No download link found");
-    } else {
-        let html: Html = Html::parse_document(&raw_html);
-        let selector: Selector = Selector::parse(r#"h1[data-view-component="true"]"#).unwrap();
-        if let Some(version) = html
-            .select(&selector)
-            .filter_map(|href| href.text().next())
-            .next()
-        {
-            let link = format!(
-                "https://github.com/VSCodium/vscodium/releases/download/{version}/VSCodiumSetup-x64-{version}.exe"
-            );
-            link
-        } else {
-            String::from("No download link found")
-        }
-    }
+pub fn get_vscode_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://code.visualstudio.com/sha/download?build=stable&os=win32-x64",
+    ))
 }
 
-pub fn get_webstorm_link() -> String {
-    String::from("https://download.jetbrains.com/webstorm/WebStorm-2024.3.5.exe")
+pub fn get_webstorm_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://download.jetbrains.com/webstorm/WebStorm-2024.3.5.exe",
+    ))
 }
 
-pub fn get_winget_link() -> String {
-    String::from("https://aka.ms/getwingetpreview")
+pub fn get_winget_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct("https://aka.ms/getwingetpreview"))
 }
 
-pub fn get_wireshark_link() -> String {
-    let result = make_web_request_v2("https://www.wireshark.org/download.html").unwrap();
+pub fn get_wireshark_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://www.wireshark.org/download.html")?;
     let raw_html = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse("a").unwrap();
+    let selector = parse_selector("a")?;
 
     let elements: Vec<&str> = html
         .select(&selector)
@@ -1010,19 +1199,34 @@ pub fn get_wireshark_link() -> String {
         .collect();
 
     if let Some(link) = elements.first() {
-        link.to_string()
+        let resolved = ResolvedDownload::direct(link.to_string());
+        // e.g. https://2.na.dl.wireshark.org/win64/Wireshark-4.4.5-x64.exe
+        let filename = link.rsplit('/').next().unwrap_or_default();
+        let Some(version) = filename
+            .strip_prefix("Wireshark-")
+            .and_then(|rest| rest.strip_suffix("-x64.exe"))
+        else {
+            return Ok(checksum::attach_sha256(resolved, Ok(None)));
+        };
+        let sha256 = checksum::wireshark_sha256(http, version, filename);
+        Ok(checksum::attach_sha256(
+            resolved.with_version(version),
+            sha256,
+        ))
     } else {
-        String::from("No download link found")
+        Err(ResolveError::SelectorMiss(String::from(
+            "No download link matched on the download page",
+        )))
     }
 
     //https://2.na.dl.wireshark.org/win64/Wireshark-4.4.5-x64.exe
 }
 
-pub fn get_xampp_link() -> String {
-    let result = make_web_request_v2("https://www.apachefriends.org/download.html").unwrap();
+pub fn get_xampp_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result = http.get_text("https://www.apachefriends.org/download.html")?;
     let raw_html = result.1;
     let html: Html = Html::parse_document(&raw_html);
-    let selector = Selector::parse("a.button").unwrap();
+    let selector = parse_selector("a.button")?;
 
     let elements: Vec<&str> = html
         .select(&selector)
@@ -1036,23 +1240,25 @@ pub fn get_xampp_link() -> String {
         })
         .collect();
 
-    if let Some(link) = elements.iter().nth(2) {
-        link.to_string() // Installer for the most recent version of xampp
+    if let Some(link) = elements.get(2) {
+        Ok(ResolvedDownload::direct(link.to_string())) // Installer for the most recent version of xampp
     } else {
-        String::from("No download link found")
+        Err(ResolveError::SelectorMiss(String::from(
+            "No download link matched on the download page",
+        )))
     }
 }
 // endregion: Programming Download Functions
 
 // region: System Utilities Functions
 
-pub fn get_seven_zip_link() -> String {
-    let result: (StatusCode, String) = make_web_request("https://7-zip.org/download.html").unwrap();
+pub fn get_seven_zip_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result: (StatusCode, String) = http.get_text("https://7-zip.org/download.html")?;
     //let status: StatusCode = result.0; // Request status, used for debugging
     let raw_html: String = result.1; // Request response (HTML)
     //println!("{}", raw_html); // Used for debugging
     let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse("a").unwrap();
+    let selector: Selector = parse_selector("a")?;
     let link: Option<String> = html
         .select(&selector)
         .filter_map(|element| {
@@ -1061,27 +1267,29 @@ pub fn get_seven_zip_link() -> String {
                 .attr("href")
                 .filter(|element| element.contains("a/7z"))
                 .filter(|element| element.contains("x64.msi"))
-                .map(|element| String::from(element))
+                .map(String::from)
         })
         .next();
     // println!("{:?}", link); // used for debugging
-    let base_link = link.unwrap_or_else(|| String::from("No download link found"));
-    if base_link == "No download link found" {
-        return String::from("No download link found");
-    } else {
-        let link: String = format!("https://7-zip.org/{base_link}");
-        link
+    match link {
+        Some(base_link) => {
+            let link: String = format!("https://7-zip.org/{base_link}");
+            Ok(ResolvedDownload::direct(link))
+        }
+        None => Err(ResolveError::SelectorMiss(String::from(
+            "No x64 .msi download link found",
+        ))),
     }
 }
 
-pub fn get_audacious_link() -> String {
+pub fn get_audacious_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
     let result: (StatusCode, String) =
-        make_web_request("https://www.audacityteam.org/download/windows/").unwrap();
+        http.get_text("https://www.audacityteam.org/download/windows/")?;
     //let status: StatusCode = result.0; // Request status, used for debugging
     let raw_html: String = result.1; // Request response (HTML)
     //println!("{}", raw_html); // Used for debugging
     let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse("a").unwrap();
+    let selector: Selector = parse_selector("a")?;
     let link: Option<String> = html
         .select(&selector)
         .filter_map(|element| {
@@ -1091,169 +1299,237 @@ pub fn get_audacious_link() -> String {
                 .filter(|element| element.contains("/releases/download/Audacity-"))
                 .filter(|element| element.contains("audacity-win-"))
                 .filter(|element| element.contains("-64bit.exe"))
-                .map(|element| String::from(element))
-        })
-        .next();
-    // println!("{:?}", link); // used for debugging
-    link.unwrap_or_else(|| String::from("No download link found"))
-}
-
-// Function that downloads libreoffice needs to handle redirect to:
-//https://mirrors.ukfast.co.uk/sites/documentfoundation.org/tdf/libreoffice/stable/25.2.1/win/x86/LibreOffice_25.2.1_Win_x86.msi
-pub fn get_libreoffice_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://www.libreoffice.org/download/download-libreoffice/").unwrap();
-    //let status: StatusCode = result.0; // Request status, used for debugging
-    let raw_html: String = result.1; // Request response (HTML)
-    //println!("{}", raw_html); // Used for debugging
-    let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse("a.dl_download_link").unwrap();
-    let link: Option<String> = html
-        .select(&selector)
-        .filter_map(|element| {
-            element
-                .value()
-                .attr("href")
-                // .filter(|element| element.contains("/donate/dl/win-x86_64/"))
-                // .filter(|element| element.contains("_Win_x86-64.msi"))
-                .map(|element| String::from(element))
+                .map(String::from)
         })
         .next();
     // println!("{:?}", link); // used for debugging
-    link.unwrap_or_else(|| String::from("No download link found"))
+    link.map(ResolvedDownload::direct).ok_or_else(|| {
+        ResolveError::SelectorMiss(String::from("No 64bit .exe download link found"))
+    })
 }
 
-pub fn get_modern_csv_link() -> String {
-    String::from("https://www.moderncsv.com/download-windows")
+pub fn get_modern_csv_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://www.moderncsv.com/download-windows",
+    ))
 }
 
-pub fn get_nomacs_link() -> String {
-    String::from("https://github.com/nomacs/nomacs/releases/latest/download/nomacs-setup-x64.msi")
+pub fn get_nomacs_link() -> Result<ResolvedDownload, ResolveError> {
+    Ok(ResolvedDownload::direct(
+        "https://github.com/nomacs/nomacs/releases/latest/download/nomacs-setup-x64.msi",
+    ))
 }
 
-pub fn get_okular_link() -> String {
-    //https://cdn.kde.org/ci-builds/graphics/okular/master/windows/
+// pub fn get_photoshop_cs6_link() -> String {
+//     String::from("https://example.com/photoshop_cs6")
+// }
 
-    let result: (StatusCode, String) =
-        make_web_request("https://cdn.kde.org/ci-builds/graphics/okular/master/windows/").unwrap();
+pub fn get_winrar_link(http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+    let result: (StatusCode, String) = http.get_text("https://www.rarlab.com/download.htm")?;
     //let status: StatusCode = result.0; // Request status, used for debugging
     let raw_html: String = result.1; // Request response (HTML)
     //println!("{}", raw_html); // Used for debugging
     let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse("a").unwrap();
-    let link: Option<String> = html
+    let selector: Selector = parse_selector("a")?;
+    let raw_link: Option<String> = html
         .select(&selector)
         .filter_map(|element| {
             element
                 .value()
                 .attr("href")
-                .filter(|element| element.ends_with(".exe"))
-                .map(|element| String::from(element))
+                .filter(|href| href.contains("/rar/winrar-x64-"))
+                .map(String::from)
         })
         .next();
     // println!("{:?}", link); // used for debugging
-    let filename = link.unwrap_or_else(|| String::from("No download link found"));
-    if filename != "No download link found" {
-        let link =
-            format!("https://cdn.kde.org/ci-builds/graphics/okular/master/windows/{filename}");
-        link
-    } else {
-        filename
+    match raw_link {
+        Some(filename) => {
+            let link = format!("https://www.rarlab.com{filename}");
+            Ok(ResolvedDownload::direct(link))
+        }
+        None => Err(ResolveError::SelectorMiss(String::from(
+            "No /rar/winrar-x64- link found",
+        ))),
     }
 }
 
-// pub fn get_photoshop_cs6_link() -> String {
-//     String::from("https://example.com/photoshop_cs6")
-// }
+// endregion: System Utilities Functions
 
-pub fn get_rufus_link() -> String {
-    //https://github.com/pbatard/rufus/releases/latest
-    let result: (StatusCode, String) =
-        make_web_request("https://github.com/pbatard/rufus/releases/latest").unwrap();
-    let raw_html = result.1;
-    if result.0 != StatusCode::OK {
-        return String::from("No download link found");
-    } else {
-        let html: Html = Html::parse_document(&raw_html);
-        let selector: Selector = Selector::parse("span.ml-1").unwrap();
-        if let Some(raw_version) = html
-            .select(&selector)
-            .filter_map(|href| href.text().next())
-            .next()
-        {
-            let version = raw_version.trim();
-            let partial_file_raw = raw_version.replace("v", "");
-            let partial_filename = partial_file_raw.trim();
-            let link: String = format!(
-                "https://github.com/pbatard/rufus/releases/download/{version}/rufus-{partial_filename}_x86.exe"
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stub::{self, StubServer};
+    use std::panic::{self, AssertUnwindSafe};
+
+    // Half a page, an unterminated tag and JSON cut off where a version would be
+    const MALFORMED: &str = "<html><head><title>Download</title></head><body><div id=\"release-version\"><a href=\"\n{\"versions\": [{\"version\": ";
+    const VIVALDI: &str = include_str!("../tests/fixtures/vendors/vivaldi-appcast.x64.xml");
+    const AMD: &str = include_str!("../tests/fixtures/vendors/amd-drivers.html");
+    const ROBLOX: &str = include_str!("../tests/fixtures/vendors/roblox-client-version.json");
+
+    // Runs resolve with every request answered by the stub serving body at path, returns the result and the paths requested
+    fn resolve_from(
+        path: &'static str,
+        body: &str,
+        resolve: fn(&HttpClient) -> Result<ResolvedDownload, ResolveError>,
+    ) -> (Result<ResolvedDownload, ResolveError>, Vec<String>) {
+        let body = body.to_string();
+        let server = StubServer::start(move |request| {
+            if request.path == path {
+                stub::response(200, &[], body.as_bytes())
+            } else {
+                stub::response(404, &[], b"")
+            }
+        });
+        let resolved = resolve(&server.client());
+        let paths = server.requests().into_iter().map(|r| r.path).collect();
+        (resolved, paths)
+    }
+
+    // Runs every catalog resolver with the stub answering all its requests, returns the apps whose resolver panicked
+    fn panicking_resolvers(server: &StubServer) -> Vec<String> {
+        let catalog = Catalog::embedded();
+        let http = server.client();
+        let panicked: Vec<String> = catalog
+            .entries()
+            .filter_map(|entry| {
+                let resolver = map_config_key_to_function_name(&catalog, &entry.key)?;
+                panic::catch_unwind(AssertUnwindSafe(|| resolver.resolve(&http)))
+                    .is_err()
+                    .then(|| entry.key.clone())
+            })
+            .collect();
+        panicked
+    }
+
+    #[test]
+    fn resolvers_survive_malformed_pages() {
+        let server = StubServer::start(|_| {
+            stub::response(200, &[("Content-Type", "text/html")], MALFORMED.as_bytes())
+        });
+        assert_eq!(panicking_resolvers(&server), Vec::<String>::new());
+        // The vendor's path reaches the stub, so the resolvers really parsed the stub's page
+        assert!(
+            server
+                .requests()
+                .iter()
+                .any(|request| request.path == "/download/tor/")
+        );
+    }
+
+    // 5xx and 429 are retried with a growing delay, these statuses fail at once
+    #[test]
+    fn resolvers_survive_error_statuses() {
+        for status in [403, 404, 410] {
+            let server = StubServer::start(move |_| stub::response(status, &[], b"Not here"));
+            assert_eq!(
+                panicking_resolvers(&server),
+                Vec::<String>::new(),
+                "status {status}"
             );
-            link
-        } else {
-            String::from("No download link found")
         }
     }
-}
 
-pub fn get_vlc_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://www.videolan.org/vlc/download-windows.html").unwrap();
-    //let status: StatusCode = result.0; // Request status, used for debugging
-    let raw_html: String = result.1; // Request response (HTML)
-    //println!("{}", raw_html); // Used for debugging
-    let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse(r#"a[id="downloadButton2"]"#).unwrap();
-    let raw_link: Option<String> = html
-        .select(&selector)
-        .filter_map(|element| {
-            element
-                .value()
-                .attr("href")
-                .map(|element| String::from(element))
-        })
-        .next();
-    // println!("{:?}", link); // used for debugging
-    let raw_version = raw_link.unwrap_or_else(|| String::from("No download link found"));
-    if raw_version == "No download link found" {
-        return raw_version;
+    fn node_variant(variant: Option<Variant>) -> NodeVariant {
+        let options = AppOptions {
+            enabled: true,
+            variant,
+            ..AppOptions::default()
+        };
+        match node_resolvers(&options, Arch::X64).unwrap().as_slice() {
+            [(None, KeyType::Node(resolver))] => resolver.variant,
+            resolvers => panic!("expected a single node resolver, found {resolvers:?}"),
+        }
     }
-    let version = raw_version
-        .split("/")
-        .nth(4)
-        .unwrap_or_else(|| "No download link found");
 
-    if !version.is_empty() {
-        return format!("https://get.videolan.org/vlc/{version}/win32/vlc-{version}-win32.exe");
-    } else {
-        return String::from("No download link found");
+    #[test]
+    fn node_variants_map_to_their_downloads() {
+        assert_eq!(node_variant(None), NodeVariant::Msi);
+        assert_eq!(node_variant(Some(Variant::Installer)), NodeVariant::Msi);
+        assert_eq!(node_variant(Some(Variant::Msi)), NodeVariant::Msi);
+        assert_eq!(node_variant(Some(Variant::Portable)), NodeVariant::Zip);
+        assert_eq!(node_variant(Some(Variant::Zip)), NodeVariant::Zip);
+        assert_eq!(node_variant(Some(Variant::Exe)), NodeVariant::Exe);
     }
-}
 
-pub fn get_winrar_link() -> String {
-    let result: (StatusCode, String) =
-        make_web_request("https://www.rarlab.com/download.htm").unwrap();
-    //let status: StatusCode = result.0; // Request status, used for debugging
-    let raw_html: String = result.1; // Request response (HTML)
-    //println!("{}", raw_html); // Used for debugging
-    let html: Html = Html::parse_document(&raw_html);
-    let selector: Selector = Selector::parse("a").unwrap();
-    let raw_link: Option<String> = html
-        .select(&selector)
-        .filter_map(|element| {
-            element
-                .value()
-                .attr("href")
-                .filter(|href| href.contains("/rar/winrar-x64-"))
-                .map(|element| String::from(element))
-        })
-        .next();
-    // println!("{:?}", link); // used for debugging
-    let filename = raw_link.unwrap_or_else(|| String::from("No download link found"));
-    if filename == "No download link found" {
-        return String::from("No download link found");
-    } else {
-        let link = format!("https://www.rarlab.com{filename}");
-        return link;
+    // The delta patch next to the installer is not a download, an appcast without an x64 installer is a miss
+    #[test]
+    fn vivaldi_resolves_from_its_appcast() {
+        let (resolved, paths) = resolve_from(
+            "/update/1.0/public/appcast.x64.xml",
+            VIVALDI,
+            get_vivaldi_link,
+        );
+        let resolved = resolved.unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://downloads.vivaldi.com/stable/Vivaldi.7.3.3635.12.x64.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("7.3.3635.12"));
+        assert_eq!(paths, ["/update/1.0/public/appcast.x64.xml"]);
+
+        let (missing, _) = resolve_from(
+            "/update/1.0/public/appcast.x64.xml",
+            &VIVALDI.replace(".x64.exe", ".x64.msi"),
+            get_vivaldi_link,
+        );
+        let e = missing.unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
     }
-}
 
-// endregion: System Utilities Functions
+    // The page also links the full package and the previous release, the first minimal setup is the current one
+    #[test]
+    fn amd_autodetect_resolves_from_the_drivers_page() {
+        let (resolved, _) = resolve_from(
+            "/en/support/download/drivers.html",
+            AMD,
+            get_amd_autodetect_link,
+        );
+        let resolved = resolved.unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://drivers.amd.com/drivers/installer/24.30/whql/amd-software-adrenalin-edition-25.3.1-minimalsetup-250312_web.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("25.3.1"));
+        assert_eq!(
+            resolved.referer.as_deref(),
+            Some("https://www.amd.com/en/support/download/drivers.html")
+        );
+
+        let (missing, _) = resolve_from(
+            "/en/support/download/drivers.html",
+            "<html><body><p>Select your product</p></body></html>",
+            get_amd_autodetect_link,
+        );
+        let e = missing.unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+    }
+
+    #[test]
+    fn roblox_resolves_from_its_client_settings() {
+        let (resolved, paths) = resolve_from(
+            "/v2/client-version/WindowsPlayer",
+            ROBLOX,
+            get_roblox_launcher_link,
+        );
+        let resolved = resolved.unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://setup.rbxcdn.com/version-4f7d3b1c2a9e8d60-Roblox.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("0.667.0.6670706"));
+        assert_eq!(
+            resolved.filename.as_deref(),
+            Some("RobloxPlayerInstaller.exe")
+        );
+        assert_eq!(paths, ["/v2/client-version/WindowsPlayer"]);
+
+        let (missing, _) = resolve_from(
+            "/v2/client-version/WindowsPlayer",
+            r#"{"version":"0.667.0.6670706"}"#,
+            get_roblox_launcher_link,
+        );
+        let e = missing.unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+    }
+}
@@ -0,0 +1,474 @@
+use crate::catalog::{Catalog, Category, ResolverKind, normalize_name};
+use crate::github;
+use crate::helper;
+use crate::http::{self, HttpConfig};
+use crate::links;
+use crate::node::{self, NodeVariant};
+use crate::options::{self, AppOptions};
+use crate::pipeline::{self, PipelineConfig};
+use crate::resolver::Arch;
+use crate::search::edit_distance;
+use ini::{Ini, Properties};
+use serde::Serialize;
+use std::fmt;
+
+// Sections holding settings rather than apps, with the keys each one reads
+pub const SETTINGS_SECTIONS: [(&str, &[&str]); 4] = [
+    ("http", &http::CONFIG_KEYS),
+    ("download", &pipeline::CONFIG_KEYS),
+    ("github", &github::CONFIG_KEYS),
+    ("helper", &helper::CONFIG_KEYS),
+];
+// Per-host header sections, [http.headers.<host>], accept any header name
+const HEADER_SECTION_PREFIX: &str = "http.headers.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    // The configuration works, possibly not the way it was meant to
+    Warning,
+    // A setting or key is ignored or makes `download` fail
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+// One problem found in config.ini
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub section: Option<String>,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        if let Some(section) = &self.section {
+            write!(f, "[{section}] ")?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "{key}: ")?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl Finding {
+    fn new(
+        severity: Severity,
+        section: Option<&str>,
+        key: Option<&str>,
+        message: String,
+    ) -> Finding {
+        Finding {
+            severity,
+            section: section.map(String::from),
+            key: key.map(String::from),
+            message,
+        }
+    }
+}
+
+// Everything `wsb config check` reports: the app selection (see check_selection), unknown sections and settings,
+// settings that don't parse and app options (versions, [category.Key] tables) the resolvers can't use
+pub fn check(catalog: &Catalog, config: &Ini) -> Vec<Finding> {
+    let mut findings = check_selection(catalog, config);
+    check_options(catalog, config, &mut findings);
+
+    for (section, properties) in config.iter() {
+        let Some(section) = section else {
+            for (key, _) in properties.iter() {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    None,
+                    Some(key),
+                    String::from("set outside of any section, it is ignored"),
+                ));
+            }
+            continue;
+        };
+        if Category::ALL.iter().any(|c| c.section_name() == section)
+            || table_category(section).is_some()
+        {
+            // Checked by check_selection and check_options
+        } else if let Some((_, keys)) = SETTINGS_SECTIONS.iter().find(|(name, _)| *name == section)
+        {
+            check_settings(section, keys, properties, &mut findings);
+        } else if !section.starts_with(HEADER_SECTION_PREFIX) {
+            let known = Category::ALL
+                .iter()
+                .map(Category::section_name)
+                .chain(SETTINGS_SECTIONS.iter().map(|(name, _)| *name));
+            findings.push(Finding::new(
+                Severity::Warning,
+                Some(section),
+                None,
+                unknown("section", &suggest(section, known)),
+            ));
+        }
+    }
+
+    if let Err(e) = HttpConfig::from_ini(config) {
+        findings.push(Finding::new(Severity::Error, None, None, e.to_string()));
+    }
+    if let Err(e) = PipelineConfig::from_ini(config) {
+        findings.push(Finding::new(Severity::Error, None, None, e.to_string()));
+    }
+    findings
+}
+
+// The problems that change which apps `download` picks up: missing category sections, keys and tables that aren't in the
+// catalog or sit in another category (neither is read), values other than true or false, which count as false, and
+// tables of apps that are set to false
+pub fn check_selection(catalog: &Catalog, config: &Ini) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for (section, properties) in config.iter() {
+        let Some((category, key)) = section.and_then(table_category) else {
+            continue;
+        };
+        let finding =
+            |severity: Severity, message: String| Finding::new(severity, section, None, message);
+        match catalog.get(key) {
+            Some(entry) if entry.category != category => findings.push(finding(
+                Severity::Error,
+                format!(
+                    "{key} belongs in [{}], this table is ignored",
+                    options::table_name(entry)
+                ),
+            )),
+            Some(entry) => {
+                let disabled = config
+                    .section(Some(category.section_name()))
+                    .and_then(|section| section.get(&entry.key))
+                    == Some("false");
+                if disabled && properties.get("enabled").is_none() {
+                    findings.push(finding(
+                        Severity::Warning,
+                        format!(
+                            "{key} = false in [{category}], set enabled = true here or remove that line to download it"
+                        ),
+                    ));
+                }
+            }
+            None => {
+                let in_category = catalog
+                    .in_category(category)
+                    .map(|entry| entry.key.as_str());
+                let mut suggestions: Vec<String> = suggest(key, in_category)
+                    .into_iter()
+                    .map(|key| format!("[{category}.{key}]"))
+                    .collect();
+                if let Some(entry) = catalog.find(key) {
+                    let table_name = format!("[{}]", options::table_name(entry));
+                    if !suggestions.contains(&table_name) {
+                        suggestions.insert(0, table_name);
+                    }
+                }
+                findings.push(finding(Severity::Error, unknown("app", &suggestions)));
+            }
+        }
+    }
+
+    for category in Category::ALL {
+        let section = category.section_name();
+        let Some(properties) = config.section(Some(section)) else {
+            // [category.Key] tables select their apps on their own
+            let has_tables = config
+                .sections()
+                .flatten()
+                .any(|name| table_category(name).is_some_and(|(c, _)| c == category));
+            if !has_tables {
+                findings.push(Finding::new(
+                    Severity::Warning,
+                    Some(section),
+                    None,
+                    String::from("section is missing, none of its apps will be downloaded"),
+                ));
+            }
+            continue;
+        };
+
+        for (key, value) in properties.iter() {
+            let finding =
+                |message: String| Finding::new(Severity::Error, Some(section), Some(key), message);
+            let Some(entry) = catalog.get(key) else {
+                let in_category = catalog
+                    .in_category(category)
+                    .map(|entry| entry.key.as_str());
+                let mut suggestions = suggest(key, in_category);
+                // Aliases and display names as well, "vscode" or "Notepad++"
+                if let Some(entry) = catalog.find(key)
+                    && !suggestions.contains(&entry.key)
+                {
+                    suggestions.insert(0, entry.key.clone());
+                }
+                findings.push(finding(unknown("key", &suggestions)));
+                continue;
+            };
+            if entry.category != category {
+                findings.push(finding(format!(
+                    "belongs in the [{}] section, it is ignored here",
+                    entry.category
+                )));
+                continue;
+            }
+            if !entry.takes_version() && value != "true" && value != "false" {
+                findings.push(finding(format!(
+                    "expected true or false, found \"{value}\" (counts as false)"
+                )));
+            }
+        }
+    }
+    findings
+}
+
+// Runs every app's options through the resolver mapping `download` uses, so invalid versions, unknown options and
+// options the app's resolver doesn't support are reported where they were set
+fn check_options(catalog: &Catalog, config: &Ini, findings: &mut Vec<Finding>) {
+    for entry in catalog.entries() {
+        let section_name = entry.category.section_name();
+        let table_name = options::table_name(entry);
+        let value = config
+            .section(Some(section_name))
+            .and_then(|section| section.get(&entry.key));
+        let table = config.section(Some(table_name.as_str()));
+        if value.is_none() && table.is_none() {
+            continue;
+        }
+
+        let (app_options, errors) = AppOptions::from_config(entry, value, table);
+        for (key, e) in &errors {
+            findings.push(Finding::new(
+                Severity::Error,
+                Some(&table_name),
+                Some(key),
+                e.clone(),
+            ));
+        }
+        if !app_options.enabled || !errors.is_empty() {
+            continue;
+        }
+        let finding = |severity: Severity, message: String| match table {
+            Some(_) => Finding::new(severity, Some(&table_name), None, message),
+            None => Finding::new(severity, Some(section_name), Some(&entry.key), message),
+        };
+        if let Some(Err(e)) = links::map_app_options(catalog, &entry.key, &app_options) {
+            findings.push(finding(Severity::Error, e));
+            continue;
+        }
+        if entry.resolver_kind() == Some(ResolverKind::Node)
+            && let Some(version) = &app_options.version
+            && let Ok(specs) = node::parse_specs(version, NodeVariant::Msi, Arch::X64)
+        {
+            for codename in node::codenames(&specs) {
+                findings.push(finding(
+                    Severity::Warning,
+                    format!(
+                        "\"{codename}\" is treated as an LTS codename, it is only checked against the release index when downloading"
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+// The category and app key of a [category.Key] table
+fn table_category(section: &str) -> Option<(Category, &str)> {
+    let (category, key) = section.split_once('.')?;
+    let category = Category::ALL
+        .into_iter()
+        .find(|c| c.section_name() == category)?;
+    Some((category, key))
+}
+
+fn check_settings(
+    section: &str,
+    keys: &[&str],
+    properties: &Properties,
+    findings: &mut Vec<Finding>,
+) {
+    for (key, _) in properties.iter() {
+        if keys.contains(&key) {
+            continue;
+        }
+        findings.push(Finding::new(
+            Severity::Warning,
+            Some(section),
+            Some(key),
+            unknown("setting", &suggest(key, keys.iter().copied())),
+        ));
+    }
+}
+
+fn unknown(what: &str, suggestions: &[String]) -> String {
+    match suggestions {
+        [] => format!("unknown {what}, it is ignored"),
+        [suggestion] => format!("unknown {what}, did you mean {suggestion}?"),
+        _ => format!(
+            "unknown {what}, did you mean one of {}?",
+            suggestions.join(", ")
+        ),
+    }
+}
+
+// Candidates within a few typos of name, closest first. Case and punctuation are ignored, so "firefox" suggests Firefox.
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let wanted = normalize_name(name);
+    let allowed = wanted.chars().count().div_ceil(3).max(2);
+    let mut close: Vec<(usize, &str)> = candidates
+        .map(|candidate| {
+            (
+                edit_distance(&wanted, &normalize_name(candidate)),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= allowed)
+        .collect();
+    close.sort_by_key(|(distance, _)| *distance);
+    close
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn missing_sections(config: &str) -> Vec<String> {
+        let config = Ini::load_from_str(config).unwrap();
+        check_selection(&Catalog::embedded(), &config)
+            .into_iter()
+            .filter(|finding| finding.message.starts_with("section is missing"))
+            .filter_map(|finding| finding.section)
+            .collect()
+    }
+
+    // Everything check reports for key, the category sections are always there so none is reported missing
+    fn findings_for(config: &str, key: &str) -> Vec<Finding> {
+        let mut config = Ini::load_from_str(config).unwrap();
+        for category in Category::ALL {
+            if config.section(Some(category.section_name())).is_none() {
+                config.with_section(Some(category.section_name()));
+            }
+        }
+        check(&Catalog::embedded(), &config)
+            .into_iter()
+            .filter(|finding| finding.key.as_deref() == Some(key))
+            .collect()
+    }
+
+    fn only_finding(config: &str, key: &str) -> Finding {
+        let findings = findings_for(config, key);
+        assert_eq!(findings.len(), 1, "{findings:?}");
+        findings.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn unknown_keys_get_suggestions() {
+        let finding = only_finding("[browser]\nLibrefox = true\n", "Librefox");
+        assert_eq!(finding.severity, Severity::Error);
+        assert_eq!(finding.section.as_deref(), Some("browser"));
+        // Firefox is a closer spelling, both are offered
+        assert_eq!(
+            finding.message,
+            "unknown key, did you mean one of Firefox, Librewolf?"
+        );
+
+        // Aliases are matched as well
+        let finding = only_finding("[programming]\nvscode = true\n", "vscode");
+        assert_eq!(
+            finding.message,
+            "unknown key, did you mean VisualStudioCode?"
+        );
+    }
+
+    #[test]
+    fn values_other_than_true_or_false() {
+        let finding = only_finding("[browser]\nFirefox = yes\n", "Firefox");
+        assert_eq!(finding.severity, Severity::Error);
+        assert_eq!(
+            finding.message,
+            "expected true or false, found \"yes\" (counts as false)"
+        );
+        // Python and Node.js take versions
+        assert!(findings_for("[programming]\nPython = 3.12\n", "Python").is_empty());
+        assert!(findings_for("[programming]\nNodeJS = 20.x\n", "NodeJS").is_empty());
+    }
+
+    #[test]
+    fn keys_in_another_category() {
+        let finding = only_finding("[browser]\nPython = true\n", "Python");
+        assert_eq!(finding.severity, Severity::Error);
+        assert_eq!(finding.section.as_deref(), Some("browser"));
+        assert_eq!(
+            finding.message,
+            "belongs in the [programming] section, it is ignored here"
+        );
+    }
+
+    #[test]
+    fn unknown_settings_are_warnings() {
+        let finding = only_finding("[http]\ntimeot = 5\n", "timeot");
+        assert_eq!(finding.severity, Severity::Warning);
+        assert_eq!(finding.section.as_deref(), Some("http"));
+        assert_eq!(finding.message, "unknown setting, did you mean timeout?");
+        let finding = only_finding("[download]\nparallel = 4\n", "parallel");
+        assert_eq!(finding.severity, Severity::Warning);
+        // Header sections take any name
+        assert!(
+            findings_for("[http.headers.example.com]\nX-Anything = 1\n", "X-Anything").is_empty()
+        );
+    }
+
+    // Used to hit the mapper's exit(0) and end the whole run
+    #[test]
+    fn apps_missing_from_the_catalog_are_findings() {
+        let finding = only_finding("[gaming]\nItchIoLauncher = true\n", "ItchIoLauncher");
+        assert_eq!(finding.severity, Severity::Error);
+        assert_eq!(finding.section.as_deref(), Some("gaming"));
+        assert!(finding.message.starts_with("unknown key"), "{finding}");
+        assert_eq!(
+            findings_for("[gaming]\nNexusManager = true\n", "NexusManager").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn app_tables_count_as_their_category() {
+        let missing = missing_sections(
+            "[browser]\n[gaming]\n[system]\n[programming.Python]\nversion = 3.13\n",
+        );
+        assert_eq!(missing, Vec::<String>::new());
+        let missing = missing_sections("[browser]\n[gaming]\n[system]\n");
+        assert_eq!(missing, ["programming"]);
+    }
+
+    #[test]
+    fn node_codenames_are_pointed_out() {
+        let config = Ini::load_from_str("[programming]\nNodeJS = lts, lst zip\n").unwrap();
+        let findings: Vec<Finding> = check(&Catalog::embedded(), &config)
+            .into_iter()
+            .filter(|finding| finding.key.as_deref() == Some("NodeJS"))
+            .collect();
+        assert_eq!(findings.len(), 1, "{findings:?}");
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert!(
+            findings[0]
+                .message
+                .contains("\"lst\" is treated as an LTS codename"),
+            "{}",
+            findings[0]
+        );
+    }
+}
@@ -1,43 +1,48 @@
 mod browser;
-mod cmd;
+mod catalog;
+mod checksum;
+mod cli;
+mod config;
+mod downloader;
+mod feed;
+mod filename;
 mod gaming;
+mod github;
+mod helper;
+mod http;
+mod index;
+mod info;
+mod init;
 mod links;
+mod lint;
+mod node;
+mod options;
+mod pipeline;
 mod programming;
+mod progress;
+mod python;
+mod resolver;
+mod search;
+mod sniff;
+#[cfg(test)]
+mod stub;
 mod system;
 mod utils;
 
-fn test() {
-    let app: gaming::Gaming = gaming::Gaming::RobloxLauncher;
-    match app.get_link() {
-        // link is returned from download_link() as either a String or None and is handled accordingly.
-        Some(link) => println!("Download link found for {:?}: {}", app, link),
-        None => println!("No download link found for {:?}.", app),
-    }
+use catalog::Catalog;
+use clap::Parser;
+use cli::Cli;
+use std::process::ExitCode;
 
-    let browser: browser::Browser = browser::Browser::SeaMonkey;
-    match browser.get_link() {
-        // link is returned from download_link() as either a String or None and is handled accordingly.
-        Some(link) => println!("Download link found for {:?}: {}", browser, link),
-        None => println!("No download link found for {:?}.", browser),
-    }
-
-    let programming: programming::Programming = programming::Programming::XAMPP;
-    match programming.get_link() {
-        Some(link) => println!("Download link found for {:?}: {}", programming, link),
-        None => println!("No download link found for {:?}", programming),
-    }
-
-    let system: system::Utility = system::Utility::WinRAR;
-    match system.get_link() {
-        Some(link) => println!("Download link found for {:?}: {}", system, link),
-        None => println!("No download link found for {:?}", system),
-    }
-
-    let arg: String = utils::get_arg();
-    let arg_enum: utils::ValidStarterArg = utils::map_arg_str_to_enum(arg);
-    utils::handle_starter_arg(arg_enum);
-}
-
-fn main() {
-    test();
+fn main() -> ExitCode {
+    // Parsed before the catalog is loaded so --help and --version work even with a broken runtime catalog
+    let cli = Cli::parse();
+    let catalog: Catalog = match Catalog::load() {
+        Ok(catalog) => catalog,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    cli.run(&catalog)
 }
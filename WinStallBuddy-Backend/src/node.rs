@@ -0,0 +1,557 @@
+use crate::checksum;
+use crate::http::HttpClient;
+use crate::resolver::{Arch, LinkResolver, ResolveError, ResolvedDownload};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+
+const DIST_URL: &str = "https://nodejs.org/dist";
+
+// What gets downloaded for a release
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeVariant {
+    // node-v22.11.0-x64.msi, node and npm installed and added to PATH
+    Msi,
+    // node-v22.11.0-win-x64.zip, a portable copy including npm
+    Zip,
+    // win-x64/node.exe on its own, without npm
+    Exe,
+}
+
+impl NodeVariant {
+    pub fn parse(value: &str) -> Option<NodeVariant> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "msi" => Some(NodeVariant::Msi),
+            "zip" => Some(NodeVariant::Zip),
+            "exe" => Some(NodeVariant::Exe),
+            _ => None,
+        }
+    }
+
+    // Name of the file in index.json's "files" list
+    fn index_name(&self, arch: Arch) -> String {
+        match self {
+            NodeVariant::Msi => format!("win-{arch}-msi"),
+            NodeVariant::Zip => format!("win-{arch}-zip"),
+            NodeVariant::Exe => format!("win-{arch}-exe"),
+        }
+    }
+
+    // Path of the file below dist/<version>/, as listed in SHASUMS256.txt
+    fn path(&self, version: &str, arch: Arch) -> String {
+        match self {
+            NodeVariant::Msi => format!("node-{version}-{arch}.msi"),
+            NodeVariant::Zip => format!("node-{version}-win-{arch}.zip"),
+            NodeVariant::Exe => format!("win-{arch}/node.exe"),
+        }
+    }
+}
+
+impl fmt::Display for NodeVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeVariant::Msi => f.write_str("msi"),
+            NodeVariant::Zip => f.write_str("zip"),
+            NodeVariant::Exe => f.write_str("exe"),
+        }
+    }
+}
+
+type Version = (u64, u64, u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+// One comparator of a semver range, partial versions are filled with zeros
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, version: Version) -> bool {
+        match self.op {
+            Op::Eq => version == self.version,
+            Op::Gt => version > self.version,
+            Op::Ge => version >= self.version,
+            Op::Lt => version < self.version,
+            Op::Le => version <= self.version,
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.op {
+            Op::Eq => "=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+        };
+        let (major, minor, patch) = self.version;
+        write!(f, "{op}{major}.{minor}.{patch}")
+    }
+}
+
+// Which releases a config value asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeSelector {
+    // The newest LTS release
+    Lts,
+    // The newest release, LTS or not
+    Current,
+    // The newest release of a named LTS line (iron, jod, ...)
+    Codename(String),
+    // A semver range, every comparator has to match: 22, 20.x, ^20.11.1, ~18.19, >=18 <21
+    Range(Vec<Comparator>),
+}
+
+impl fmt::Display for NodeSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeSelector::Lts => f.write_str("lts"),
+            NodeSelector::Current => f.write_str("current"),
+            NodeSelector::Codename(codename) => f.write_str(codename),
+            NodeSelector::Range(comparators) => {
+                let comparators: Vec<String> = comparators.iter().map(|c| c.to_string()).collect();
+                f.write_str(&comparators.join(" "))
+            }
+        }
+    }
+}
+
+impl NodeSelector {
+    fn matches(&self, release: &Release) -> bool {
+        match self {
+            NodeSelector::Lts => release.lts_name().is_some(),
+            NodeSelector::Current => true,
+            NodeSelector::Codename(codename) => release
+                .lts_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(codename)),
+            NodeSelector::Range(comparators) => release.semver().is_some_and(|version| {
+                comparators
+                    .iter()
+                    .all(|comparator| comparator.matches(version))
+            }),
+        }
+    }
+}
+
+// Parses "20", "20.11", "20.x", "20.11.1" into the version and how many components were given
+fn parse_partial(value: &str) -> Option<(Version, usize)> {
+    let value = value.strip_prefix('v').unwrap_or(value);
+    let mut parts: Vec<u64> = Vec::new();
+    for part in value.split('.') {
+        match part {
+            "x" | "X" | "*" => break,
+            part => parts.push(part.parse().ok()?),
+        }
+    }
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+    let given = parts.len();
+    parts.resize(3, 0);
+    Some(((parts[0], parts[1], parts[2]), given))
+}
+
+// The first version past a partial one: 20 -> 21.0.0, 20.11 -> 20.12.0
+fn bump((major, minor, patch): Version, given: usize) -> Version {
+    match given {
+        1 => (major + 1, 0, 0),
+        2 => (major, minor + 1, 0),
+        _ => (major, minor, patch + 1),
+    }
+}
+
+fn parse_comparators(value: &str) -> Option<Vec<Comparator>> {
+    let at = |op: Op, version: Version| Comparator { op, version };
+    let (op, rest) = ["^", "~", ">=", "<=", ">", "<", "="]
+        .iter()
+        .find_map(|op| value.strip_prefix(op).map(|rest| (*op, rest)))
+        .unwrap_or(("", value));
+    let (version, given) = parse_partial(rest)?;
+    let (major, minor, _) = version;
+
+    Some(match op {
+        "" | "=" if given == 3 => vec![at(Op::Eq, version)],
+        "" | "=" => vec![at(Op::Ge, version), at(Op::Lt, bump(version, given))],
+        // Changes left of the first non-zero component are breaking
+        "^" => {
+            let breaking = if major > 0 || given == 1 {
+                1
+            } else if minor > 0 || given == 2 {
+                2
+            } else {
+                3
+            };
+            vec![at(Op::Ge, version), at(Op::Lt, bump(version, breaking))]
+        }
+        "~" => vec![at(Op::Ge, version), at(Op::Lt, bump(version, given.min(2)))],
+        ">=" => vec![at(Op::Ge, version)],
+        ">" if given == 3 => vec![at(Op::Gt, version)],
+        ">" => vec![at(Op::Ge, bump(version, given))],
+        "<" => vec![at(Op::Lt, version)],
+        "<=" if given == 3 => vec![at(Op::Le, version)],
+        _ => vec![at(Op::Lt, bump(version, given))],
+    })
+}
+
+// Resolves a release selector to a Windows download through nodejs.org's dist index, checked against SHASUMS256.txt.
+// Catalog entries use it with resolver = "node".
+#[derive(Debug, Clone)]
+pub struct NodeResolver {
+    pub selector: NodeSelector,
+    pub variant: NodeVariant,
+    pub arch: Arch,
+}
+
+// Splits a config.ini value into one resolver per selector, "lts, 18 zip" asks for two downloads side by side.
+// Every selector can be followed by the variant (msi, zip or exe) and the architecture, in any order:
+//   NodeJS = lts, iron zip, >=18 <21 exe arm64
+// The returned labels tell the downloads apart in messages ("lts", "iron zip").
+pub fn parse_specs(
+    value: &str,
+    default_variant: NodeVariant,
+    default_arch: Arch,
+) -> Result<Vec<(String, NodeResolver)>, String> {
+    value
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| {
+            let mut variant = default_variant;
+            let mut arch = default_arch;
+            let mut selector_words: Vec<&str> = Vec::new();
+            for word in item.split_whitespace() {
+                if let Some(parsed) = NodeVariant::parse(word) {
+                    variant = parsed;
+                } else if let Some(parsed) = Arch::parse(word) {
+                    arch = parsed;
+                } else {
+                    selector_words.push(word);
+                }
+            }
+
+            let selector = match selector_words.as_slice() {
+                [] => NodeSelector::Lts,
+                [word] if matches!(word.to_ascii_lowercase().as_str(), "lts" | "true") => {
+                    NodeSelector::Lts
+                }
+                [word] if matches!(word.to_ascii_lowercase().as_str(), "current" | "latest") => {
+                    NodeSelector::Current
+                }
+                [word] if word.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    NodeSelector::Codename(word.to_ascii_lowercase())
+                }
+                words => {
+                    let mut comparators: Vec<Comparator> = Vec::new();
+                    for word in words {
+                        comparators.extend(parse_comparators(word).ok_or_else(|| {
+                            format!(
+                                "\"{word}\" is not a version, expected lts, current, an LTS codename or a semver range"
+                            )
+                        })?);
+                    }
+                    NodeSelector::Range(comparators)
+                }
+            };
+            let label = item.split_whitespace().collect::<Vec<&str>>().join(" ");
+            Ok((
+                label,
+                NodeResolver {
+                    selector,
+                    variant,
+                    arch,
+                },
+            ))
+        })
+        .collect()
+}
+
+// The codenames among the selectors, only the release index can tell whether they name an LTS line
+pub fn codenames(specs: &[(String, NodeResolver)]) -> Vec<&str> {
+    specs
+        .iter()
+        .filter_map(|(_, resolver)| match &resolver.selector {
+            NodeSelector::Codename(codename) => Some(codename.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+// One release of dist/index.json, newest first
+#[derive(Debug, Deserialize)]
+struct Release {
+    // v22.11.0
+    version: String,
+    #[serde(default)]
+    files: Vec<String>,
+    // The LTS codename, false for releases outside an LTS line
+    #[serde(default)]
+    lts: Value,
+}
+
+impl Release {
+    fn lts_name(&self) -> Option<&str> {
+        self.lts.as_str()
+    }
+
+    fn semver(&self) -> Option<Version> {
+        match parse_partial(&self.version)? {
+            (version, 3) => Some(version),
+            _ => None,
+        }
+    }
+}
+
+impl LinkResolver for NodeResolver {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        let index_url = format!("{DIST_URL}/index.json");
+        let (_, body) = http.get_text(&index_url)?;
+        let releases: Vec<Release> = serde_json::from_str(&body).map_err(|e| {
+            ResolveError::Parse(format!("Invalid Node.js release index {index_url}: {e}"))
+        })?;
+
+        // Any single word is read as a codename, a typo like "lst" is told apart from a line without a Windows download
+        if let NodeSelector::Codename(codename) = &self.selector
+            && !releases.iter().any(|release| {
+                release
+                    .lts_name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(codename))
+            })
+        {
+            let mut known: Vec<String> = Vec::new();
+            for name in releases.iter().filter_map(Release::lts_name) {
+                let name = name.to_ascii_lowercase();
+                if !known.contains(&name) {
+                    known.push(name);
+                }
+            }
+            return Err(ResolveError::SelectorMiss(format!(
+                "\"{codename}\" is not a Node.js LTS codename, expected lts, current, a semver range or one of {}",
+                known.join(", ")
+            )));
+        }
+
+        // Old releases don't ship every variant (no msi before 0.x, no arm64 before 19.9), those are skipped
+        let file = self.variant.index_name(self.arch);
+        let release = releases
+            .iter()
+            .filter(|release| self.selector.matches(release))
+            .find(|release| release.files.contains(&file))
+            .ok_or_else(|| {
+                ResolveError::SelectorMiss(format!(
+                    "No Node.js release matches {} with an {} {} download",
+                    self.selector, self.arch, self.variant
+                ))
+            })?;
+
+        let version = &release.version;
+        let path = self.variant.path(version, self.arch);
+        let mut resolved = ResolvedDownload::direct(format!("{DIST_URL}/{version}/{path}"))
+            .with_version(version.trim_start_matches('v'));
+        if self.variant == NodeVariant::Exe {
+            // Every release calls it node.exe, the version keeps several side by side
+            resolved = resolved.with_filename(format!("node-{version}-win-{}.exe", self.arch));
+        }
+        Ok(checksum::attach_sha256(
+            resolved,
+            checksum::nodejs_sha256(http, version, &path),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stub::{self, StubServer};
+
+    // Recorded nodejs.org/dist/index.json, cut down to a few releases of each line. v23.6.0 lists no arm64 msi.
+    const INDEX: &str = include_str!("../tests/fixtures/node/index.json");
+    // dist/v22.13.0/SHASUMS256.txt
+    const SHASUMS: &str = include_str!("../tests/fixtures/node/SHASUMS256.txt");
+
+    fn nodejs_org() -> StubServer {
+        StubServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/dist/index.json" => INDEX,
+                "/dist/v22.13.0/SHASUMS256.txt" => SHASUMS,
+                _ => return stub::response(404, &[], b"Not Found"),
+            };
+            stub::response(200, &[], body.as_bytes())
+        })
+    }
+
+    fn spec(value: &str) -> NodeResolver {
+        let specs = parse_specs(value, NodeVariant::Msi, Arch::X64).unwrap();
+        let [(_, resolver)] = &specs[..] else {
+            panic!("{value} is not a single spec");
+        };
+        resolver.clone()
+    }
+
+    fn range(value: &str) -> String {
+        spec(value).selector.to_string()
+    }
+
+    fn resolve(value: &str) -> Result<ResolvedDownload, ResolveError> {
+        spec(value).resolve(&nodejs_org().client())
+    }
+
+    fn version(value: &str) -> String {
+        resolve(value).unwrap().version.unwrap()
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(range("22"), ">=22.0.0 <23.0.0");
+        assert_eq!(range("20.x"), ">=20.0.0 <21.0.0");
+        assert_eq!(range("v20.11.1"), "=20.11.1");
+        assert_eq!(range("^20.11.1"), ">=20.11.1 <21.0.0");
+        assert_eq!(range("^0.12.3"), ">=0.12.3 <0.13.0");
+        assert_eq!(range("^0.0.3"), ">=0.0.3 <0.0.4");
+        assert_eq!(range("^0.0"), ">=0.0.0 <0.1.0");
+        assert_eq!(range("^0.x"), ">=0.0.0 <1.0.0");
+        assert_eq!(range("~18.19"), ">=18.19.0 <18.20.0");
+        assert_eq!(range("~18.19.1"), ">=18.19.1 <18.20.0");
+        assert_eq!(range("~18"), ">=18.0.0 <19.0.0");
+        // Partial bounds cover the whole partial version
+        assert_eq!(range(">20"), ">=21.0.0");
+        assert_eq!(range(">20.11"), ">=20.12.0");
+        assert_eq!(range(">20.11.1"), ">20.11.1");
+        assert_eq!(range("<=20"), "<21.0.0");
+        assert_eq!(range("<=20.11.1"), "<=20.11.1");
+        assert_eq!(range(">=18 <21"), ">=18.0.0 <21.0.0");
+    }
+
+    #[test]
+    fn selector_words() {
+        assert_eq!(spec("zip").selector, NodeSelector::Lts);
+        assert_eq!(spec("TRUE").selector, NodeSelector::Lts);
+        assert_eq!(spec("latest").selector, NodeSelector::Current);
+        assert_eq!(
+            spec("Iron").selector,
+            NodeSelector::Codename(String::from("iron"))
+        );
+
+        // Variant and arch words can sit anywhere in a spec
+        let resolver = spec("zip >=18 arm64 <21");
+        assert_eq!(resolver.variant, NodeVariant::Zip);
+        assert_eq!(resolver.arch, Arch::Arm64);
+        assert_eq!(resolver.selector.to_string(), ">=18.0.0 <21.0.0");
+
+        let specs =
+            parse_specs("lts, iron  zip,, 18 exe x86", NodeVariant::Msi, Arch::X64).unwrap();
+        let labels: Vec<&str> = specs.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, ["lts", "iron zip", "18 exe x86"]);
+        assert_eq!(codenames(&specs), ["iron"]);
+
+        for invalid in ["20.1.2.3", "^", ">=x", "iron-2", "lts 22"] {
+            let e = parse_specs(invalid, NodeVariant::Msi, Arch::X64).unwrap_err();
+            assert!(e.contains("is not a version"), "{invalid}: {e}");
+        }
+    }
+
+    #[test]
+    fn selectors_pick_the_newest_match() {
+        assert_eq!(version("lts"), "22.13.0");
+        assert_eq!(version("current"), "23.6.0");
+        assert_eq!(version("iron"), "20.18.1");
+        assert_eq!(version("22"), "22.13.0");
+        assert_eq!(version("20.x"), "20.18.1");
+        assert_eq!(version("^20.11.1"), "20.18.1");
+        assert_eq!(version("~20.11"), "20.11.1");
+        assert_eq!(version("~18.19"), "18.19.1");
+        assert_eq!(version(">=18 <21"), "20.18.1");
+        assert_eq!(version("<=20.11"), "20.11.1");
+        assert_eq!(version("^0.x"), "0.12.18");
+    }
+
+    #[test]
+    fn variants_and_archs() {
+        let resolved = resolve("lts").unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://nodejs.org/dist/v22.13.0/node-v22.13.0-x64.msi"
+        );
+        assert_eq!(resolved.filename, None);
+        let resolved = resolve("lts zip arm64").unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://nodejs.org/dist/v22.13.0/node-v22.13.0-win-arm64.zip"
+        );
+        // Every release names it node.exe, the filename carries the version
+        let resolved = resolve("lts exe").unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://nodejs.org/dist/v22.13.0/win-x64/node.exe"
+        );
+        assert_eq!(
+            resolved.filename.as_deref(),
+            Some("node-v22.13.0-win-x64.exe")
+        );
+        let resolved = resolve("lts msi x86").unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://nodejs.org/dist/v22.13.0/node-v22.13.0-x86.msi"
+        );
+    }
+
+    #[test]
+    fn releases_without_the_file_are_skipped() {
+        assert_eq!(version("current arm64"), "23.5.0");
+        assert_eq!(version("current zip arm64"), "23.6.0");
+        // Node.js 18 was never built for arm64
+        let e = resolve("18 arm64").unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+        assert!(e.to_string().contains("arm64 msi"), "{e}");
+    }
+
+    #[test]
+    fn sha256_comes_from_shasums() {
+        let sha256 = |path: &str| {
+            SHASUMS
+                .lines()
+                .find_map(|line| line.strip_suffix(path)?.split_whitespace().next())
+                .map(String::from)
+        };
+        let server = nodejs_org();
+        let resolved = spec("lts").resolve(&server.client()).unwrap();
+        assert_eq!(resolved.sha256, sha256("  node-v22.13.0-x64.msi"));
+        assert!(resolved.sha256.is_some());
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/dist/index.json", "/dist/v22.13.0/SHASUMS256.txt"]);
+
+        let resolved = resolve("lts exe").unwrap();
+        assert_eq!(resolved.sha256, sha256("  win-x64/node.exe"));
+        // No SHASUMS256.txt recorded for it, the download goes ahead unverified
+        assert_eq!(resolve("iron").unwrap().sha256, None);
+    }
+
+    #[test]
+    fn unknown_codenames_are_named() {
+        let e = resolve("lst").unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+        assert!(
+            e.to_string()
+                .contains("\"lst\" is not a Node.js LTS codename"),
+            "{e}"
+        );
+        assert!(e.to_string().contains("jod, iron, hydrogen"), "{e}");
+    }
+
+    #[test]
+    fn invalid_index_is_a_parse_error() {
+        let server = StubServer::start(|_| stub::response(200, &[], b"{\"error\": true}"));
+        let e = spec("lts").resolve(&server.client()).unwrap_err();
+        assert!(matches!(e, ResolveError::Parse(_)), "{e}");
+    }
+}
@@ -0,0 +1,366 @@
+use crate::catalog::CatalogEntry;
+use crate::resolver::Arch;
+use ini::Properties;
+use std::fmt;
+
+// Keys of a [category.Key] table, which of them an app supports depends on its resolver (see CatalogEntry::supported_options):
+//
+// [programming.NodeJS]
+// version = 22
+// arch = arm64
+// variant = zip
+// output_name = node-portable.zip
+pub const OPTION_KEYS: [&str; 7] = [
+    "enabled",
+    "version",
+    "channel",
+    "arch",
+    "variant",
+    "locale",
+    "output_name",
+];
+
+// Which kind of file to download when an app offers several
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Installer,
+    Portable,
+    Msi,
+    Zip,
+    // A bare .exe, which for Node.js is node.exe on its own rather than an installer
+    Exe,
+}
+
+impl Variant {
+    pub fn parse(value: &str) -> Option<Variant> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "installer" => Some(Variant::Installer),
+            "portable" => Some(Variant::Portable),
+            "msi" => Some(Variant::Msi),
+            "zip" => Some(Variant::Zip),
+            "exe" => Some(Variant::Exe),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Variant::Installer => f.write_str("installer"),
+            Variant::Portable => f.write_str("portable"),
+            Variant::Msi => f.write_str("msi"),
+            Variant::Zip => f.write_str("zip"),
+            Variant::Exe => f.write_str("exe"),
+        }
+    }
+}
+
+// What config.ini asks for one app: its `Key = value` line and the [category.Key] table on top of it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppOptions {
+    pub enabled: bool,
+    // Passed to the resolver as is, python and node parse it the same way as a versioned `Key = value`
+    pub version: Option<String>,
+    pub channel: Option<String>,
+    pub arch: Option<Arch>,
+    pub variant: Option<Variant>,
+    pub locale: Option<String>,
+    // File name the download is saved as, in place of the one the vendor picks
+    pub output_name: Option<String>,
+}
+
+impl AppOptions {
+    // Reads an app's options. The table's enabled defaults to the `Key = value` line, or to true when the app only has a table.
+    // Invalid options are returned with the key they were read from and left unset, the others still apply.
+    pub fn from_config(
+        entry: &CatalogEntry,
+        value: Option<&str>,
+        table: Option<&Properties>,
+    ) -> (AppOptions, Vec<(String, String)>) {
+        let mut options = match value {
+            Some(value) => AppOptions::from_value(entry, value),
+            None => AppOptions {
+                enabled: table.is_some(),
+                ..AppOptions::default()
+            },
+        };
+        let mut errors: Vec<(String, String)> = Vec::new();
+        for (key, value) in table.into_iter().flat_map(Properties::iter) {
+            if let Err(e) = options.set(key, value) {
+                errors.push((key.to_string(), e));
+            }
+        }
+        (options, errors)
+    }
+
+    // true, false or the versions of a versioned app (Python = 3.12, 3.13), anything else counts as false
+    pub fn from_value(entry: &CatalogEntry, value: &str) -> AppOptions {
+        match value {
+            "true" => AppOptions {
+                enabled: true,
+                ..AppOptions::default()
+            },
+            "false" => AppOptions::default(),
+            version if entry.takes_version() => AppOptions {
+                enabled: true,
+                version: Some(version.to_string()),
+                ..AppOptions::default()
+            },
+            _ => AppOptions::default(),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let text = || match value.is_empty() {
+            true => Err(String::from("is empty")),
+            false => Ok(Some(value.to_string())),
+        };
+        match key {
+            "enabled" => {
+                self.enabled = match value {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(format!("expected true or false, found \"{value}\"")),
+                }
+            }
+            "version" => self.version = text()?,
+            "channel" => self.channel = text()?,
+            "arch" => {
+                self.arch = Some(Arch::parse(value).ok_or_else(|| {
+                    format!("unknown arch \"{value}\", expected x86, x64 or arm64")
+                })?)
+            }
+            "variant" => {
+                self.variant = Some(Variant::parse(value).ok_or_else(|| {
+                    format!(
+                        "unknown variant \"{value}\", expected installer, portable, msi, zip or exe"
+                    )
+                })?)
+            }
+            "locale" => {
+                if !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                    return Err(format!(
+                        "\"{value}\" is not a locale, expected e.g. en-US or de"
+                    ));
+                }
+                self.locale = text()?
+            }
+            "output_name" => {
+                if value.contains(['/', '\\']) {
+                    return Err(String::from(
+                        "is a file name, the folder is set with --output-dir",
+                    ));
+                }
+                self.output_name = text()?
+            }
+            _ => {
+                return Err(format!(
+                    "unknown option, expected one of {}",
+                    OPTION_KEYS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Options other than enabled that are set, checked against what the app supports
+    pub fn set_options(&self) -> Vec<&'static str> {
+        [
+            ("version", self.version.is_some()),
+            ("channel", self.channel.is_some()),
+            ("arch", self.arch.is_some()),
+            ("variant", self.variant.is_some()),
+            ("locale", self.locale.is_some()),
+            ("output_name", self.output_name.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+// config.ini section holding an app's table, [programming.NodeJS]
+pub fn table_name(entry: &CatalogEntry) -> String {
+    format!("{}.{}", entry.category.section_name(), entry.key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::links;
+    use ini::{Ini, ParseOption};
+
+    // The options of key read from its `Key = value` line in [category] and its [category.Key] table,
+    // parsed without escapes like config.ini
+    fn read(key: &str, config: &str) -> (AppOptions, Vec<(String, String)>) {
+        let catalog = Catalog::embedded();
+        let entry = catalog.get(key).unwrap();
+        let parse_option = ParseOption {
+            enabled_escape: false,
+            ..ParseOption::default()
+        };
+        let config = Ini::load_from_str_opt(config, parse_option).unwrap();
+        let value = config
+            .section(Some(entry.category.section_name()))
+            .and_then(|section| section.get(&entry.key));
+        AppOptions::from_config(
+            entry,
+            value,
+            config.section(Some(table_name(entry).as_str())),
+        )
+    }
+
+    #[test]
+    fn boolean_values() {
+        assert_eq!(
+            read("Firefox", "[browser]\nFirefox = true\n"),
+            (
+                AppOptions {
+                    enabled: true,
+                    ..AppOptions::default()
+                },
+                Vec::new()
+            )
+        );
+        assert!(!read("Firefox", "[browser]\nFirefox = false\n").0.enabled);
+        // Versions are only taken by apps that have them
+        assert!(!read("Firefox", "[browser]\nFirefox = 136\n").0.enabled);
+        let (python, _) = read("Python", "[programming]\nPython = 3.12, 3.13\n");
+        assert!(python.enabled);
+        assert_eq!(python.version.as_deref(), Some("3.12, 3.13"));
+        // Not mentioned at all
+        assert!(!read("Python", "[programming]\n").0.enabled);
+    }
+
+    #[test]
+    fn tables_enable_their_app() {
+        let (options, errors) = read(
+            "NodeJS",
+            "[programming.NodeJS]\nversion = 22\narch = ARM64\nvariant = zip\noutput_name = node.zip\n",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            options,
+            AppOptions {
+                enabled: true,
+                version: Some(String::from("22")),
+                arch: Some(Arch::Arm64),
+                variant: Some(Variant::Zip),
+                output_name: Some(String::from("node.zip")),
+                ..AppOptions::default()
+            }
+        );
+        assert_eq!(
+            options.set_options(),
+            ["version", "arch", "variant", "output_name"]
+        );
+
+        // The table's enabled wins over the line, whichever way round
+        let config = "[programming]\nNodeJS = true\n[programming.NodeJS]\nenabled = false\n";
+        assert!(!read("NodeJS", config).0.enabled);
+        let config = "[programming]\nNodeJS = false\n[programming.NodeJS]\nenabled = true\n";
+        assert!(read("NodeJS", config).0.enabled);
+        // The table's version replaces the one on the line
+        let config = "[programming]\nPython = 3.12\n[programming.Python]\nversion = 3.13\n";
+        assert_eq!(read("Python", config).0.version.as_deref(), Some("3.13"));
+    }
+
+    #[test]
+    fn invalid_options_are_returned_with_their_key() {
+        let (options, errors) = read(
+            "Firefox",
+            "[browser.Firefox]\n\
+             arch = sparc\n\
+             variant = appx\n\
+             locale = en_US\n\
+             output_name = ..\\firefox.exe\n\
+             enabled = yes\n\
+             edition = esr\n\
+             channel = beta\n",
+        );
+        let keys: Vec<&str> = errors.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "arch",
+                "variant",
+                "locale",
+                "output_name",
+                "enabled",
+                "edition"
+            ]
+        );
+        assert!(errors[0].1.contains("unknown arch \"sparc\""), "{errors:?}");
+        assert!(
+            errors[1].1.contains("unknown variant \"appx\""),
+            "{errors:?}"
+        );
+        assert!(
+            errors[2].1.contains("\"en_US\" is not a locale"),
+            "{errors:?}"
+        );
+        assert!(errors[3].1.contains("is a file name"), "{errors:?}");
+        assert!(errors[4].1.contains("expected true or false"), "{errors:?}");
+        assert!(errors[5].1.starts_with("unknown option"), "{errors:?}");
+
+        // The valid ones still apply, the invalid ones are left unset
+        assert_eq!(
+            options,
+            AppOptions {
+                enabled: true,
+                channel: Some(String::from("beta")),
+                ..AppOptions::default()
+            }
+        );
+        let (_, errors) = read(
+            "NodeJS",
+            "[programming.NodeJS]\noutput_name = dist/node.msi\n",
+        );
+        assert_eq!(errors[0].0, "output_name");
+        let (_, errors) = read("NodeJS", "[programming.NodeJS]\nversion =\n");
+        assert_eq!(
+            errors,
+            [(String::from("version"), String::from("is empty"))]
+        );
+    }
+
+    #[test]
+    fn options_the_resolver_cant_use_are_errors() {
+        let catalog = Catalog::embedded();
+        let map = |key: &str, config: &str| {
+            let (options, errors) = read(key, config);
+            assert!(errors.is_empty(), "{errors:?}");
+            links::map_app_options(&catalog, key, &options).unwrap()
+        };
+        let e = map("Python", "[programming.Python]\nvariant = zip\n").unwrap_err();
+        assert_eq!(
+            e,
+            "Python doesn't support variant, only enabled, version, arch, output_name"
+        );
+        let e = map("VLC", "[system.VLC]\nlocale = de\n").unwrap_err();
+        assert!(e.starts_with("VLC doesn't support locale"), "{e}");
+        let e = map("Python", "[programming.Python]\narch = x86\n").unwrap_err();
+        assert!(e.contains("unsupported arch \"x86\""), "{e}");
+        assert!(map("NodeJS", "[programming.NodeJS]\nvariant = exe\n").is_ok());
+        assert!(map("VLC", "[system.VLC]\noutput_name = vlc.exe\n").is_ok());
+    }
+
+    #[test]
+    fn variants_parse_to_themselves() {
+        for variant in [
+            Variant::Installer,
+            Variant::Portable,
+            Variant::Msi,
+            Variant::Zip,
+            Variant::Exe,
+        ] {
+            assert_eq!(Variant::parse(&variant.to_string()), Some(variant));
+        }
+        assert_eq!(Variant::parse(" EXE "), Some(Variant::Exe));
+        assert_eq!(Variant::parse("appx"), None);
+    }
+}
@@ -0,0 +1,464 @@
+use crate::downloader;
+use crate::helper::{self, HelperConfig};
+use crate::http::HttpClient;
+use crate::links::KeyType;
+use crate::progress::{ProgressEvent, ProgressOutput, ProgressSubscriber};
+use crate::resolver::{LinkResolver, ResolutionKind, ResolveError, ResolvedDownload};
+use crate::utils::get_exe_dir;
+use ini::Ini;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Runtime;
+use tokio::sync::Semaphore;
+use tokio::task;
+use url::Url;
+
+// Downloads handed to DU_CLI.exe share one per-host slot pool under this name
+const EXTERNAL_HELPER_HOST: &str = "DU_CLI.exe";
+// Keys read from the [download] section, anything else is reported by `wsb config check`
+pub const CONFIG_KEYS: [&str; 4] = ["resolve_jobs", "jobs", "per_host", "progress"];
+
+// Limits read from the [download] section of config.ini:
+//
+// [download]
+// resolve_jobs = 8   ; vendor pages scraped at the same time
+// jobs = 4           ; downloads running at the same time
+// per_host = 2       ; downloads running at the same time against a single host
+// progress = bars    ; bars, json (one event per line on stdout, see src/progress.rs) or none
+//
+// The external helper's settings come from the [helper] section (see src/helper.rs).
+// output_dir and dry_run aren't read from config.ini, they are set from the command line (--output-dir, --dry-run).
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    pub resolve_jobs: usize,
+    pub jobs: usize,
+    pub per_host: usize,
+    pub progress: ProgressOutput,
+    pub helper: HelperConfig,
+    pub output_dir: PathBuf,
+    // Resolve every app without downloading anything
+    pub dry_run: bool,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> PipelineConfig {
+        PipelineConfig {
+            resolve_jobs: 8,
+            jobs: 4,
+            per_host: 2,
+            progress: ProgressOutput::Bars,
+            helper: HelperConfig::default(),
+            output_dir: PathBuf::from(get_exe_dir()).join("applications"),
+            dry_run: false,
+        }
+    }
+}
+
+impl PipelineConfig {
+    pub fn from_ini(config: &Ini) -> Result<PipelineConfig, Box<dyn Error>> {
+        let mut pipeline_config = PipelineConfig::default();
+
+        if let Some(section) = config.section(Some("download")) {
+            if let Some(resolve_jobs) = section.get("resolve_jobs") {
+                pipeline_config.resolve_jobs = parse_limit(resolve_jobs, "resolve_jobs")?;
+            }
+            if let Some(jobs) = section.get("jobs") {
+                pipeline_config.jobs = parse_limit(jobs, "jobs")?;
+            }
+            if let Some(per_host) = section.get("per_host") {
+                pipeline_config.per_host = parse_limit(per_host, "per_host")?;
+            }
+            if let Some(progress) = section.get("progress") {
+                pipeline_config.progress = ProgressOutput::parse(progress).ok_or_else(|| {
+                    format!("[download] progress must be bars, json or none, found \"{progress}\"")
+                })?;
+            }
+        }
+
+        pipeline_config.helper = HelperConfig::from_ini(config)?;
+        Ok(pipeline_config)
+    }
+}
+
+fn parse_limit(value: &str, key: &str) -> Result<usize, Box<dyn Error>> {
+    match value.trim().parse::<usize>() {
+        Ok(limit) if limit > 0 => Ok(limit),
+        _ => Err(
+            format!("[download] {key} must be a whole number above 0, found \"{value}\"").into(),
+        ),
+    }
+}
+
+// Result of processing a single app during `download`, every app gets one so a failure never stops the run
+#[derive(Debug)]
+pub enum DownloadOutcome {
+    Downloaded(String),
+    ExternalHelper,
+    Manual(String),
+    // --dry-run, the url the app resolved to (the helper's app name for helper downloads)
+    DryRun(String),
+    Failed(String),
+}
+
+struct Limits {
+    resolve: Semaphore,
+    download: Semaphore,
+    per_host: usize,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+    progress: Arc<dyn ProgressSubscriber>,
+    http: Arc<HttpClient>,
+    helper: HelperConfig,
+    output_dir: PathBuf,
+    dry_run: bool,
+}
+
+impl Limits {
+    fn new(
+        config: &PipelineConfig,
+        http: Arc<HttpClient>,
+        progress: Arc<dyn ProgressSubscriber>,
+    ) -> Limits {
+        Limits {
+            resolve: Semaphore::new(config.resolve_jobs),
+            download: Semaphore::new(config.jobs),
+            per_host: config.per_host,
+            hosts: Mutex::new(HashMap::new()),
+            progress,
+            http,
+            helper: config.helper.clone(),
+            output_dir: config.output_dir.clone(),
+            dry_run: config.dry_run,
+        }
+    }
+
+    fn host(&self, host: &str) -> Arc<Semaphore> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.per_host)))
+            .clone()
+    }
+}
+
+// Resolves and downloads every app concurrently within the configured limits.
+// Outcomes are returned in the order the apps were given, whichever finishes first.
+// Download progress and failures are reported to the subscriber selected by config.progress.
+pub fn run(
+    apps: Vec<(String, Result<KeyType, String>)>,
+    config: &PipelineConfig,
+    http: Arc<HttpClient>,
+) -> Vec<(String, DownloadOutcome)> {
+    let progress = config.progress.subscriber();
+    let runtime = match Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            let reason = format!("unable to start the download runtime: {e}");
+            return apps
+                .into_iter()
+                .map(|(app, _)| (app, DownloadOutcome::Failed(reason.clone())))
+                .collect();
+        }
+    };
+    runtime.block_on(run_all(apps, config, http, progress))
+}
+
+async fn run_all(
+    apps: Vec<(String, Result<KeyType, String>)>,
+    config: &PipelineConfig,
+    http: Arc<HttpClient>,
+    progress: Arc<dyn ProgressSubscriber>,
+) -> Vec<(String, DownloadOutcome)> {
+    let limits = Arc::new(Limits::new(config, http, progress));
+
+    let mut tasks = Vec::new();
+    for (app, resolver) in apps {
+        let handle = tokio::spawn(process_app(app.clone(), resolver, limits.clone()));
+        tasks.push((app, handle));
+    }
+
+    let mut outcomes: Vec<(String, DownloadOutcome)> = Vec::new();
+    for (app, handle) in tasks {
+        let outcome = handle.await.unwrap_or_else(|e| {
+            DownloadOutcome::Failed(format!("the download task stopped unexpectedly: {e}"))
+        });
+        outcomes.push((app, outcome));
+    }
+    outcomes
+}
+
+async fn process_app(
+    app: String,
+    resolver: Result<KeyType, String>,
+    limits: Arc<Limits>,
+) -> DownloadOutcome {
+    let outcome = resolve_and_download(&app, resolver, &limits).await;
+    if let DownloadOutcome::Failed(reason) = &outcome {
+        limits.progress.on_event(&ProgressEvent::Failed {
+            app: app.clone(),
+            reason: reason.clone(),
+        });
+    }
+    outcome
+}
+
+async fn resolve_and_download(
+    app: &str,
+    resolver: Result<KeyType, String>,
+    limits: &Limits,
+) -> DownloadOutcome {
+    let resolver = match resolver {
+        Ok(resolver) => resolver,
+        Err(reason) => return DownloadOutcome::Failed(reason),
+    };
+    let message = |message: String| {
+        limits.progress.on_event(&ProgressEvent::Message {
+            app: app.to_string(),
+            message,
+        });
+    };
+
+    let resolved: ResolvedDownload = {
+        let Ok(_permit) = limits.resolve.acquire().await else {
+            return DownloadOutcome::Failed(String::from("the resolve queue was closed"));
+        };
+        // The resolvers use the blocking http client, so they run on tokio's blocking thread pool
+        let http = limits.http.clone();
+        match task::spawn_blocking(move || resolver.resolve(&http)).await {
+            Ok(Ok(mut resolved)) => {
+                for note in resolved.notes.drain(..) {
+                    message(note);
+                }
+                resolved
+            }
+            Ok(Err(e)) => {
+                return DownloadOutcome::Failed(format!("unable to get download link: {e}"));
+            }
+            Err(e) => {
+                return DownloadOutcome::Failed(format!("the resolver stopped unexpectedly: {e}"));
+            }
+        }
+    };
+
+    let host = match resolved.kind {
+        ResolutionKind::Manual => {
+            message(format!(
+                "{app} can't be downloaded automatically, please download it from: {}",
+                resolved.url
+            ));
+            return DownloadOutcome::Manual(resolved.url);
+        }
+        ResolutionKind::ExternalHelper => String::from(EXTERNAL_HELPER_HOST),
+        ResolutionKind::Direct => Url::parse(&resolved.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+            .unwrap_or_default(),
+    };
+
+    if limits.dry_run {
+        return DownloadOutcome::DryRun(resolved.url);
+    }
+
+    // The host slot is taken first so a download waiting on a busy host doesn't hold a global slot
+    let host_slots = limits.host(&host);
+    let (Ok(_host_permit), Ok(_permit)) =
+        (host_slots.acquire().await, limits.download.acquire().await)
+    else {
+        return DownloadOutcome::Failed(String::from("the download queue was closed"));
+    };
+
+    // Handles cases where downloads are done through DUCLI (Download Utility Command Line Interface)
+    if resolved.kind == ResolutionKind::ExternalHelper {
+        let exe_dir = PathBuf::from(get_exe_dir());
+        let result = helper::run(
+            app,
+            "download",
+            &resolved.url,
+            &limits.output_dir,
+            &limits.helper,
+            &exe_dir,
+            &*limits.progress,
+        )
+        .await;
+        return match result {
+            Ok(outcome) => {
+                if let Some(version) = &outcome.version {
+                    message(format!("{app} {version} was downloaded by the helper"));
+                }
+                match outcome
+                    .path
+                    .as_deref()
+                    .and_then(|path| Path::new(path).file_name())
+                {
+                    Some(filename) => {
+                        DownloadOutcome::Downloaded(filename.to_string_lossy().to_string())
+                    }
+                    None => DownloadOutcome::ExternalHelper,
+                }
+            }
+            Err(e) => DownloadOutcome::Failed(ResolveError::from(e).to_string()),
+        };
+    }
+
+    let app = app.to_string();
+    let progress = limits.progress.clone();
+    let http = limits.http.clone();
+    let output_dir = limits.output_dir.clone();
+    // Handles the major of cases where a direct download link is supplied or scraped.
+    let download = task::spawn_blocking(move || {
+        match downloader::download_file(&http, &app, &resolved, &output_dir, &*progress) {
+            Ok(filename) => DownloadOutcome::Downloaded(filename),
+            Err(e) => DownloadOutcome::Failed(e.to_string()),
+        }
+    });
+    download.await.unwrap_or_else(|e| {
+        DownloadOutcome::Failed(format!("the download stopped unexpectedly: {e}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stub::{self, StubServer};
+    use std::fs;
+    use std::thread;
+    use std::time::Duration;
+
+    // Requests the stub is answering right now and the most it answered at once, per host and in total ("*")
+    #[derive(Default)]
+    struct InFlight {
+        now: Mutex<HashMap<String, usize>>,
+        most: Mutex<HashMap<String, usize>>,
+    }
+
+    impl InFlight {
+        fn enter(&self, host: &str) {
+            let mut now = self.now.lock().unwrap();
+            let mut most = self.most.lock().unwrap();
+            for key in [host, "*"] {
+                let count = now.entry(key.to_string()).or_default();
+                *count += 1;
+                let most = most.entry(key.to_string()).or_default();
+                *most = (*most).max(*count);
+            }
+        }
+
+        fn leave(&self, host: &str) {
+            let mut now = self.now.lock().unwrap();
+            for key in [host, "*"] {
+                *now.get_mut(key).unwrap() -= 1;
+            }
+        }
+
+        fn most(&self, key: &str) -> usize {
+            self.most.lock().unwrap().get(key).copied().unwrap_or(0)
+        }
+    }
+
+    // /<host>/<host><n>.exe takes longer the earlier the app is, so the transfers finish in reverse order
+    fn delayed_server(in_flight: Arc<InFlight>) -> StubServer {
+        StubServer::start(move |request| {
+            let Some((host, name)) = request.path[1..].split_once('/') else {
+                return stub::response(404, &[], b"");
+            };
+            if name == "missing.exe" {
+                return stub::response(404, &[], b"Not Found");
+            }
+            let n: u64 = name
+                .trim_start_matches(host)
+                .trim_end_matches(".exe")
+                .parse()
+                .unwrap();
+            in_flight.enter(host);
+            thread::sleep(Duration::from_millis(350 - 50 * n));
+            in_flight.leave(host);
+            stub::response(200, &[], b"MZ\x90\x00")
+        })
+    }
+
+    fn app(key: &str, url: &str) -> (String, Result<KeyType, String>) {
+        (key.to_string(), Ok(KeyType::Direct(url.to_string())))
+    }
+
+    #[test]
+    fn outcomes_keep_the_input_order_within_the_limits() {
+        let in_flight = Arc::new(InFlight::default());
+        let server = delayed_server(in_flight.clone());
+        let dir = stub::scratch_dir("pipeline-order");
+        let config = PipelineConfig {
+            jobs: 3,
+            per_host: 2,
+            progress: ProgressOutput::None,
+            output_dir: dir.clone(),
+            ..PipelineConfig::default()
+        };
+        let apps = vec![
+            app("A1", "https://a.example/a/a1.exe"),
+            app("A2", "https://a.example/a/a2.exe"),
+            app("B1", "https://b.example/b/b1.exe"),
+            (String::from("Broken"), Err(String::from("unknown option"))),
+            app("A3", "https://a.example/a/a3.exe"),
+            app("B2", "https://b.example/b/b2.exe"),
+            app("Missing", "https://b.example/b/missing.exe"),
+            app("A4", "https://a.example/a/a4.exe"),
+            app("C1", "https://c.example/c/c1.exe"),
+        ];
+
+        let outcomes = run(apps, &config, Arc::new(server.client()));
+        let summary: Vec<(&str, String)> = outcomes
+            .iter()
+            .map(|(app, outcome)| {
+                let outcome = match outcome {
+                    DownloadOutcome::Downloaded(filename) => filename.clone(),
+                    DownloadOutcome::Failed(_) => String::from("failed"),
+                    other => format!("{other:?}"),
+                };
+                (app.as_str(), outcome)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("A1", String::from("a1.exe")),
+                ("A2", String::from("a2.exe")),
+                ("B1", String::from("b1.exe")),
+                ("Broken", String::from("failed")),
+                ("A3", String::from("a3.exe")),
+                ("B2", String::from("b2.exe")),
+                ("Missing", String::from("failed")),
+                ("A4", String::from("a4.exe")),
+                ("C1", String::from("c1.exe")),
+            ]
+        );
+        assert!(
+            matches!(&outcomes[3].1, DownloadOutcome::Failed(reason) if reason == "unknown option")
+        );
+
+        assert_eq!(in_flight.most("*"), 3);
+        assert_eq!(in_flight.most("a"), 2);
+        assert!(in_flight.most("b") <= 2);
+        assert_eq!(in_flight.most("c"), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dry_run_resolves_without_downloading() {
+        let in_flight = Arc::new(InFlight::default());
+        let server = delayed_server(in_flight.clone());
+        let config = PipelineConfig {
+            dry_run: true,
+            progress: ProgressOutput::None,
+            ..PipelineConfig::default()
+        };
+        let outcomes = run(
+            vec![app("A1", "https://a.example/a/a1.exe")],
+            &config,
+            Arc::new(server.client()),
+        );
+        assert!(
+            matches!(&outcomes[0].1, DownloadOutcome::DryRun(url) if url == "https://a.example/a/a1.exe")
+        );
+        assert!(server.requests().is_empty());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::http::HttpClient;
 use crate::links;
+use crate::resolver::{LinkResolver, ResolveError, ResolvedDownload};
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum FilezillaType {
@@ -7,6 +9,7 @@ pub enum FilezillaType {
 }
 #[allow(dead_code)]
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Programming {
     AndroidStudio,
     AzureDataStudio,
@@ -21,7 +24,6 @@ pub enum Programming {
     MongoDB,
     MySQLWorkbench,
     Nmap,
-    NodeJS,
     NotepadPlusPlus,
     Npcap,
     Ollama,
@@ -30,19 +32,11 @@ pub enum Programming {
     OWASPZAP,
     PostgreSQL,
     Postman,
-    Python2_7_18,
-    Python3_8_10,
-    Python3_9_10,
-    Python3_10_10,
-    Python3_11_10,
-    Python3_12_9,
-    Python3_13_2,
     PuTTY,
     SublimeText,
     UTM,
     VisualStudio,
     VisualStudioCode,
-    VSCodium,
     WebStorm,
     WinGet,
     WireShark,
@@ -50,50 +44,81 @@ pub enum Programming {
 }
 
 impl Programming {
-    pub fn get_link(&self) -> Option<String> {
+    // Maps the `builtin` name of a catalog entry to its compiled in resolver
+    pub fn from_name(name: &str) -> Option<Programming> {
+        match name {
+            "AndroidStudio" => Some(Programming::AndroidStudio),
+            "AzureDataStudio" => Some(Programming::AzureDataStudio),
+            "BurpSuite" => Some(Programming::BurpSuite),
+            "DockerDesktop" => Some(Programming::DockerDesktop),
+            "FileZillaClient" => Some(Programming::FileZilla(FilezillaType::Client)),
+            "FileZillaServer" => Some(Programming::FileZilla(FilezillaType::Server)),
+            "GithubDesktop" => Some(Programming::GithubDesktop),
+            "EclipseIDE" => Some(Programming::EclipseIDE),
+            "FiddlerClassic" => Some(Programming::FiddlerClassic),
+            "FiddlerEverywhere" => Some(Programming::FiddlerEverywhere),
+            "Kubernetes" => Some(Programming::Kubernetes),
+            "MongoDB" => Some(Programming::MongoDB),
+            "MySQLWorkbench" => Some(Programming::MySQLWorkbench),
+            "Nmap" => Some(Programming::Nmap),
+            "NotepadPlusPlus" => Some(Programming::NotepadPlusPlus),
+            "Npcap" => Some(Programming::Npcap),
+            "Ollama" => Some(Programming::Ollama),
+            "OracleVirtualBox" => Some(Programming::OracleVirtualBox),
+            "OracleVirtualBoxExtPack" => Some(Programming::OracleVirtualBoxExtPack),
+            "OWASPZAP" => Some(Programming::OWASPZAP),
+            "PostgreSQL" => Some(Programming::PostgreSQL),
+            "Postman" => Some(Programming::Postman),
+            "PuTTY" => Some(Programming::PuTTY),
+            "SublimeText" => Some(Programming::SublimeText),
+            "UTM" => Some(Programming::UTM),
+            "VisualStudio" => Some(Programming::VisualStudio),
+            "VisualStudioCode" => Some(Programming::VisualStudioCode),
+            "WebStorm" => Some(Programming::WebStorm),
+            "WinGet" => Some(Programming::WinGet),
+            "WireShark" => Some(Programming::WireShark),
+            "XAMPP" => Some(Programming::XAMPP),
+            _ => None,
+        }
+    }
+}
+
+impl LinkResolver for Programming {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
         match self {
-            Programming::AndroidStudio => Some(links::get_android_studio_link()),
-            Programming::AzureDataStudio => Some(links::get_azure_link()),
-            Programming::BurpSuite => Some(links::get_burp_suite_link()),
-            Programming::DockerDesktop => Some(links::get_docker_desktop_link()),
+            Programming::AndroidStudio => links::get_android_studio_link(),
+            Programming::AzureDataStudio => links::get_azure_link(),
+            Programming::BurpSuite => links::get_burp_suite_link(http),
+            Programming::DockerDesktop => links::get_docker_desktop_link(),
             Programming::FileZilla(filezilla_type) => match filezilla_type {
-                FilezillaType::Client => Some(links::get_filezilla_link(FilezillaType::Client)),
-                FilezillaType::Server => Some(links::get_filezilla_link(FilezillaType::Server)),
+                FilezillaType::Client => links::get_filezilla_link(FilezillaType::Client),
+                FilezillaType::Server => links::get_filezilla_link(FilezillaType::Server),
             },
-            Programming::GithubDesktop => Some(links::get_github_desktop_link()),
-            Programming::EclipseIDE => Some(links::get_eclipse_ide_link()),
-            Programming::FiddlerClassic => Some(links::get_fiddler_classic_link()),
-            Programming::FiddlerEverywhere => Some(links::get_fiddler_everywhere_link()),
-            Programming::Kubernetes => Some(links::get_kubernetes_link()),
-            Programming::MongoDB => Some(links::get_mongodb_link()),
-            Programming::MySQLWorkbench => Some(links::get_mysql_workbench_link()),
-            Programming::Npcap => Some(links::get_npcap_link()),
-            Programming::Nmap => Some(links::get_nmap_link()),
-            Programming::NodeJS => Some(links::get_nodejs_link()),
-            Programming::NotepadPlusPlus => Some(links::get_notepadplusplus_link()),
-            Programming::Ollama => Some(links::get_ollama_link()),
-            Programming::OracleVirtualBox => Some(links::get_oracle_virtualbox_link()),
-            Programming::OracleVirtualBoxExtPack => Some(links::get_oracle_vbox_ext_pack_link()),
-            Programming::OWASPZAP => Some(links::get_owasp_zap_link()),
-            Programming::PostgreSQL => Some(links::get_postgresql_link()),
-            Programming::Postman => Some(links::get_postman_link()),
-            Programming::Python2_7_18 => Some(links::get_python2_7_18_link()),
-            Programming::Python3_8_10 => Some(links::get_python3_8_10_link()),
-            Programming::Python3_9_10 => Some(links::get_python3_9_10_link()),
-            Programming::Python3_10_10 => Some(links::get_python3_10_10_link()),
-            Programming::Python3_11_10 => Some(links::get_python3_11_10_link()),
-            Programming::Python3_12_9 => Some(links::get_python3_12_9_link()),
-            Programming::Python3_13_2 => Some(links::get_python3_13_2_link()),
-            Programming::PuTTY => Some(links::get_putty_link()),
-            Programming::SublimeText => Some(links::get_sublime_text_link()),
-            Programming::UTM => Some(links::get_utm_link()),
-            Programming::VisualStudio => Some(links::get_visual_studio_link()),
-            Programming::VisualStudioCode => Some(links::get_vscode_link()),
-            Programming::VSCodium => Some(links::get_vscodium_link()),
-            Programming::WebStorm => Some(links::get_webstorm_link()),
-            Programming::WinGet => Some(links::get_winget_link()),
-            Programming::WireShark => Some(links::get_wireshark_link()),
-            Programming::XAMPP => Some(links::get_xampp_link()),
+            Programming::GithubDesktop => links::get_github_desktop_link(),
+            Programming::EclipseIDE => links::get_eclipse_ide_link(http),
+            Programming::FiddlerClassic => links::get_fiddler_classic_link(),
+            Programming::FiddlerEverywhere => links::get_fiddler_everywhere_link(),
+            Programming::Kubernetes => links::get_kubernetes_link(http),
+            Programming::MongoDB => links::get_mongodb_link(http),
+            Programming::MySQLWorkbench => links::get_mysql_workbench_link(),
+            Programming::Npcap => links::get_npcap_link(http),
+            Programming::Nmap => links::get_nmap_link(http),
+            Programming::NotepadPlusPlus => links::get_notepadplusplus_link(http),
+            Programming::Ollama => links::get_ollama_link(),
+            Programming::OracleVirtualBox => links::get_oracle_virtualbox_link(http),
+            Programming::OracleVirtualBoxExtPack => links::get_oracle_vbox_ext_pack_link(http),
+            Programming::OWASPZAP => links::get_owasp_zap_link(http),
+            Programming::PostgreSQL => links::get_postgresql_link(http),
+            Programming::Postman => links::get_postman_link(),
+            Programming::PuTTY => links::get_putty_link(http),
+            Programming::SublimeText => links::get_sublime_text_link(http),
+            Programming::UTM => links::get_utm_link(),
+            Programming::VisualStudio => links::get_visual_studio_link(),
+            Programming::VisualStudioCode => links::get_vscode_link(),
+            Programming::WebStorm => links::get_webstorm_link(),
+            Programming::WinGet => links::get_winget_link(),
+            Programming::WireShark => links::get_wireshark_link(http),
+            Programming::XAMPP => links::get_xampp_link(http),
         }
     }
 }
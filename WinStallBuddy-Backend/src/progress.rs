@@ -0,0 +1,343 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How often Progress events are emitted while a download is streaming
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+// Events emitted for every app while `download` runs, subscribers receive them from the download threads in order per app.
+// Serialized as one JSON object per line with an "event" field (see JsonLinesProgress), e.g.
+// {"event":"progress","app":"VLC","bytes":1048576,"total":44040192,"bytes_per_second":524288}
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    // The transfer started, resumed_from is above 0 when a .part file was resumed
+    Started {
+        app: String,
+        filename: String,
+        total: Option<u64>,
+        resumed_from: u64,
+    },
+    // bytes and total include the resumed bytes, bytes_per_second only covers this run
+    Progress {
+        app: String,
+        bytes: u64,
+        total: Option<u64>,
+        bytes_per_second: u64,
+    },
+    // verified is true when the file matched a published or pinned SHA-256
+    Finished {
+        app: String,
+        filename: String,
+        bytes: u64,
+        verified: bool,
+    },
+    Failed {
+        app: String,
+        reason: String,
+    },
+    // Anything else worth telling the user about an app: a restarted download, an unverified checksum
+    Message {
+        app: String,
+        message: String,
+    },
+}
+
+pub trait ProgressSubscriber: Send + Sync {
+    fn on_event(&self, event: &ProgressEvent);
+}
+
+// Which subscriber `download` reports to, set with `progress` in the [download] section of config.ini or --json
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressOutput {
+    Bars,
+    Json,
+    None,
+}
+
+impl ProgressOutput {
+    pub fn parse(value: &str) -> Option<ProgressOutput> {
+        match value.trim() {
+            "bars" => Some(ProgressOutput::Bars),
+            "json" => Some(ProgressOutput::Json),
+            "none" => Some(ProgressOutput::None),
+            _ => None,
+        }
+    }
+
+    pub fn subscriber(&self) -> Arc<dyn ProgressSubscriber> {
+        match self {
+            ProgressOutput::Bars => Arc::new(TerminalProgress::new()),
+            ProgressOutput::Json => Arc::new(JsonLinesProgress::new(std::io::stdout())),
+            ProgressOutput::None => Arc::new(NoProgress),
+        }
+    }
+}
+
+// Used when progress output is disabled
+pub struct NoProgress;
+
+impl ProgressSubscriber for NoProgress {
+    fn on_event(&self, _event: &ProgressEvent) {}
+}
+
+// Writes every event as a JSON line, meant for frontends (the Qt GUI) reading wsb's stdout
+pub struct JsonLinesProgress<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonLinesProgress<W> {
+    pub fn new(writer: W) -> JsonLinesProgress<W> {
+        JsonLinesProgress {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<W: Write + Send> ProgressSubscriber for JsonLinesProgress<W> {
+    fn on_event(&self, event: &ProgressEvent) {
+        let Ok(line) = serde_json::to_string(event) else {
+            return;
+        };
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        // A closed pipe shouldn't stop the downloads, the events are informational
+        let _ = writeln!(writer, "{line}");
+        let _ = writer.flush();
+    }
+}
+
+// Renders one progress bar per running download, bars are removed once their download ends
+pub struct TerminalProgress {
+    bars: MultiProgress,
+    running: Mutex<HashMap<String, ProgressBar>>,
+}
+
+impl TerminalProgress {
+    pub fn new() -> TerminalProgress {
+        TerminalProgress {
+            bars: MultiProgress::new(),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Printed above the bars, falls back to stdout when stderr isn't a terminal (the bars are hidden then)
+    fn println(&self, line: &str) {
+        if self.bars.is_hidden() || self.bars.println(line).is_err() {
+            println!("{line}");
+        }
+    }
+
+    fn new_bar(&self, app: &str, total: Option<u64>) -> ProgressBar {
+        let bar = match total {
+            Some(total) => {
+                let bar = ProgressBar::new(total);
+                bar.set_style(
+                    ProgressStyle::with_template(
+                        "{prefix:<20} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec} ETA {eta}",
+                    )
+                    .unwrap_or_else(|_| ProgressStyle::default_bar())
+                    .progress_chars("=> "),
+                );
+                bar
+            }
+            // Without a Content-Length there is nothing to fill a bar against
+            None => {
+                let bar = ProgressBar::new_spinner();
+                bar.set_style(
+                    ProgressStyle::with_template("{prefix:<20} {spinner} {bytes} {bytes_per_sec}")
+                        .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+                );
+                bar
+            }
+        };
+        bar.set_prefix(app.to_string());
+        self.bars.add(bar)
+    }
+}
+
+impl Default for TerminalProgress {
+    fn default() -> TerminalProgress {
+        TerminalProgress::new()
+    }
+}
+
+impl ProgressSubscriber for TerminalProgress {
+    fn on_event(&self, event: &ProgressEvent) {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            ProgressEvent::Started {
+                app,
+                filename,
+                total,
+                resumed_from,
+            } => {
+                if *resumed_from > 0 {
+                    self.println(&format!(
+                        "Downloading: {filename} (resuming from {resumed_from} bytes)"
+                    ));
+                } else {
+                    self.println(&format!("Downloading: {filename}"));
+                }
+                let bar = self.new_bar(app, *total);
+                bar.set_position(*resumed_from);
+                bar.reset_eta();
+                running.insert(app.clone(), bar);
+            }
+            ProgressEvent::Progress { app, bytes, .. } => {
+                if let Some(bar) = running.get(app) {
+                    bar.set_position(*bytes);
+                }
+            }
+            ProgressEvent::Finished {
+                app,
+                filename,
+                verified,
+                ..
+            } => {
+                if let Some(bar) = running.remove(app) {
+                    bar.finish_and_clear();
+                    self.bars.remove(&bar);
+                }
+                if *verified {
+                    self.println(&format!(
+                        "Finished downloaded for: {filename} (SHA-256 verified)"
+                    ));
+                } else {
+                    self.println(&format!("Finished downloaded for: {filename}"));
+                }
+            }
+            ProgressEvent::Failed { app, reason } => {
+                if let Some(bar) = running.remove(app) {
+                    bar.abandon();
+                    self.bars.remove(&bar);
+                }
+                self.println(&format!("{app} failed ({reason}), continuing..."));
+            }
+            ProgressEvent::Message { message, .. } => self.println(message),
+        }
+    }
+}
+
+// Throttles Progress events for a single transfer and computes its speed
+pub struct TransferMeter {
+    started: Instant,
+    last_emit: Instant,
+    resumed_from: u64,
+}
+
+impl TransferMeter {
+    pub fn new(resumed_from: u64) -> TransferMeter {
+        let now = Instant::now();
+        TransferMeter {
+            started: now,
+            last_emit: now,
+            resumed_from,
+        }
+    }
+
+    // Returns the current speed when a Progress event is due
+    pub fn tick(&mut self, bytes: u64) -> Option<u64> {
+        if self.last_emit.elapsed() < PROGRESS_INTERVAL {
+            return None;
+        }
+        self.last_emit = Instant::now();
+        Some(self.bytes_per_second(bytes))
+    }
+
+    pub fn bytes_per_second(&self, bytes: u64) -> u64 {
+        let seconds = self.started.elapsed().as_secs_f64();
+        if seconds <= 0.0 {
+            return 0;
+        }
+        (bytes.saturating_sub(self.resumed_from) as f64 / seconds) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn json_lines(events: &[ProgressEvent]) -> Vec<Value> {
+        let progress = JsonLinesProgress::new(Vec::new());
+        for event in events {
+            progress.on_event(event);
+        }
+        let output = String::from_utf8(progress.writer.into_inner().unwrap()).unwrap();
+        output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn json_lines_are_tagged_with_the_event() {
+        let lines = json_lines(&[
+            ProgressEvent::Started {
+                app: String::from("VLC"),
+                filename: String::from("vlc-3.0.21-win64.exe"),
+                total: None,
+                resumed_from: 1024,
+            },
+            ProgressEvent::Progress {
+                app: String::from("VLC"),
+                bytes: 1048576,
+                total: Some(44040192),
+                bytes_per_second: 524288,
+            },
+            ProgressEvent::Finished {
+                app: String::from("VLC"),
+                filename: String::from("vlc-3.0.21-win64.exe"),
+                bytes: 44040192,
+                verified: true,
+            },
+            ProgressEvent::Failed {
+                app: String::from("Brave"),
+                reason: String::from("network error"),
+            },
+            ProgressEvent::Message {
+                app: String::from("Brave"),
+                message: String::from("restarting the download"),
+            },
+        ]);
+        assert_eq!(
+            lines,
+            [
+                json!({"event": "started", "app": "VLC", "filename": "vlc-3.0.21-win64.exe", "total": null, "resumed_from": 1024}),
+                json!({"event": "progress", "app": "VLC", "bytes": 1048576, "total": 44040192, "bytes_per_second": 524288}),
+                json!({"event": "finished", "app": "VLC", "filename": "vlc-3.0.21-win64.exe", "bytes": 44040192, "verified": true}),
+                json!({"event": "failed", "app": "Brave", "reason": "network error"}),
+                json!({"event": "message", "app": "Brave", "message": "restarting the download"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_is_throttled_to_the_interval() {
+        let mut meter = TransferMeter::new(0);
+        assert_eq!(meter.tick(1024), None);
+
+        meter.last_emit -= PROGRESS_INTERVAL;
+        assert!(meter.tick(2048).is_some());
+        // The interval starts over from the emitted event
+        assert_eq!(meter.tick(4096), None);
+        meter.last_emit -= PROGRESS_INTERVAL / 2;
+        assert_eq!(meter.tick(8192), None);
+        meter.last_emit -= PROGRESS_INTERVAL / 2;
+        assert!(meter.tick(8192).is_some());
+    }
+
+    // Resumed bytes were downloaded by an earlier run and don't count towards the speed
+    #[test]
+    fn speed_covers_this_run_only() {
+        let mut meter = TransferMeter::new(1000);
+        meter.started -= Duration::from_secs(2);
+        meter.last_emit = meter.started;
+        let speed = meter.tick(3000).unwrap();
+        assert!((990..=1000).contains(&speed), "{speed}");
+        assert_eq!(meter.bytes_per_second(500), 0);
+    }
+}
@@ -0,0 +1,391 @@
+use crate::checksum::{self, normalize_sha256};
+use crate::http::HttpClient;
+use crate::index::compare_versions;
+use crate::resolver::{Arch, LinkResolver, ResolveError, ResolvedDownload};
+use serde_json::Value;
+use std::fmt;
+
+const RELEASES_URL: &str = "https://www.python.org/api/v2/downloads/release/?is_published=true";
+const RELEASE_FILES_URL: &str = "https://www.python.org/api/v2/downloads/release_file/?release=";
+// Security-only releases ship as source only, this many of the newest matching releases are checked for an installer
+const MAX_RELEASES_TRIED: usize = 5;
+
+// Which releases a config value asks for:
+//   latest (or true) - the newest release
+//   3.12 or 3.12.*   - the newest release of that series
+//   3.12.4           - that exact release, prereleases (3.14.0rc1) can only be asked for this way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSpec {
+    Latest,
+    Series(String),
+    Exact(String),
+}
+
+impl fmt::Display for VersionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionSpec::Latest => f.write_str("latest"),
+            VersionSpec::Series(series) => write!(f, "{series}.*"),
+            VersionSpec::Exact(exact) => f.write_str(exact),
+        }
+    }
+}
+
+impl VersionSpec {
+    pub fn parse(value: &str) -> Option<VersionSpec> {
+        let value = value.trim().to_ascii_lowercase();
+        if value == "latest" || value == "true" {
+            return Some(VersionSpec::Latest);
+        }
+        let version = value.strip_suffix(".*").unwrap_or(&value);
+        let parts: Vec<&str> = version.split('.').collect();
+        if parts.is_empty() || parts.len() > 3 || parts.iter().any(|part| part.is_empty()) {
+            return None;
+        }
+        let (numbers, last) = parts.split_at(parts.len() - 1);
+        if !numbers
+            .iter()
+            .all(|part| part.chars().all(|c| c.is_ascii_digit()))
+        {
+            return None;
+        }
+        // Only the last component can carry a prerelease tag (0rc1, 0b2, 0a1)
+        let last = last[0];
+        if !last.starts_with(|c: char| c.is_ascii_digit())
+            || !last.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return None;
+        }
+        if parts.len() == 3 && !value.ends_with(".*") {
+            Some(VersionSpec::Exact(version.to_string()))
+        } else if last.chars().all(|c| c.is_ascii_digit()) {
+            Some(VersionSpec::Series(version.to_string()))
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, version: &str, prerelease: bool) -> bool {
+        match self {
+            VersionSpec::Latest => !prerelease,
+            VersionSpec::Series(series) => {
+                !prerelease
+                    && version
+                        .strip_prefix(series.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            }
+            VersionSpec::Exact(exact) => version == exact,
+        }
+    }
+}
+
+// Resolves a version spec to a Windows installer through python.org's downloads API, one entry covers every release
+// so new patch versions need no code change. Catalog entries use it with resolver = "python".
+#[derive(Debug, Clone)]
+pub struct PythonResolver {
+    pub spec: VersionSpec,
+    pub arch: Arch,
+}
+
+// Splits a config.ini value into one resolver per version spec, "3.12, 3.13 arm64" asks for two installers side by side.
+// Every spec can be followed by the architecture to download, default_arch is used when it's left out.
+// The returned labels tell the installers apart in messages ("3.12", "3.13 arm64").
+pub fn parse_specs(
+    value: &str,
+    default_arch: Arch,
+) -> Result<Vec<(String, PythonResolver)>, String> {
+    value
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| {
+            let mut words = item.split_whitespace();
+            let version = words.next().unwrap_or_default();
+            let spec = VersionSpec::parse(version).ok_or_else(|| {
+                format!("\"{version}\" is not a version, expected latest, 3.12, 3.13.* or 3.12.4")
+            })?;
+            let arch = match words.next() {
+                Some(arch) => match Arch::parse(arch) {
+                    Some(Arch::X86) | None => {
+                        return Err(format!(
+                            "unsupported arch \"{arch}\", expected x64 or arm64"
+                        ));
+                    }
+                    Some(arch) => arch,
+                },
+                None => default_arch,
+            };
+            if let Some(extra) = words.next() {
+                return Err(format!("unexpected \"{extra}\" after \"{version}\""));
+            }
+            let label = item.split_whitespace().collect::<Vec<&str>>().join(" ");
+            Ok((label, PythonResolver { spec, arch }))
+        })
+        .collect()
+}
+
+impl LinkResolver for PythonResolver {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
+        let releases = get_json(http, RELEASES_URL)?;
+        let mut candidates: Vec<(String, String)> = releases
+            .as_array()
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .filter_map(|release| {
+                let version = release["name"].as_str()?.strip_prefix("Python ")?.trim();
+                let prerelease = release["pre_release"].as_bool().unwrap_or(false);
+                let id = release["resource_uri"]
+                    .as_str()?
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()?;
+                self.spec
+                    .matches(version, prerelease)
+                    .then(|| (version.to_string(), id.to_string()))
+            })
+            .collect();
+        candidates.sort_by(|(a, _), (b, _)| compare_versions(b, a));
+
+        for (version, id) in candidates.into_iter().take(MAX_RELEASES_TRIED) {
+            let files = get_json(http, &format!("{RELEASE_FILES_URL}{id}"))?;
+            if let Some(resolved) = self.select_installer(&files) {
+                return Ok(resolved.with_version(version));
+            }
+        }
+        Err(ResolveError::SelectorMiss(format!(
+            "No Python release matching {} has a Windows {} installer",
+            self.spec, self.arch
+        )))
+    }
+}
+
+impl PythonResolver {
+    // python-3.12.4-amd64.exe, python-3.12.4-arm64.exe and python-2.7.18.amd64.msi, the web installers and
+    // embeddable zips are skipped
+    fn select_installer(&self, files: &Value) -> Option<ResolvedDownload> {
+        let file = files.as_array()?.iter().find(|file| {
+            let Some(filename) = file["url"].as_str().and_then(|url| url.rsplit('/').next()) else {
+                return false;
+            };
+            let lower = filename.to_ascii_lowercase();
+            (lower.ends_with(".exe") || lower.ends_with(".msi"))
+                && !lower.contains("webinstall")
+                && self.arch.mentioned_in(&lower)
+        })?;
+        // Older releases only publish an md5
+        let sha256 = file["sha256_sum"].as_str().and_then(normalize_sha256);
+        Some(checksum::attach_sha256(
+            ResolvedDownload::direct(file["url"].as_str()?),
+            Ok(sha256),
+        ))
+    }
+}
+
+fn get_json(http: &HttpClient, url: &str) -> Result<Value, ResolveError> {
+    let (_, body) = http.get_text(url)?;
+    serde_json::from_str(&body).map_err(|e| {
+        ResolveError::Parse(format!("Invalid python.org API response from {url}: {e}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stub::{self, StubServer};
+
+    const RELEASES: &str = include_str!("../tests/fixtures/python/release.json");
+
+    fn release_files(id: &str) -> Option<&'static str> {
+        Some(match id {
+            "1001" => include_str!("../tests/fixtures/python/release_file-1001.json"),
+            "1002" => include_str!("../tests/fixtures/python/release_file-1002.json"),
+            "1003" => include_str!("../tests/fixtures/python/release_file-1003.json"),
+            "1004" => include_str!("../tests/fixtures/python/release_file-1004.json"),
+            "1005" => include_str!("../tests/fixtures/python/release_file-1005.json"),
+            "1006" => include_str!("../tests/fixtures/python/release_file-1006.json"),
+            "1007" => include_str!("../tests/fixtures/python/release_file-1007.json"),
+            _ => return None,
+        })
+    }
+
+    // Serves the fixtures under the paths python.org's downloads API uses for them
+    fn python_org() -> StubServer {
+        StubServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/api/v2/downloads/release/?is_published=true" => RELEASES,
+                path => match path
+                    .strip_prefix("/api/v2/downloads/release_file/?release=")
+                    .and_then(release_files)
+                {
+                    Some(body) => body,
+                    None => return stub::response(404, &[], b"{\"detail\": \"Not found.\"}"),
+                },
+            };
+            stub::response(
+                200,
+                &[("Content-Type", "application/json")],
+                body.as_bytes(),
+            )
+        })
+    }
+
+    fn resolve(value: &str) -> (Result<ResolvedDownload, ResolveError>, Vec<String>) {
+        let server = python_org();
+        let specs = parse_specs(value, Arch::X64).unwrap();
+        let [(_, resolver)] = &specs[..] else {
+            panic!("{value} is not a single spec");
+        };
+        let resolved = resolver.resolve(&server.client());
+        let paths = server.requests().into_iter().map(|r| r.path).collect();
+        (resolved, paths)
+    }
+
+    #[test]
+    fn version_specs() {
+        assert_eq!(VersionSpec::parse("latest"), Some(VersionSpec::Latest));
+        assert_eq!(VersionSpec::parse(" TRUE "), Some(VersionSpec::Latest));
+        assert_eq!(
+            VersionSpec::parse("3.12"),
+            Some(VersionSpec::Series(String::from("3.12")))
+        );
+        assert_eq!(
+            VersionSpec::parse("3.13.*"),
+            Some(VersionSpec::Series(String::from("3.13")))
+        );
+        assert_eq!(
+            VersionSpec::parse("3.12.4"),
+            Some(VersionSpec::Exact(String::from("3.12.4")))
+        );
+        assert_eq!(
+            VersionSpec::parse("3.14.0RC1"),
+            Some(VersionSpec::Exact(String::from("3.14.0rc1")))
+        );
+        for invalid in [
+            "", "3.x", "3..1", "3.14rc1", "3.12.4.1", "v3.12", "3.12.4-1",
+        ] {
+            assert_eq!(VersionSpec::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn specs_with_arch_and_labels() {
+        let specs = parse_specs("3.12, 3.13  arm64,", Arch::X64).unwrap();
+        let labels: Vec<&str> = specs.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, ["3.12", "3.13 arm64"]);
+        assert_eq!(specs[0].1.arch, Arch::X64);
+        assert_eq!(specs[1].1.arch, Arch::Arm64);
+
+        let e = parse_specs("3.12 x86", Arch::X64).unwrap_err();
+        assert!(e.contains("unsupported arch \"x86\""), "{e}");
+        let e = parse_specs("3.12 arm64 now", Arch::X64).unwrap_err();
+        assert!(e.contains("unexpected \"now\""), "{e}");
+        let e = parse_specs("newest", Arch::X64).unwrap_err();
+        assert!(e.contains("\"newest\" is not a version"), "{e}");
+    }
+
+    // 3.14.0a5 is newer but a prerelease, the installer's digest comes from the API
+    #[test]
+    fn latest_skips_prereleases() {
+        let (resolved, paths) = resolve("latest");
+        let resolved = resolved.unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://www.python.org/ftp/python/3.13.2/python-3.13.2-amd64.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("3.13.2"));
+        assert_eq!(
+            resolved.sha256.as_deref(),
+            Some("2898ace7e6f38b61531d1c7074a075d0a524f73a52b38f0ae1036b81ea35b081")
+        );
+        assert_eq!(
+            paths,
+            [
+                "/api/v2/downloads/release/?is_published=true",
+                "/api/v2/downloads/release_file/?release=1002",
+            ]
+        );
+    }
+
+    #[test]
+    fn arch_picks_the_installer() {
+        let (resolved, _) = resolve("3.13 arm64");
+        assert_eq!(
+            resolved.unwrap().url,
+            "https://www.python.org/ftp/python/3.13.2/python-3.13.2-arm64.exe"
+        );
+        // The web installer mentions amd64 as well
+        let (resolved, _) = resolve("3.12");
+        assert_eq!(
+            resolved.unwrap().url,
+            "https://www.python.org/ftp/python/3.12.9/python-3.12.9-amd64.exe"
+        );
+    }
+
+    #[test]
+    fn prereleases_only_resolve_exactly() {
+        let (resolved, _) = resolve("3.14.0a5 arm64");
+        let resolved = resolved.unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://www.python.org/ftp/python/3.14.0/python-3.14.0a5-arm64.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("3.14.0a5"));
+        let (resolved, _) = resolve("3.14");
+        assert!(matches!(resolved, Err(ResolveError::SelectorMiss(_))));
+    }
+
+    // 3.11.11 is a security release shipped as source only
+    #[test]
+    fn source_only_releases_fall_back_to_older_ones() {
+        let (resolved, paths) = resolve("3.11");
+        let resolved = resolved.unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://www.python.org/ftp/python/3.11.9/python-3.11.9-amd64.exe"
+        );
+        assert_eq!(resolved.version.as_deref(), Some("3.11.9"));
+        assert_eq!(
+            &paths[1..],
+            [
+                "/api/v2/downloads/release_file/?release=1005",
+                "/api/v2/downloads/release_file/?release=1006",
+            ]
+        );
+    }
+
+    #[test]
+    fn old_msi_releases_without_a_sha256() {
+        let (resolved, _) = resolve("2.7");
+        let resolved = resolved.unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://www.python.org/ftp/python/2.7.18/python-2.7.18.amd64.msi"
+        );
+        assert_eq!(resolved.sha256, None);
+    }
+
+    #[test]
+    fn missing_installers_are_selector_misses() {
+        let (resolved, paths) = resolve("3.12 arm64");
+        let e = resolved.unwrap_err();
+        assert!(matches!(e, ResolveError::SelectorMiss(_)), "{e}");
+        assert!(e.to_string().contains("3.12.*"), "{e}");
+        assert!(e.to_string().contains("arm64"), "{e}");
+        assert_eq!(paths.len(), 2);
+
+        let (resolved, paths) = resolve("3.10");
+        assert!(matches!(resolved, Err(ResolveError::SelectorMiss(_))));
+        assert_eq!(paths.len(), 1);
+    }
+
+    #[test]
+    fn invalid_api_responses_are_parse_errors() {
+        let server = StubServer::start(|_| stub::response(200, &[], b"<html>Maintenance</html>"));
+        let resolver = PythonResolver {
+            spec: VersionSpec::Latest,
+            arch: Arch::X64,
+        };
+        let e = resolver.resolve(&server.client()).unwrap_err();
+        assert!(matches!(e, ResolveError::Parse(_)), "{e}");
+        assert!(e.to_string().contains("python.org"), "{e}");
+    }
+}
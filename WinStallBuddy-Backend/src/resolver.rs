@@ -0,0 +1,175 @@
+use crate::http::{HttpClient, HttpError};
+use crate::sniff::FileType;
+use std::error::Error;
+use std::fmt;
+
+// How a resolved download has to be fetched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolutionKind {
+    // The url points at the installer and can be downloaded by wsb
+    Direct,
+    // The download is handed off to an external helper (DU_CLI.exe), the url holds the helper's app name
+    ExternalHelper,
+    // The vendor requires user interaction (login, license prompt), the url is the page to open
+    Manual,
+}
+
+// CPU architecture an installer is built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X86,
+    X64,
+    Arm64,
+}
+
+impl Arch {
+    pub const ALL: [Arch; 3] = [Arch::X86, Arch::X64, Arch::Arm64];
+
+    pub fn parse(value: &str) -> Option<Arch> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "x86" | "i386" | "i686" | "win32" => Some(Arch::X86),
+            "x64" | "amd64" | "x86_64" | "win64" => Some(Arch::X64),
+            "arm64" | "aarch64" => Some(Arch::Arm64),
+            _ => None,
+        }
+    }
+
+    // Spellings vendors use for the architecture in file names
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Arch::X86 => &["x86", "i386", "i686", "win32", "ia32"],
+            Arch::X64 => &["x64", "amd64", "x86_64", "x86-64", "win64"],
+            Arch::Arm64 => &["arm64", "aarch64"],
+        }
+    }
+
+    // Whether a file name mentions this architecture, "x86" inside "x86_64" doesn't count as x86
+    pub fn mentioned_in(&self, name: &str) -> bool {
+        let mut name = name.to_ascii_lowercase();
+        if *self == Arch::X86 {
+            name = name.replace("x86_64", "").replace("x86-64", "");
+        }
+        self.aliases().iter().any(|alias| name.contains(alias))
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arch::X86 => f.write_str("x86"),
+            Arch::X64 => f.write_str("x64"),
+            Arch::Arm64 => f.write_str("arm64"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedDownload {
+    pub url: String,
+    pub version: Option<String>,
+    pub filename: Option<String>,
+    // Lowercase hex SHA-256 the downloaded file has to match, published by the vendor or pinned in the catalog
+    pub sha256: Option<String>,
+    // What the file has to look like once downloaded, derived from the filename's extension when unset
+    pub file_type: Option<FileType>,
+    // Sent as the Referer of the download request, for vendors that refuse hotlinked downloads
+    pub referer: Option<String>,
+    pub kind: ResolutionKind,
+    // Things worth telling the user that came up while resolving (no published checksum, a rate limit waited out),
+    // `download` reports them through the progress subscriber so they don't tear through the progress bars
+    pub notes: Vec<String>,
+}
+
+impl ResolvedDownload {
+    pub fn direct(url: impl Into<String>) -> ResolvedDownload {
+        ResolvedDownload {
+            url: url.into(),
+            version: None,
+            filename: None,
+            sha256: None,
+            file_type: None,
+            referer: None,
+            kind: ResolutionKind::Direct,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn external_helper(app_name: impl Into<String>) -> ResolvedDownload {
+        ResolvedDownload {
+            kind: ResolutionKind::ExternalHelper,
+            ..ResolvedDownload::direct(app_name)
+        }
+    }
+
+    pub fn manual(url: impl Into<String>) -> ResolvedDownload {
+        ResolvedDownload {
+            kind: ResolutionKind::Manual,
+            ..ResolvedDownload::direct(url)
+        }
+    }
+
+    pub fn with_version(mut self, version: impl Into<String>) -> ResolvedDownload {
+        self.version = Some(version.into());
+        self
+    }
+
+    pub fn with_filename(mut self, filename: impl Into<String>) -> ResolvedDownload {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    pub fn with_file_type(mut self, file_type: FileType) -> ResolvedDownload {
+        self.file_type = Some(file_type);
+        self
+    }
+
+    pub fn with_referer(mut self, referer: impl Into<String>) -> ResolvedDownload {
+        self.referer = Some(referer.into());
+        self
+    }
+
+    pub fn with_sha256(mut self, sha256: impl Into<String>) -> ResolvedDownload {
+        self.sha256 = Some(sha256.into().to_ascii_lowercase());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> ResolvedDownload {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum ResolveError {
+    // The vendor could not be reached or answered with an error status
+    Network(String),
+    // The page was fetched but the element holding the link or version is missing (the vendor page changed)
+    SelectorMiss(String),
+    // The response could not be parsed (invalid JSON/XML, unexpected version format)
+    Parse(String),
+    // The app has no resolver wsb can use
+    Unsupported(String),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::Network(message) => write!(f, "network error: {message}"),
+            ResolveError::SelectorMiss(message) => write!(f, "selector miss: {message}"),
+            ResolveError::Parse(message) => write!(f, "parse error: {message}"),
+            ResolveError::Unsupported(message) => write!(f, "unsupported: {message}"),
+        }
+    }
+}
+
+impl Error for ResolveError {}
+
+impl From<HttpError> for ResolveError {
+    fn from(e: HttpError) -> ResolveError {
+        ResolveError::Network(e.to_string())
+    }
+}
+
+pub trait LinkResolver {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError>;
+}
@@ -0,0 +1,180 @@
+use crate::catalog::{Catalog, CatalogEntry, normalize_name};
+use serde::Serialize;
+
+// How well a query matched, the best of the entry's key, display name and aliases counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchQuality {
+    // Within a typo or two of a name, "firefix"
+    Typo,
+    // The name starts with the query's first letter and has the rest in order, "vsc" in VSCodium
+    Letters,
+    // Found inside a name, "studio" in Visual Studio Code
+    Contains,
+    // A name starts with it, "note" in Notepad++
+    Prefix,
+    Exact,
+}
+
+// Fuzzy search over the catalog, best matches first and catalog order among equal ones.
+// Names are compared without case and punctuation, so "notepad++", "Notepad PlusPlus" and "notepadplusplus" are the same.
+pub fn search<'a>(catalog: &'a Catalog, query: &str) -> Vec<(&'a CatalogEntry, MatchQuality)> {
+    let query = normalize_name(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<(&CatalogEntry, MatchQuality)> = catalog
+        .entries()
+        .filter_map(|entry| {
+            names(entry)
+                .filter_map(|name| quality(&query, &normalize_name(name)))
+                .max()
+                .map(|quality| (entry, quality))
+        })
+        .collect();
+    // sort_by is stable, equal matches keep the catalog order
+    results.sort_by(|(_, a), (_, b)| b.cmp(a));
+    results
+}
+
+fn names(entry: &CatalogEntry) -> impl Iterator<Item = &str> {
+    [entry.key.as_str(), entry.name.as_str()]
+        .into_iter()
+        .chain(entry.aliases.iter().map(String::as_str))
+}
+
+fn quality(query: &str, name: &str) -> Option<MatchQuality> {
+    if name == query {
+        Some(MatchQuality::Exact)
+    } else if name.starts_with(query) {
+        Some(MatchQuality::Prefix)
+    } else if name.contains(query) {
+        Some(MatchQuality::Contains)
+    } else if query.len() >= 3 && is_subsequence(query, name) {
+        Some(MatchQuality::Letters)
+    } else if edit_distance(query, name) <= allowed_typos(query) {
+        Some(MatchQuality::Typo)
+    } else {
+        None
+    }
+}
+
+// Short queries would match nearly everything with a typo allowed
+fn allowed_typos(query: &str) -> usize {
+    match query.chars().count() {
+        0..=4 => 0,
+        5..=6 => 1,
+        _ => 2,
+    }
+}
+
+// Anchored at the first letter, loose letters spread over a long name are rarely what was meant
+fn is_subsequence(query: &str, name: &str) -> bool {
+    let mut name = name.chars();
+    let mut query = query.chars();
+    query.next() == name.next() && query.all(|c| name.any(|n| n == c))
+}
+
+// Levenshtein distance, also used to suggest the config key a misspelled one was meant to be
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(catalog: &Catalog, query: &str) -> Vec<(String, MatchQuality)> {
+        search(catalog, query)
+            .into_iter()
+            .map(|(entry, quality)| (entry.key.clone(), quality))
+            .collect()
+    }
+
+    #[test]
+    fn shared_aliases_find_every_app() {
+        let catalog = Catalog::embedded();
+        let found = keys(&catalog, "code");
+        assert!(found.contains(&("VisualStudioCode".to_string(), MatchQuality::Exact)));
+        assert!(found.contains(&("VSCodium".to_string(), MatchQuality::Exact)));
+        // Missing letters are still in order, a wrong one makes it a typo
+        assert_eq!(
+            keys(&catalog, "firefx").first(),
+            Some(&("Firefox".to_string(), MatchQuality::Letters))
+        );
+        assert_eq!(
+            keys(&catalog, "firefix").first(),
+            Some(&("Firefox".to_string(), MatchQuality::Typo))
+        );
+    }
+
+    #[test]
+    fn better_matches_come_first() {
+        let catalog = Catalog::parse(
+            &[
+                "Nodes",
+                "NotesPro",
+                "MyNotes",
+                "NotableSet",
+                "Notes",
+                "NotesPlus",
+                "Paint",
+            ]
+            .iter()
+            .map(|key| {
+                format!(
+                    "[[app]]\nkey = \"{key}\"\ncategory = \"system\"\nname = \"{key}\"\n\
+                         resolver = \"direct\"\nurl = \"https://example.com/{key}.exe\"\n"
+                )
+            })
+            .collect::<String>(),
+        )
+        .unwrap();
+        let expected = [
+            ("Notes", MatchQuality::Exact),
+            ("NotesPro", MatchQuality::Prefix),
+            ("NotesPlus", MatchQuality::Prefix),
+            ("MyNotes", MatchQuality::Contains),
+            ("NotableSet", MatchQuality::Letters),
+            ("Nodes", MatchQuality::Typo),
+        ]
+        .map(|(key, quality)| (key.to_string(), quality));
+        assert_eq!(keys(&catalog, "notes"), expected);
+        assert_eq!(keys(&catalog, "NOTES!"), expected);
+    }
+
+    #[test]
+    fn empty_queries_match_nothing() {
+        let catalog = Catalog::embedded();
+        assert!(search(&catalog, "").is_empty());
+        assert!(search(&catalog, " +-. ").is_empty());
+    }
+
+    #[test]
+    fn edit_distances() {
+        for (a, b, distance) in [
+            ("", "", 0),
+            ("", "abc", 3),
+            ("firefox", "firefox", 0),
+            ("firefx", "firefox", 1),
+            ("frefox", "firefox", 1),
+            ("firefox", "fierfox", 2),
+            ("timeot", "timeout", 1),
+            ("kitten", "sitting", 3),
+        ] {
+            assert_eq!(edit_distance(a, b), distance, "{a} -> {b}");
+            assert_eq!(edit_distance(b, a), distance, "{b} -> {a}");
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+// OLE compound file header, every .msi starts with it
+const OLE_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+// ISO 9660 volume descriptors start at sector 16, their identifier follows a one byte type
+const ISO_SIGNATURE: &[u8] = b"CD001";
+const ISO_SIGNATURE_OFFSETS: [usize; 3] = [0x8001, 0x8801, 0x9001];
+// Enough bytes to reach the last ISO volume descriptor identifier
+const SNIFF_LENGTH: usize = 0x9001 + 5;
+
+// What a downloaded installer is expected to be, set with `file_type` on a catalog entry
+// or derived from the filename's extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileType {
+    // Portable executable (.exe), starts with "MZ"
+    Exe,
+    // Windows installer package (.msi), an OLE compound file
+    Msi,
+    // Any zip container (.zip, .msix, .appx, .jar, ...)
+    Zip,
+    // ISO 9660 disc image
+    Iso,
+}
+
+impl FileType {
+    pub fn from_extension(filename: &str) -> Option<FileType> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "exe" => Some(FileType::Exe),
+            "msi" => Some(FileType::Msi),
+            "zip" | "msix" | "msixbundle" | "appx" | "appxbundle" | "jar" | "nupkg" => {
+                Some(FileType::Zip)
+            }
+            "iso" => Some(FileType::Iso),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileType::Exe => f.write_str("a Windows executable (PE)"),
+            FileType::Msi => f.write_str("a Windows installer package (MSI)"),
+            FileType::Zip => f.write_str("a zip archive"),
+            FileType::Iso => f.write_str("an ISO disc image"),
+        }
+    }
+}
+
+// What the first bytes of a file look like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sniffed {
+    Type(FileType),
+    // A login wall, captcha or error page served with a 200 status
+    Html,
+    Unknown,
+}
+
+impl fmt::Display for Sniffed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sniffed::Type(file_type) => write!(f, "{file_type}"),
+            Sniffed::Html => f.write_str("an HTML page"),
+            Sniffed::Unknown => f.write_str("an unrecognized file"),
+        }
+    }
+}
+
+pub fn sniff_bytes(bytes: &[u8]) -> Sniffed {
+    if bytes.starts_with(b"MZ") {
+        return Sniffed::Type(FileType::Exe);
+    }
+    if bytes.starts_with(&OLE_SIGNATURE) {
+        return Sniffed::Type(FileType::Msi);
+    }
+    // Local file header, or the end of central directory record of an empty archive
+    if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        return Sniffed::Type(FileType::Zip);
+    }
+    if ISO_SIGNATURE_OFFSETS.iter().any(|offset| {
+        bytes
+            .get(*offset..*offset + ISO_SIGNATURE.len())
+            .is_some_and(|identifier| identifier == ISO_SIGNATURE)
+    }) {
+        return Sniffed::Type(FileType::Iso);
+    }
+    if looks_like_html(bytes) {
+        return Sniffed::Html;
+    }
+    Sniffed::Unknown
+}
+
+fn looks_like_html(bytes: &[u8]) -> bool {
+    let start = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes); // UTF-8 byte order mark
+    let start = &start[start
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(start.len())..];
+    let head = String::from_utf8_lossy(&start[..start.len().min(64)]).to_ascii_lowercase();
+    ["<!doctype html", "<html", "<head", "<body", "<?xml", "<!--"]
+        .iter()
+        .any(|prefix| head.starts_with(prefix))
+}
+
+pub fn sniff_file(path: &Path) -> io::Result<Sniffed> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(SNIFF_LENGTH);
+    File::open(path)?
+        .take(SNIFF_LENGTH as u64)
+        .read_to_end(&mut bytes)?;
+    Ok(sniff_bytes(&bytes))
+}
+
+// Compares the file against its expected type. HTML is rejected even when no type is expected,
+// nothing wsb downloads is a web page.
+pub fn check(path: &Path, expected: Option<FileType>) -> io::Result<Result<(), Sniffed>> {
+    let sniffed = sniff_file(path)?;
+    let matches = match (expected, sniffed) {
+        (_, Sniffed::Html) => false,
+        (Some(expected), Sniffed::Type(found)) => expected == found,
+        (Some(_), Sniffed::Unknown) => false,
+        (None, _) => true,
+    };
+    Ok(if matches { Ok(()) } else { Err(sniffed) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    // Zeroed bytes long enough to reach every ISO descriptor, with the identifier written at offset
+    fn iso_with_identifier_at(offset: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; SNIFF_LENGTH];
+        bytes[offset..offset + ISO_SIGNATURE.len()].copy_from_slice(ISO_SIGNATURE);
+        bytes
+    }
+
+    #[test]
+    fn signatures() {
+        assert_eq!(
+            sniff_bytes(b"MZ\x90\x00\x03\x00\x00\x00"),
+            Sniffed::Type(FileType::Exe)
+        );
+        let mut msi = OLE_SIGNATURE.to_vec();
+        msi.extend([0u8; 24]);
+        assert_eq!(sniff_bytes(&msi), Sniffed::Type(FileType::Msi));
+        assert_eq!(
+            sniff_bytes(b"PK\x03\x04\x14\x00\x00\x00"),
+            Sniffed::Type(FileType::Zip)
+        );
+        assert_eq!(
+            sniff_bytes(b"PK\x05\x06\x00\x00\x00\x00"),
+            Sniffed::Type(FileType::Zip)
+        );
+        for offset in ISO_SIGNATURE_OFFSETS {
+            assert_eq!(
+                sniff_bytes(&iso_with_identifier_at(offset)),
+                Sniffed::Type(FileType::Iso),
+                "CD001 at {offset:#x}"
+            );
+        }
+        // Only the descriptor offsets count
+        assert_eq!(
+            sniff_bytes(&iso_with_identifier_at(0x8000)),
+            Sniffed::Unknown
+        );
+    }
+
+    #[test]
+    fn truncated_files_are_unknown() {
+        assert_eq!(sniff_bytes(b""), Sniffed::Unknown);
+        assert_eq!(sniff_bytes(b"M"), Sniffed::Unknown);
+        assert_eq!(sniff_bytes(&OLE_SIGNATURE[..4]), Sniffed::Unknown);
+        assert_eq!(sniff_bytes(b"PK\x03"), Sniffed::Unknown);
+        // Cut off inside the last ISO identifier
+        let iso = iso_with_identifier_at(0x9001);
+        assert_eq!(sniff_bytes(&iso[..0x9003]), Sniffed::Unknown);
+    }
+
+    #[test]
+    fn html_behind_a_byte_order_mark_and_whitespace() {
+        assert_eq!(
+            sniff_bytes(b"\xEF\xBB\xBF\r\n  \t<!DOCTYPE html><html><head>"),
+            Sniffed::Html
+        );
+        assert_eq!(sniff_bytes(b"\n\n<HTML lang=\"en\">"), Sniffed::Html);
+        assert_eq!(
+            sniff_bytes(b"<?xml version=\"1.0\"?><Error>AccessDenied</Error>"),
+            Sniffed::Html
+        );
+        assert_eq!(sniff_bytes(b"   \n"), Sniffed::Unknown);
+        assert_eq!(sniff_bytes(b"plain text"), Sniffed::Unknown);
+    }
+
+    #[test]
+    fn check_rejects_html_and_mismatches() {
+        let dir = env::temp_dir().join(format!("wsb-sniff-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("setup.exe");
+        fs::write(&exe, b"MZ\x90\x00").unwrap();
+        let page = dir.join("login.exe");
+        fs::write(&page, b"<!doctype html><title>Sign in</title>").unwrap();
+
+        assert_eq!(check(&exe, Some(FileType::Exe)).unwrap(), Ok(()));
+        assert_eq!(check(&exe, None).unwrap(), Ok(()));
+        assert_eq!(
+            check(&exe, Some(FileType::Msi)).unwrap(),
+            Err(Sniffed::Type(FileType::Exe))
+        );
+        assert_eq!(check(&page, None).unwrap(), Err(Sniffed::Html));
+        assert!(check(&dir.join("missing.exe"), None).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+use crate::http::{HttpClient, HttpConfig};
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// A request as the stub server received it, header names are lowercased
+#[derive(Debug, Clone)]
+pub struct StubRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl StubRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Local HTTP server for the tests. Every connection gets the raw bytes the handler returns and is then closed,
+// so a response shorter than its Content-Length is a connection cut mid-transfer.
+// Connections are served on their own threads, a handler that sleeps holds up only its own request.
+// The server threads run until the test binary exits.
+pub struct StubServer {
+    pub origin: String,
+    requests: Arc<Mutex<Vec<StubRequest>>>,
+}
+
+impl StubServer {
+    pub fn start(handler: impl Fn(&StubRequest) -> Vec<u8> + Send + Sync + 'static) -> StubServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        let requests: Arc<Mutex<Vec<StubRequest>>> = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        let handler = Arc::new(handler);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let Some(request) = read_request(&stream) else {
+                    continue;
+                };
+                received.lock().unwrap().push(request.clone());
+                let handler = handler.clone();
+                thread::spawn(move || {
+                    let _ = stream.write_all(&handler(&request));
+                });
+            }
+        });
+        StubServer { origin, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.origin)
+    }
+
+    // A client sending every request to this server whatever the url's host, without retries
+    pub fn client(&self) -> HttpClient {
+        let config = HttpConfig {
+            retries: 0,
+            ..HttpConfig::default()
+        };
+        HttpClient::new(&config).unwrap().with_origin(&self.origin)
+    }
+
+    pub fn requests(&self) -> Vec<StubRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Option<StubRequest> {
+    let mut lines = BufReader::new(stream).lines();
+    let request_line = lines.next()?.ok()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in lines {
+        let line = line.ok()?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    Some(StubRequest {
+        method,
+        path,
+        headers,
+    })
+}
+
+// A complete response, Content-Length and Connection: close are added
+pub fn response(status: u16, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    truncated_response(status, headers, body, body.len())
+}
+
+// A response announcing length bytes of which only body is sent
+pub fn truncated_response(
+    status: u16,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    length: usize,
+) -> Vec<u8> {
+    let mut head =
+        format!("HTTP/1.1 {status} Stub\r\nContent-Length: {length}\r\nConnection: close\r\n");
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    let mut bytes = head.into_bytes();
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+// A fresh, empty directory below the system temp dir, names only have to be unique within the test binary
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("wsb-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
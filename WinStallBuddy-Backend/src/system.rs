@@ -1,33 +1,42 @@
+use crate::http::HttpClient;
 use crate::links;
+use crate::resolver::{LinkResolver, ResolveError, ResolvedDownload};
 
 #[allow(dead_code)]
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Utility {
     SevenZip,
     Audacious,
-    LibreOffice,
     ModernCSV,
     NoMacs,
-    Okular,
     //PhotoshopCS6,
-    Rufus,
-    VLC,
     WinRAR,
 }
 
 impl Utility {
-    pub fn get_link(&self) -> Option<String> {
+    // Maps the `builtin` name of a catalog entry to its compiled in resolver
+    pub fn from_name(name: &str) -> Option<Utility> {
+        match name {
+            "SevenZip" => Some(Utility::SevenZip),
+            "Audacious" => Some(Utility::Audacious),
+            "ModernCSV" => Some(Utility::ModernCSV),
+            "NoMacs" => Some(Utility::NoMacs),
+            "WinRAR" => Some(Utility::WinRAR),
+            _ => None,
+        }
+    }
+}
+
+impl LinkResolver for Utility {
+    fn resolve(&self, http: &HttpClient) -> Result<ResolvedDownload, ResolveError> {
         match self {
-            Utility::SevenZip => Some(links::get_seven_zip_link()),
-            Utility::Audacious => Some(links::get_audacious_link()),
-            Utility::LibreOffice => Some(links::get_libreoffice_link()),
-            Utility::ModernCSV => Some(links::get_modern_csv_link()),
-            Utility::NoMacs => Some(links::get_nomacs_link()),
-            Utility::Okular => Some(links::get_okular_link()),
-            //Utility::PhotoshopCS6 => Some(links::get_photoshop_cs6_link()),
-            Utility::Rufus => Some(links::get_rufus_link()),
-            Utility::VLC => Some(links::get_vlc_link()),
-            Utility::WinRAR => Some(links::get_winrar_link()),
+            Utility::SevenZip => links::get_seven_zip_link(http),
+            Utility::Audacious => links::get_audacious_link(http),
+            Utility::ModernCSV => links::get_modern_csv_link(),
+            Utility::NoMacs => links::get_nomacs_link(),
+            //Utility::PhotoshopCS6 => links::get_photoshop_cs6_link(),
+            Utility::WinRAR => links::get_winrar_link(http),
         }
     }
 }
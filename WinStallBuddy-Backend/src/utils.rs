@@ -1,574 +1,752 @@
-use crate::cmd;
-use crate::links;
-use ini::{Ini, Properties};
-use reqwest::StatusCode;
-use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
-use std::fs::File;
-use std::io::Write;
-use std::time::Duration;
-use std::{collections::HashMap, env, fs, process::exit};
-use url::Url;
-use urlencoding::decode;
-
-#[allow(dead_code)]
-pub fn make_web_request_w_ua(
-    url: &str,
-) -> Result<(StatusCode, Vec<u8>, HeaderMap), Box<dyn std::error::Error>> {
-    let mut headers = HeaderMap::new();
-
-    headers.insert(USER_AGENT, HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36"));
-    headers.insert("Connection", HeaderValue::from_static("keep-alive"));
-    let timeout = Duration::from_secs(120);
-    let client = Client::builder()
-        .timeout(timeout)
-        .default_headers(headers)
-        .build()?;
-
-    let response = client.get(url).send().unwrap();
-
-    println!("{}", response.url());
-    let mut resp_headers = response.headers().clone();
-
-    let final_url = response.url().as_str().to_string();
-    let value = String::from("/") + &final_url;
-    let header_value = HeaderValue::from_str(&value).unwrap();
-
-    resp_headers.append("location", header_value); // Clone is required to convert a &HeaderMap to HeaderMap
-    let status = response.status();
-    if status.is_success() {
-        let body = response.bytes()?.to_vec(); // response.text() is only valid for utf-8 encoded files (which executables are not)
-        Ok((status, body, resp_headers))
-    } else {
-        let status: StatusCode = response.status();
-        if status.as_u16() == 410 {
-            return Ok((status, vec![], HeaderMap::new()));
-        }
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Request failed with status {}, please try again", status),
-        )))
-    }
+use crate::catalog::{Catalog, CatalogEntry, Category};
+use crate::config::{self, ConfigOptions};
+use crate::github;
+use crate::http::{HttpClient, HttpConfig};
+use crate::info::{self, AppInfo};
+use crate::init;
+use crate::links::{self, KeyType};
+use crate::lint::{self, Severity};
+use crate::options::{self, AppOptions};
+use crate::pipeline::{self, DownloadOutcome, PipelineConfig};
+use crate::progress::ProgressOutput;
+use crate::resolver::{LinkResolver, ResolveError};
+use crate::search;
+use indicatif::HumanBytes;
+use ini::Ini;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::{env, fs};
+
+// Options of `wsb download`, filled in from the command line (see src/cli.rs)
+#[derive(Debug, Default)]
+pub struct DownloadOptions {
+    pub config: ConfigOptions,
+    // App names as typed, Python=3.12 passes a value to a versioned key
+    pub apps: Vec<String>,
+    // config.ini sections to read the selection from
+    pub categories: Vec<Category>,
+    pub output_dir: Option<PathBuf>,
+    pub dry_run: bool,
+    pub jobs: Option<usize>,
+    pub json: bool,
+    pub yes: bool,
 }
 
-#[allow(dead_code)]
-
-pub enum ValidStarterArg {
-    DOWNLOAD,
-    HELP,
-    INSTALL,
-    VERSION,
-}
+// Downloads the apps named on the command line, or the ones set in config.ini when none are named.
+// Exits with 1 when any app failed and 2 when the configuration or the app names are invalid.
+// With --json stdout carries only the progress events and the summary, everything meant for people goes to stderr.
+pub fn download(catalog: &Catalog, options: &DownloadOptions) -> ExitCode {
+    if !options.json {
+        println!("Loading download configuration, please wait...");
+    }
+    // config.ini is optional when the apps are named on the command line, the defaults are used without it
+    let required = options.apps.is_empty() || options.config.path.is_some();
+    let config = match config::load(&options.config, required) {
+        Ok(config) => config.ini,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    let (mut pipeline_config, http) = match configure(&config) {
+        Ok(configured) => configured,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    if let Some(output_dir) = &options.output_dir {
+        pipeline_config.output_dir = output_dir.clone();
+    }
+    if let Some(jobs) = options.jobs {
+        pipeline_config.jobs = jobs;
+    }
+    if options.json {
+        pipeline_config.progress = ProgressOutput::Json;
+    }
+    pipeline_config.dry_run = options.dry_run;
+
+    let mut sections: Vec<Vec<(String, Result<AppOptions, String>)>> = Vec::new();
+    match select_apps(catalog, &config, &options.apps) {
+        Ok(selected) => sections.push(
+            selected
+                .into_iter()
+                .map(|(key, app_options)| (key, Ok(app_options)))
+                .collect(),
+        ),
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    }
+    if options.apps.is_empty() || !options.categories.is_empty() {
+        // Reported on stderr without stopping the run, also with --json, `wsb config check` lists every problem
+        for finding in lint::check_selection(catalog, &config) {
+            let in_selection = finding.section.as_deref().is_none_or(|section| {
+                options.categories.is_empty()
+                    || options
+                        .categories
+                        .iter()
+                        .any(|c| c.section_name() == section)
+            });
+            if in_selection {
+                eprintln!("config.ini {finding}");
+            }
+        }
+        for category in Category::ALL {
+            if !options.categories.is_empty() && !options.categories.contains(&category) {
+                continue;
+            }
+            sections.push(load_section(catalog, category, &config));
+        }
+    }
+    let apps: Vec<(String, Result<KeyType, String>)> = get_desired_downloads(catalog, sections);
+    if apps.is_empty() {
+        eprintln!(
+            "Nothing to download, name apps on the command line or set them to true in config.ini"
+        );
+        return ExitCode::SUCCESS;
+    }
 
-#[allow(dead_code)]
-pub fn map_enum_to_arg_str(arg: ValidStarterArg) -> String {
-    match arg {
-        ValidStarterArg::DOWNLOAD => String::from("download"),
-        ValidStarterArg::HELP => String::from("help"),
-        ValidStarterArg::INSTALL => String::from("install"),
-        ValidStarterArg::VERSION => String::from("version"),
+    // Only asked on an interactive terminal, scripts and CI jobs never wait on it
+    if !options.yes && !options.dry_run && !options.json && io::stdin().is_terminal() {
+        let question = format!(
+            "Download {} apps to {}?",
+            apps.len(),
+            pipeline_config.output_dir.display()
+        );
+        if !confirm(&question) {
+            println!("Cancelled");
+            return ExitCode::SUCCESS;
+        }
     }
-}
 
-pub fn map_arg_str_to_enum(arg: String) -> ValidStarterArg {
-    // Deferencing is required because the match expression doesnt accept
-    match &*arg {
-        "download" => ValidStarterArg::DOWNLOAD,
-        "help" => ValidStarterArg::HELP,
-        "install" => ValidStarterArg::INSTALL,
-        "version" => ValidStarterArg::VERSION,
-        _ => ValidStarterArg::HELP, // In the event an invalid arg is returned, the help key is returned
+    let outcomes = pipeline::run(apps, &pipeline_config, http);
+    if options.json {
+        print_download_summary_json(&outcomes);
+    } else {
+        print_download_summary(&outcomes);
+    }
+    if outcomes
+        .iter()
+        .any(|(_, outcome)| matches!(outcome, DownloadOutcome::Failed(_)))
+    {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
     }
 }
 
-#[allow(unused_assignments)]
-#[allow(dead_code)]
-pub fn handle_starter_arg(arg: ValidStarterArg) {
-    match arg {
-        ValidStarterArg::DOWNLOAD => {
-            println!("Loading download configuration, please wait...");
-            let mut cwd = String::new();
-            match env::current_exe() {
-                Ok(path) => cwd = path.parent().unwrap().display().to_string(),
-                Err(_) => cwd = "Not found".to_string(),
-            };
-            let filename = format!("{cwd}/config.ini");
-            let config: Ini = Ini::load_from_file(filename).unwrap();
-            let browser_section: &Properties = config.section(Some("browser")).unwrap();
-            let gaming_section: &Properties = config.section(Some("gaming")).unwrap();
-            let programming_section: &Properties = config.section(Some("programming")).unwrap();
-            let system_section: &Properties = config.section(Some("system")).unwrap();
-            let browsers: HashMap<String, String> = load_browser_section(browser_section);
-            let gaming: HashMap<String, String> = load_gaming_section(gaming_section);
-            let programming: HashMap<String, String> =
-                load_programming_section(programming_section);
-            let systems: HashMap<String, String> = load_system_section(system_section);
-            let desired_downloads: Vec<String> =
-                get_desired_downloads(browsers, gaming, programming, systems);
-
-            for download in desired_downloads {
-                let variable = links::map_config_key_to_function_name(&download);
-                let link = variable
-                    .get_download_link()
-                    .unwrap_or(String::from("Unable to get download link"));
-                if link == "Unable to get download link" {
-                    println!("Unable to get download link for {download}, continuing...");
-                    continue;
-                }
-
-                println!("Downloading {download}, please wait...");
-
-                if link.starts_with("DU_CLI") {
-                    // Handles cases where downloads are done through DUCLI (Download Utility Command Line Interface)
-                    let split_link: Vec<&str> = link.split(" ").collect();
-                    if split_link.len() == 3 {
-                        let app_name = split_link[2];
-
-                        cmd::run_cmd("download", app_name);
-                    }
-                } else {
-                    // Handles the major of cases where a direct download link is supplied or scraped.
-                    let result_object = download_file(&link);
-                    match result_object {
-                        Ok(()) => println!("{:?}", result_object.unwrap()),
-                        Err(e) => println!("{:?}", e),
-                    }
-                    // println!("{:?}", result.unwrap());
-                }
-            }
+// Resolves a single app and probes its download (final url, size, type, checksum), nothing is downloaded
+pub fn info(catalog: &Catalog, config_options: &ConfigOptions, name: &str, json: bool) -> ExitCode {
+    let config = match config::load(config_options, config_options.path.is_some()) {
+        Ok(config) => config.ini,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
         }
-        ValidStarterArg::HELP => {
-            let help_string = "Usage: wsb.exe <command> [arguments]
-        Commands:
-            download - Download something from somewhere.
-            install - Install the thing.
-            version - Print the current version.
-            help - Show this message.
-
-        Arguments:
-            
-            Press any key to exit...";
-
-            println!("{}", help_string);
-            let mut line = String::new();
-            std::io::stdin().read_line(&mut line).expect("");
-            exit(0)
+    };
+    let selected = match select_apps(catalog, &config, &[name.to_string()]) {
+        Ok(selected) => selected,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    let http = match configure(&config) {
+        Ok((_, http)) => http,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
         }
-        ValidStarterArg::INSTALL => {
-            println!("Works")
+    };
+    let mut code = ExitCode::SUCCESS;
+    let selected = selected
+        .into_iter()
+        .map(|(key, app_options)| (key, Ok(app_options)))
+        .collect();
+    for (app, resolver) in get_desired_downloads(catalog, vec![selected]) {
+        let resolved = resolver.and_then(|resolver| {
+            resolver
+                .resolve(&http)
+                .map_err(|e| format!("unable to get download link: {e}"))
+        });
+        // No progress bars here, the notes can go straight to stderr
+        if let Ok(resolved) = &resolved {
+            for note in &resolved.notes {
+                eprintln!("{note}");
+            }
         }
-        ValidStarterArg::VERSION => {
-            println!("WSB v0.0.1")
+        let app_info = resolved.and_then(|resolved| {
+            info::probe(&http, &app, &resolved)
+                .map_err(|e| format!("unable to probe {}: {e}", resolved.url))
+        });
+        match (app_info, json) {
+            (Ok(app_info), true) => println!("{}", json!(app_info)),
+            (Ok(app_info), false) => print_app_info(&app_info),
+            (Err(e), true) => {
+                println!("{}", json!({ "app": app, "error": e }));
+                code = ExitCode::FAILURE;
+            }
+            (Err(e), false) => {
+                println!("{app}: {e}");
+                code = ExitCode::FAILURE;
+            }
         }
     }
+    code
 }
 
-pub fn create_starter_args() -> Vec<String> {
-    vec![
-        String::from("download"),
-        String::from("help"),
-        String::from("install"),
-        String::from("version"),
-    ]
-}
-
-pub fn get_arg() -> String {
-    let starter_args: Vec<String> = create_starter_args();
-    //println!("{:?}", starter_args);
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("{}", "Invalid argument provided, please see below.");
+fn print_app_info(app_info: &AppInfo) {
+    let unknown = "unknown";
+    println!("{}", app_info.app);
+    match app_info.kind {
+        "helper" => {
+            println!("    helper app:     {}", app_info.url);
+            println!("    downloaded by:  DU_CLI.exe, nothing else is known before it runs");
+            return;
+        }
+        "manual" => {
+            println!("    download page:  {}", app_info.url);
+            println!("    downloaded by:  you, wsb can't download it");
+            return;
+        }
+        _ => println!("    resolved url:   {}", app_info.url),
+    }
+    for (i, redirect) in app_info.redirects.iter().enumerate() {
+        let label = if i == 0 { "redirects:" } else { "" };
+        println!("    {label:<15} {redirect}");
+    }
+    if let Some(final_url) = &app_info.final_url {
+        println!("    final url:      {final_url}");
+    }
+    println!(
+        "    filename:       {}",
+        app_info.filename.as_deref().unwrap_or(unknown)
+    );
+    match app_info.content_length {
+        Some(bytes) => println!("    size:           {} ({bytes} bytes)", HumanBytes(bytes)),
+        None => println!("    size:           {unknown}"),
+    }
+    println!(
+        "    last modified:  {}",
+        app_info.last_modified.as_deref().unwrap_or(unknown)
+    );
+    println!(
+        "    version:        {}",
+        app_info.version.as_deref().unwrap_or(unknown)
+    );
+    match app_info.installer_type {
+        Some(file_type) => println!("    installer type: {file_type}"),
+        None => println!("    installer type: {unknown}"),
+    }
+    match &app_info.sha256 {
+        Some(sha256) => println!("    sha256:         {sha256}, the download is verified"),
+        None => println!("    sha256:         not published, the download can't be verified"),
+    }
+    if app_info.ranged_get {
+        println!("    (the server rejected HEAD, the headers come from a ranged GET)");
     }
-
-    let def: String = String::from("help");
-    let raw_arg: &String = args.iter().nth(1).unwrap_or(&def);
-    let arg_str: &mut String = &mut String::from(raw_arg); // Rust analyzer was screaming at me for not adding &mut
-    let _: bool = is_valid_starter_arg(starter_args, arg_str); // Modifies the value of raw_arg
-
-    // rename _ to result when debugging
-    // println!("{}", result); // Used for debugging
-    // println!("{:?}", arg_str); // Used for debugging
-    return arg_str.to_string();
 }
 
-// starter_arg is mutable, as its modified to 'help' in the event an incorrect parameter is passed.
-pub fn is_valid_starter_arg(starter_args: Vec<String>, starter_arg: &mut String) -> bool {
-    let result: bool = starter_args.contains(&starter_arg);
-    match result {
-        true => return true,
-        false => {
-            *starter_arg = String::from("help"); // The * operator allows for a mutable param to be modified aslong as its still in scope.
-            return false;
+// Lints config.ini against the catalog (see lint::check), exits with 1 when it has errors
+pub fn check(catalog: &Catalog, config_options: &ConfigOptions, json: bool) -> ExitCode {
+    let config = match config::load(config_options, true) {
+        Ok(config) => config.ini,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    let findings = lint::check(catalog, &config);
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    if json {
+        for finding in &findings {
+            println!("{}", json!(finding));
         }
+    } else if findings.is_empty() {
+        println!("No problems found");
+    } else {
+        for finding in &findings {
+            println!("{finding}");
+        }
+        println!("\n{errors} errors, {} warnings", findings.len() - errors);
+    }
+    if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
     }
 }
 
-pub fn get_desired_downloads(
-    browsers: HashMap<String, String>,
-    gaming: HashMap<String, String>,
-    programming: HashMap<String, String>,
-    systems: HashMap<String, String>,
-) -> Vec<String> {
-    let mut desired_downloads: Vec<String> = Vec::new();
-
-    for section in [browsers, gaming, programming, systems] {
-        for program in section {
-            if program.1 == "true" {
-                desired_downloads.push(program.0);
-            }
+// Prints the effective configuration, the files it was read from and where each value was set
+pub fn show_config(config_options: &ConfigOptions, json: bool) -> ExitCode {
+    let config = match config::load(config_options, false) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    let values = config.ini.iter().flat_map(|(section, properties)| {
+        properties
+            .iter()
+            .map(move |(key, value)| (section, key, value))
+    });
+    if json {
+        for (section, key, value) in values {
+            let origin = config.origin(section, key).map(ToString::to_string);
+            println!(
+                "{}",
+                json!({ "section": section, "key": key, "value": value, "origin": origin })
+            );
         }
+        return ExitCode::SUCCESS;
     }
-    desired_downloads
-}
 
-fn load_browser_section(browser_section: &Properties) -> HashMap<String, String> {
-    let browser_names = vec![
-        "Brave",
-        "Chrome",
-        "Chromium",
-        "Edge",
-        "Firefox",
-        "Librefox",
-        "Opera",
-        "OperaGX",
-        "PaleMoon",
-        "Seamonkey-Experimental",
-        "Tor",
-        "Vivaldi-Experimental",
-        "Waterfox",
-    ];
-
-    let mut browsers: HashMap<String, String> = HashMap::new();
-    for browser_name in browser_names {
-        browsers.insert(
-            String::from(browser_name),
-            browser_section
-                .get(browser_name)
-                .unwrap_or("false")
-                .to_string(),
+    match &config.found {
+        Some((path, discovery)) => println!("; {} (found through {discovery})", path.display()),
+        None => println!("; No config.ini found, every setting is at its default"),
+    }
+    if config.files.len() > 1 {
+        let files: Vec<String> = config
+            .files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        println!(
+            "; Read in this order, later files win: {}",
+            files.join(", ")
         );
     }
-    // Used for debugging
-    // for browser in browsers {
-    //     println!("{:?}", browser);
-    // }
-    browsers
+    let mut current_section: Option<&str> = None;
+    for (section, key, value) in values {
+        if section.is_some() && section != current_section {
+            println!("\n[{}]", section.unwrap_or_default());
+            current_section = section;
+        }
+        match config.origin(section, key) {
+            Some(origin) => println!("{key} = {value}  ; {origin}"),
+            None => println!("{key} = {value}"),
+        }
+    }
+    ExitCode::SUCCESS
 }
 
-fn load_gaming_section(gaming_section: &Properties) -> HashMap<String, String> {
-    let app_names: Vec<&str> = vec![
-        "AMDAutoDetect",
-        "BattleNetLauncher",
-        "BluestacksEmulator",
-        "CPUZ",
-        "CurseForge",
-        "EpicGamesLauncher",
-        "GPUZ",
-        "HWiNFO",
-        "HWMonitor",
-        "ItchIoLauncher",
-        "MSIAfterburner",
-        "NexusManager",
-        "NvidiaApp",
-        "OBSStudio",
-        "Parsec",
-        "PingPlotter",
-        "ProcessLasso",
-        "RazerCortex",
-        "Reshade",
-        "RockstarLauncher",
-        "RobloxLauncher",
-        "StreamlabsOBS",
-        "SteamLauncher",
-        "WTFast",
-    ];
-
-    let mut apps: HashMap<String, String> = HashMap::new();
-
-    for app_name in app_names {
-        apps.insert(
-            String::from(app_name),
-            gaming_section.get(app_name).unwrap_or("false").to_string(),
+// Writes a commented config.ini listing every app (see init::render) to path, WSB_CONFIG or next to wsb.exe.
+// An existing file is only replaced with force, from_installed enables the apps winget reports as installed.
+pub fn init(catalog: &Catalog, path: Option<&Path>, force: bool, from_installed: bool) -> ExitCode {
+    let path = &config::init_path(path);
+    if path.exists() && !force {
+        eprintln!(
+            "{} already exists, pass --force to overwrite it",
+            path.display()
         );
+        return ExitCode::from(2);
     }
 
-    // Used for debugging
-    // for app in apps {
-    //     println!("{:?}", app);
-    // }
+    let enabled = if from_installed {
+        match init::installed_apps(catalog) {
+            Ok(enabled) if enabled.is_empty() => {
+                println!(
+                    "None of the installed apps are in the catalog, every app is set to false"
+                );
+                enabled
+            }
+            Ok(enabled) => {
+                println!("Enabled the installed apps: {}", enabled.join(", "));
+                enabled
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::from(2);
+            }
+        }
+    } else {
+        Vec::new()
+    };
 
-    apps
+    match fs::write(path, init::render(catalog, &enabled)) {
+        Ok(()) => {
+            println!("Wrote {}", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Unable to write {}: {e}", path.display());
+            ExitCode::FAILURE
+        }
+    }
 }
 
-fn load_programming_section(programming_section: &Properties) -> HashMap<String, String> {
-    let app_names = vec![
-        "AndroidStudio",
-        "AzureDataStudio",
-        "BurpSuite",
-        "DockerDesktop",
-        "FileZillaClient",
-        "FileZillaServer",
-        "GithubDesktop",
-        "EclipseIDE",
-        "FiddlerClassic",
-        "FiddlerEverywhere",
-        "Kubernetes",
-        "MongoDB",
-        "MySQLWorkbench",
-        "Nmap",
-        "NodeJS",
-        "NotepadPlusPlus",
-        "Npcap",
-        "Ollama",
-        "OracleVirtualBox",
-        "OracleVirtualBoxExtPack",
-        "OWASPZAP",
-        "PostgreSQL",
-        "Postman",
-        "Python2_7_18",
-        "Python3_8_10",
-        "Python3_9_10",
-        "Python3_10_10",
-        "Python3_11_10",
-        "Python3_12_9",
-        "Python3_13_2",
-        "PuTTY",
-        "SublimeText",
-        "UTM",
-        "VisualStudio",
-        "VisualStudioCode",
-        "VSCodium",
-        "WebStorm",
-        "WinGet",
-        "WireShark",
-        "XAMPP",
-    ];
-
-    let mut apps: HashMap<String, String> = HashMap::new();
-
-    for app_name in app_names {
-        apps.insert(
-            String::from(app_name),
-            programming_section
-                .get(app_name)
-                .unwrap_or("false")
-                .to_string(),
-        );
+// Prints the catalog grouped by config.ini section, or one JSON object per app with --json
+pub fn list(catalog: &Catalog, categories: &[Category], json: bool) {
+    let entries: Vec<&CatalogEntry> = Category::ALL
+        .into_iter()
+        .filter(|category| categories.is_empty() || categories.contains(category))
+        .flat_map(|category| catalog.in_category(category))
+        .collect();
+    if json {
+        for entry in entries {
+            println!("{}", entry_json(entry));
+        }
+        return;
     }
 
-    // Used for debugging
-    // for app in apps {
-    //     println!("{:?}", app);
-    // }
-
-    apps
+    let widths = column_widths(&entries);
+    let mut current: Option<Category> = None;
+    for entry in entries {
+        if current != Some(entry.category) {
+            if current.is_some() {
+                println!();
+            }
+            println!("[{}]", entry.category);
+            current = Some(entry.category);
+        }
+        println!("    {}", entry_row(entry, widths));
+    }
 }
 
-fn load_system_section(system_section: &Properties) -> HashMap<String, String> {
-    let app_names = vec![
-        "7Zip",
-        "Audacious",
-        "LibreOffice",
-        "ModernCSV",
-        "NoMacs",
-        "Okular",
-        "Rufus",
-        "VLC",
-        "WinRAR",
-    ];
-
-    let mut apps: HashMap<String, String> = HashMap::new();
-
-    for app_name in app_names {
-        apps.insert(
-            String::from(app_name),
-            system_section.get(app_name).unwrap_or("false").to_string(),
+// Fuzzy searches the catalog by name, config key and alias, exits with 1 when nothing matched
+pub fn search(catalog: &Catalog, query: &str, json: bool) -> ExitCode {
+    let results = search::search(catalog, query);
+    if results.is_empty() {
+        if !json {
+            println!("No apps match \"{query}\", run `wsb list` to see them all");
+        }
+        return ExitCode::FAILURE;
+    }
+    if json {
+        for (entry, quality) in results {
+            let mut object = entry_json(entry);
+            object["match"] = json!(quality);
+            println!("{object}");
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let entries: Vec<&CatalogEntry> = results.iter().map(|(entry, _)| *entry).collect();
+    let widths = column_widths(&entries);
+    for entry in entries {
+        println!(
+            "{:<11} {}",
+            format!("[{}]", entry.category),
+            entry_row(entry, widths)
         );
     }
+    ExitCode::SUCCESS
+}
 
-    // Used for debugging
-    /*for  (key, value) in &system_tools {
-       printlnprintln_string_err("{}: {}", key, value);
-    }*/
+fn entry_json(entry: &CatalogEntry) -> Value {
+    json!({
+        "key": entry.key,
+        "name": entry.name,
+        "category": entry.category.to_string(),
+        "aliases": entry.aliases,
+        "resolver": entry.resolver_kind().map(|kind| kind.to_string()),
+        "needs_helper": entry.needs_helper(),
+    })
+}
 
-    apps
+// Widths of the name and key columns
+fn column_widths(entries: &[&CatalogEntry]) -> (usize, usize) {
+    entries.iter().fold((0, 0), |(name, key), entry| {
+        (
+            name.max(entry.name.chars().count()),
+            key.max(entry.key.chars().count()),
+        )
+    })
 }
 
-fn decode_url(encoded_url: &str) -> String {
-    let owned_decoded_object = decode(encoded_url).expect("UTF-8").to_owned();
-    let decoded_filename = owned_decoded_object.to_string();
-    let filename_result = decoded_filename.clone();
-    return filename_result;
+fn entry_row(entry: &CatalogEntry, (name_width, key_width): (usize, usize)) -> String {
+    let resolver = entry
+        .resolver_kind()
+        .map_or_else(|| String::from("unknown"), |kind| kind.to_string());
+    let helper = if entry.needs_helper() {
+        "  (needs DU_CLI.exe)"
+    } else {
+        ""
+    };
+    format!(
+        "{:<name_width$}  {:<key_width$}  {resolver}{helper}",
+        entry.name, entry.key
+    )
 }
 
-#[allow(unused_variables)]
-#[allow(unused_assignments)] // although cwd is used the fact its overwritten before its initialized requires this flag
-fn download_file(download_link: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let (status, response_bytes, headers) = make_web_request_w_ua(download_link)?; // ? Handles cases where an Err is raised, returning Ok() or the Err()
-
-    match status {
-        StatusCode::OK => {
-            let mut cwd = String::new();
-            match env::current_exe() {
-                Ok(path) => cwd = path.parent().unwrap().display().to_string(),
-                Err(error) => cwd = error.to_string(),
-            };
-            let content_disposition = headers.get("content-disposition");
-            let content_encoding = headers.get("content-encoding");
-            let final_url = headers.get("location");
-
-            if content_disposition.is_none()
-                && final_url.is_none()
-                && !download_link.ends_with(".zip")
-            {
-                println!("Unable to download file");
-                return Err("Unable to download file".into());
-            }
+// Reads the download settings and builds the http client every resolver and download of the run shares,
+// from the [http] settings and the GitHub token
+fn configure(config: &Ini) -> Result<(PipelineConfig, Arc<HttpClient>), String> {
+    let mut http_config =
+        HttpConfig::from_ini(config).map_err(|e| format!("Invalid [http] configuration: {e}"))?;
+    let pipeline_config = PipelineConfig::from_ini(config)
+        .map_err(|e| format!("Invalid [download] configuration: {e}"))?;
+    github::add_token(config, &mut http_config);
+    let http = HttpClient::new(&http_config)
+        .map_err(|e| format!("Unable to create the http client: {e}"))?;
+    Ok((pipeline_config, Arc::new(http)))
+}
 
-            let content_disposition_value = content_disposition
-                .map(|value| {
-                    value.to_str().unwrap_or_else(|_| {
-                        print!("Unable to download.. A");
-                        return "Not found";
-                    })
-                })
-                .unwrap_or_else(|| {
-                    // Content disposition's value was unable to be unwrapped
-                    return "Not found";
-                });
-
-            let content_encoding_value = content_encoding
-                .map(|value| {
-                    value.to_str().unwrap_or_else(|_| {
-                        println!("Unable to download.. B");
-                        return "Not found";
-                    })
-                })
-                .unwrap_or_else(|| {
-                    // Final url's value was unable to be unwrapped
-                    return "Not found";
-                });
-
-            let final_url_value = final_url
-                .map(|value| {
-                    value.to_str().unwrap_or_else(|_| {
-                        println!("Unable to download.. C");
-                        return "Not found";
-                    })
-                })
-                .unwrap_or_else(|| {
-                    // Final url's value was unable to be unwrapped
-                    return "Not found";
-                });
-
-            if content_disposition_value == "Not Found" && final_url_value == "Not Found" {
-                if !download_link.ends_with(".zip") || !download_link.contains(".exe?installer_id=")
-                {
-                    println!("Unable to parse filename, continuing..");
-                    return Err("Unable to parse filename, continuing..".into());
-                }
+// Maps app names typed on the command line to config keys, set to true unless a value is given with Key=value.
+// The options of the app's [category.Key] table still apply, a version given on the command line replaces the table's.
+fn select_apps(
+    catalog: &Catalog,
+    config: &Ini,
+    names: &[String],
+) -> Result<Vec<(String, AppOptions)>, String> {
+    let mut selected: Vec<(String, AppOptions)> = Vec::new();
+    for name in names {
+        let (name, value) = match name.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (name.trim(), "true"),
+        };
+        let entry = catalog.find(name).ok_or_else(|| {
+            let suggestions: Vec<String> = search::search(catalog, name)
+                .into_iter()
+                .take(3)
+                .map(|(entry, _)| entry.key.clone())
+                .collect();
+            match suggestions.is_empty() {
+                true => format!("Unknown app \"{name}\", run `wsb list` to see the available apps"),
+                false => format!(
+                    "Unknown app \"{name}\", did you mean {}?",
+                    suggestions.join(", ")
+                ),
             }
+        })?;
+        if value != "true" && value != "false" && !entry.takes_version() {
+            return Err(format!(
+                "{} doesn't take a version, found \"{value}\"",
+                entry.key
+            ));
+        }
+        if selected.iter().any(|(key, _)| *key == entry.key) {
+            continue;
+        }
+        let table_name = options::table_name(entry);
+        let (mut app_options, errors) =
+            AppOptions::from_config(entry, None, config.section(Some(table_name.as_str())));
+        if let Some((key, e)) = errors.first() {
+            return Err(format!("config.ini [{table_name}] {key}: {e}"));
+        }
+        let named = AppOptions::from_value(entry, value);
+        app_options.enabled = named.enabled;
+        if named.version.is_some() {
+            app_options.version = named.version;
+        }
+        selected.push((entry.key.clone(), app_options));
+    }
+    Ok(selected)
+}
 
-            let mut parsed_url_value = final_url_value;
-            if parsed_url_value.starts_with("/http") {
-                parsed_url_value = &parsed_url_value[1..]; // removes the leading / from the url value
-            }
-            let mut file = Url::parse(parsed_url_value).unwrap();
-            let mut filename: &str = "";
-            let mut decoded_filename: String = String::new();
-            let mut found = false;
-            while !found {
-                if final_url_value.contains(".exe?") {
-                    file.set_query(None);
-                    let mut raw_filename = file.as_str().split("/").last().unwrap();
-                    if raw_filename.contains("?filename=") {
-                        raw_filename = raw_filename.split("?filename=").next().unwrap();
-                    }
-                    //println!("{}", raw_filename);
-                    filename = raw_filename;
-                    found = true;
-                } else if final_url_value.contains("&installer=Battle.net") {
-                    filename = "battle-net-setup.exe";
-                    found = true;
-                } else if content_disposition_value.contains("filename=") && !found {
-                    filename = content_disposition_value
-                        .split("filename=")
-                        .last()
-                        .unwrap_or_else(|| return "Not found");
-
-                    found = true;
-                } else if final_url_value.ends_with(".exe") && !found
-                    || final_url_value.ends_with(".msi") && !found
-                    || final_url_value.ends_with(".zip") && !found
-                {
-                    let encoded_filename = final_url_value
-                        .split("/")
-                        .last()
-                        .unwrap_or_else(|| return "Not found");
-                    decoded_filename = decode_url(encoded_filename);
-                    filename = &decoded_filename;
-                    found = true;
-                } else if download_link.ends_with(".exe") && !found
-                    || download_link.ends_with(".msi") && !found
-                    || download_link.ends_with(".zip") && !found
-                {
-                    filename = download_link
-                        .split("/")
-                        .last()
-                        .unwrap_or_else(|| return "Not found");
-
-                    found = true;
-                } else if download_link.contains(".exe?installer_id=") && !found {
-                    filename = download_link.split("?installer_id=").next().unwrap();
-                    found = true;
-                } else {
-                    filename = "Not found";
-                    if !found {
-                        println!("Unable to parse filename from response, continuing...");
-                        return Err("Not found".into());
-                    }
-                }
-            }
+fn confirm(question: &str) -> bool {
+    print!("{question} [y/N] ");
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
 
-            println!("\nDownloading: {}", filename);
+fn print_download_summary(outcomes: &[(String, DownloadOutcome)]) {
+    let failed = outcomes
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, DownloadOutcome::Failed(_)))
+        .count();
+    println!(
+        "\nSummary: {} of {} apps succeeded",
+        outcomes.len() - failed,
+        outcomes.len()
+    );
+    for (app, outcome) in outcomes {
+        match outcome {
+            DownloadOutcome::Downloaded(filename) => println!("    [ok]     {app} -> {filename}"),
+            DownloadOutcome::ExternalHelper => println!("    [ok]     {app} (DU_CLI.exe)"),
+            DownloadOutcome::Manual(url) => println!("    [manual] {app} -> {url}"),
+            DownloadOutcome::DryRun(url) => println!("    [dry-run] {app} -> {url}"),
+            DownloadOutcome::Failed(reason) => println!("    [failed] {app}: {reason}"),
+        }
+    }
+}
 
-            if filename == "Not found" {
-                return Err("Unable to parse filename from response, continuing...".into());
+// The summary as a single JSON line for --json, after the progress events
+fn print_download_summary_json(outcomes: &[(String, DownloadOutcome)]) {
+    let apps: Vec<Value> = outcomes
+        .iter()
+        .map(|(app, outcome)| match outcome {
+            DownloadOutcome::Downloaded(filename) => {
+                json!({ "app": app, "status": "downloaded", "filename": filename })
+            }
+            DownloadOutcome::ExternalHelper => json!({ "app": app, "status": "downloaded" }),
+            DownloadOutcome::Manual(url) => json!({ "app": app, "status": "manual", "url": url }),
+            DownloadOutcome::DryRun(url) => json!({ "app": app, "status": "resolved", "url": url }),
+            DownloadOutcome::Failed(reason) => {
+                json!({ "app": app, "status": "failed", "reason": reason })
             }
+        })
+        .collect();
+    let failed = outcomes
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, DownloadOutcome::Failed(_)))
+        .count();
+    println!(
+        "{}",
+        json!({
+            "event": "summary",
+            "succeeded": outcomes.len() - failed,
+            "total": outcomes.len(),
+            "apps": apps,
+        })
+    );
+}
+
+// Directory containing wsb.exe, config.ini and the applications folder live next to it
+pub fn get_exe_dir() -> String {
+    env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(|dir| dir.display().to_string()))
+        .unwrap_or_else(|| String::from("."))
+}
 
-            match fs::create_dir(format!("{cwd}/applications")) {
-                Ok(()) => println!("Applications directory created, continuing..."),
-                Err(_) => println!("Applications directory already exists, continuing..."),
-            };
-            let mut file: Result<File, std::io::Error> =
-                File::create(format!("{cwd}/applications/{filename}"));
-
-            match &mut file {
-                Ok(file_obj) => {
-                    // Used for debugging response
-                    // for (key, value) in headers.iter() {
-                    //     println!("{}: {:?}", key, value);
-                    // }
-                    let _ = file_obj.write(&response_bytes);
-                    println!("Finished downloaded for: {filename}");
+// Enabled apps are downloaded once, versioned ones (e.g. Python = 3.12, 3.13) once per requested version.
+// An app selected twice (named on the command line and set in a -c section) only counts the first time, two downloads
+// of it would write the same .part file. Apps with invalid options, or options their resolver can't use, are queued
+// with the reason instead of a resolver so they fail in the summary (`wsb config check` explains them).
+pub fn get_desired_downloads(
+    catalog: &Catalog,
+    sections: Vec<Vec<(String, Result<AppOptions, String>)>>,
+) -> Vec<(String, Result<KeyType, String>)> {
+    let mut desired_downloads: Vec<(String, Result<KeyType, String>)> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for (key, app_options) in sections.into_iter().flatten() {
+        let app_options = match app_options {
+            Ok(app_options) if !app_options.enabled => continue,
+            Ok(app_options) => app_options,
+            Err(e) => {
+                if seen.insert(key.clone()) {
+                    desired_downloads.push((key, Err(e)));
                 }
-                Err(e) => {
-                    return Err(format!(
-                        "Failed to parse response..\nrequest status: {}\nError:\n{}",
-                        status, e
-                    )
-                    .into());
+                continue;
+            }
+        };
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+        match links::map_app_options(catalog, &key, &app_options) {
+            Some(Ok(resolvers)) => {
+                for (label, resolver) in resolvers {
+                    let name = match label {
+                        Some(label) => format!("{key} {label}"),
+                        None => key.clone(),
+                    };
+                    desired_downloads.push((name, Ok(resolver)));
                 }
             }
-            Ok(())
+            Some(Err(e)) => desired_downloads.push((key, Err(e))),
+            None => {
+                let e = ResolveError::Unsupported(String::from("no resolver in this build of wsb"));
+                desired_downloads.push((key, Err(format!("unable to get download link: {e}"))));
+            }
         }
-        _ => {
-            if status.as_u16() == 410 {
-                println!("An error occured while downloading.. continuing..");
-                return Ok(());
+    }
+    desired_downloads
+}
+
+// Reads every catalog app of the given category from its config.ini section and [category.Key] tables, apps missing
+// from both default to false. A Vec is used instead of a HashMap so downloads happen in catalog order.
+// An enabled app with an invalid option is returned as the error, so it fails instead of downloading something else.
+fn load_section(
+    catalog: &Catalog,
+    category: Category,
+    config: &Ini,
+) -> Vec<(String, Result<AppOptions, String>)> {
+    let section = config.section(Some(category.section_name()));
+    let mut apps: Vec<(String, Result<AppOptions, String>)> = Vec::new();
+
+    for entry in catalog.in_category(category) {
+        let table_name = options::table_name(entry);
+        let (app_options, errors) = AppOptions::from_config(
+            entry,
+            section.and_then(|section| section.get(&entry.key)),
+            config.section(Some(table_name.as_str())),
+        );
+        match errors.first().filter(|_| app_options.enabled) {
+            Some((key, e)) => {
+                apps.push((entry.key.clone(), Err(format!("[{table_name}] {key}: {e}"))))
             }
-            println!("Failed to parse response");
-            return Err("Failed to parse response.".into());
+            None => apps.push((entry.key.clone(), Ok(app_options))),
         }
     }
+
+    // Used for debugging
+    // for app in &apps {
+    //     println!("{:?}", app);
+    // }
+
+    apps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> Result<AppOptions, String> {
+        Ok(AppOptions {
+            enabled: true,
+            ..AppOptions::default()
+        })
+    }
+
+    #[test]
+    fn an_app_selected_twice_is_downloaded_once() {
+        let catalog = Catalog::embedded();
+        let named = vec![(String::from("Firefox"), enabled())];
+        let section = vec![
+            (String::from("Firefox"), enabled()),
+            (String::from("Chrome"), enabled()),
+        ];
+
+        let apps: Vec<String> = get_desired_downloads(&catalog, vec![named, section])
+            .into_iter()
+            .map(|(app, _)| app)
+            .collect();
+        assert_eq!(apps, ["Firefox", "Chrome"]);
+    }
+
+    #[test]
+    fn apps_with_invalid_options_are_queued_as_failures() {
+        let catalog = Catalog::embedded();
+        let mut config = Ini::new();
+        config
+            .with_section(Some("programming"))
+            .set("NodeJS", "true");
+        config
+            .with_section(Some("programming.NodeJS"))
+            .set("arch", "sparc");
+        config.with_section(Some("browser")).set("Chrome", "true");
+        config
+            .with_section(Some("browser.Chrome"))
+            .set("locale", "de");
+        let sections = vec![
+            load_section(&catalog, Category::Programming, &config),
+            load_section(&catalog, Category::Browser, &config),
+        ];
+
+        let failures: Vec<(String, String)> = get_desired_downloads(&catalog, sections)
+            .into_iter()
+            .filter_map(|(app, resolver)| resolver.err().map(|e| (app, e)))
+            .collect();
+        assert_eq!(failures.len(), 2, "{failures:?}");
+        assert_eq!(failures[0].0, "NodeJS");
+        assert!(failures[0].1.contains("[programming.NodeJS] arch"));
+        assert_eq!(failures[1].0, "Chrome");
+        assert!(failures[1].1.contains("locale"));
+    }
 }
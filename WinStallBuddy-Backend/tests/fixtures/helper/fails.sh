@@ -0,0 +1,3 @@
+#!/bin/sh
+echo '{"type":"result","ok":false,"error":"The download page didn'"'"'t load"}'
+exit 1
@@ -0,0 +1,3 @@
+#!/bin/sh
+echo '{"type":"progress","bytes":"lots"}'
+echo '{"type":"result","ok":true}'
@@ -0,0 +1,3 @@
+#!/bin/sh
+echo '{"type":"progress","bytes":1,"total":1024}'
+exec sleep 30
@@ -0,0 +1,4 @@
+#!/bin/sh
+# Predates the protocol: ignores stdin, prints plain text and exits with 0
+echo "Downloading $2"
+echo "Done"
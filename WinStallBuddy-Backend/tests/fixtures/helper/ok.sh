@@ -0,0 +1,12 @@
+#!/bin/sh
+# Speaks the protocol: checks the request, reports progress and a log line, then the result
+read -r request
+case "$request" in
+  *'"protocol":1'*'"action":"download"'*) ;;
+  *) exit 2 ;;
+esac
+echo "Starting $2"
+echo '{"type":"log","message":"Waiting for the download button"}'
+echo '{"type":"progress","bytes":512,"total":1024}'
+echo '{"type":"progress","bytes":1024,"total":1024}'
+echo '{"type":"result","ok":true,"path":"/tmp/Vivaldi.7.1.3570.39.x64.exe","version":"7.1.3570.39"}'